@@ -0,0 +1,364 @@
+// formats.rs
+//
+// Config format detection and the YAML and JSON readers.
+//
+// Every format is flattened into the same `ConfigEntry` list that the TOML-style parser in
+// main.rs produces, with nested keys joined into dotted paths and list items numbered from 0
+// (`servers.0`), so schema rules and reports work the same for all of them.
+// The readers cover what configuration files commonly use, not the full specifications:
+// - YAML: block mappings nested by indentation, `- item` scalar lists, `---` document markers
+//   and `#` comment lines. Flow collections (`[a, b]`, `{a: 1}`) are kept as plain values, and
+//   block scalars (`|`, `>`), anchors and multi-document files are not supported.
+// - JSON: the full syntax, nested up to 128 levels; scalar values are kept as written, without
+//   string quotes. The same reader loads saved JSON reports for `--baseline-report`.
+// Standard library only, in keeping with the rest of the tool.
+
+use std::collections::HashMap;
+
+use crate::{clean_value, fatal_error, normalize_whitespace, warn, ConfigEntry};
+
+/// Syntax of a configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// `key = value` lines with `[table]` headers.
+    Toml,
+    /// Indentation-based `key: value` mappings.
+    Yaml,
+    /// A JSON object.
+    Json,
+    /// Plain `key=value` lines, read by the same parser as TOML.
+    Flat,
+}
+
+impl ConfigFormat {
+    /// Name used in `--config-format` and in verbose output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Json => "json",
+            ConfigFormat::Flat => "flat",
+        }
+    }
+}
+
+impl std::str::FromStr for ConfigFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "toml" => Ok(ConfigFormat::Toml),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            "json" => Ok(ConfigFormat::Json),
+            "flat" => Ok(ConfigFormat::Flat),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Returns the key of a YAML `key: value` or `key:` line, if `trimmed` looks like one.
+fn yaml_key(trimmed: &str) -> Option<&str> {
+    let key = match trimmed.find(": ") {
+        Some(index) => &trimmed[..index],
+        None => trimmed.strip_suffix(':')?,
+    };
+    let bare = clean_value(key);
+    Some(key).filter(|_| !bare.is_empty() && !bare.contains(char::is_whitespace) && !bare.contains('='))
+}
+
+/// Guesses the format of a config file from its content. A first line starting with `{` means
+/// JSON. `[table]` headers and `key = "value"` lines suggest TOML. A `key = value` line never
+/// occurs in YAML, so any such line rules YAML out; otherwise `key:` and `- item` lines suggest
+/// YAML. With no evidence either way the file is read as plain `key=value` lines.
+pub fn detect_config_format(content: &str) -> ConfigFormat {
+    let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#')).collect();
+    if lines.first().is_some_and(|line| line.trim_start().starts_with('{')) {
+        return ConfigFormat::Json;
+    }
+    let (mut toml, mut assignments, mut yaml) = (0, 0, 0);
+    for line in lines {
+        let trimmed = line.trim();
+        // In `url: http://host/?a=b` the '=' belongs to a YAML value.
+        let assignment = trimmed.split_once('=').filter(|(key, _)| !key.contains(':'));
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            toml += 1;
+        } else if let Some((_, value)) = assignment {
+            let value = value.trim();
+            if value.starts_with('"') || value.starts_with('\'') {
+                toml += 1;
+            }
+            assignments += 1;
+        } else if trimmed == "---" || trimmed == "-" || trimmed.starts_with("- ") || yaml_key(trimmed).is_some() {
+            yaml += 1;
+        }
+    }
+    if toml > 0 {
+        ConfigFormat::Toml
+    } else if yaml > 0 && assignments == 0 {
+        ConfigFormat::Yaml
+    } else {
+        ConfigFormat::Flat
+    }
+}
+
+/// Parses a YAML config into entries. Mapping keys nest by indentation; each `- item` of a list
+/// becomes a numbered key under its parent. With `tabs_to_spaces`, internal whitespace in
+/// unquoted values is normalized as for TOML.
+pub fn parse_yaml(content: &str, tabs_to_spaces: bool) -> Vec<ConfigEntry> {
+    let mut parents: Vec<(usize, String)> = Vec::new(); // Open mappings: indentation and key
+    let mut list_lengths: HashMap<String, usize> = HashMap::new();
+    let mut entries = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" || trimmed == "..." {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        while parents.last().is_some_and(|(parent_indent, _)| *parent_indent >= indent) {
+            parents.pop();
+        }
+        let mut path: Vec<String> = parents.iter().map(|(_, key)| key.clone()).collect();
+        let value = if let Some(item) = trimmed.strip_prefix('-').filter(|rest| rest.is_empty() || rest.starts_with(' ')) {
+            let length = list_lengths.entry(path.join(".")).or_default();
+            path.push(length.to_string());
+            *length += 1;
+            item
+        } else if let Some(key) = yaml_key(trimmed) {
+            let value = trimmed[key.len() + 1..].trim();
+            if value.is_empty() {
+                parents.push((indent, clean_value(key)));
+                continue;
+            }
+            path.push(clean_value(key));
+            value
+        } else {
+            warn(&format!("Skipping malformed YAML line: {}", trimmed));
+            continue;
+        };
+        let value = if tabs_to_spaces { clean_value(&normalize_whitespace(value)) } else { clean_value(value) };
+        entries.push(ConfigEntry { key: path.join("."), path, value, line: line_index + 1, file: None });
+    }
+    entries
+}
+
+/// The deepest nesting of JSON objects and arrays that is read. The reader recurses once per
+/// level, so deeper input is rejected as malformed instead of overflowing the stack.
+const MAX_JSON_DEPTH: usize = 128;
+
+/// A recursive-descent JSON reader that records each scalar with its key path and line.
+struct JsonReader {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    /// What is being read, for error messages: "config" or "report".
+    kind: &'static str,
+}
+
+impl JsonReader {
+    /// Skips whitespace, counting newlines.
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.get(self.pos) {
+            if !c.is_whitespace() {
+                break;
+            }
+            if c == '\n' {
+                self.line += 1;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Builds an error naming what was expected at the current position and what was found.
+    fn error<T>(&self, expected: &str) -> Result<T, String> {
+        match self.chars.get(self.pos) {
+            Some(c) => Err(format!("Malformed JSON {} at line {}: expected {}, found '{}'.", self.kind, self.line, expected, c)),
+            None => Err(format!("Malformed JSON {}: expected {}, found end of file.", self.kind, expected)),
+        }
+    }
+
+    /// Consumes `c` after any whitespace, or fails.
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) != Some(&c) {
+            return self.error(&format!("'{}'", c));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Reads a string literal, decoding escapes. The opening quote must be next.
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            let Some(&c) = self.chars.get(self.pos) else {
+                return self.error("a closing '\"'");
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\n' => return Err(format!("Malformed JSON {} at line {}: unterminated string.", self.kind, self.line)),
+                '\\' => {
+                    let escape = self.chars.get(self.pos).copied();
+                    self.pos += 1;
+                    match escape {
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        Some('r') => out.push('\r'),
+                        Some('b') => out.push('\u{8}'),
+                        Some('f') => out.push('\u{c}'),
+                        Some('u') => {
+                            let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+                            let code = u32::from_str_radix(&hex, 16).map_err(|_| format!("Malformed JSON {} at line {}: invalid \\u escape.", self.kind, self.line))?;
+                            out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                            self.pos += 4;
+                        }
+                        Some(c @ ('"' | '\\' | '/')) => out.push(c),
+                        _ => return Err(format!("Malformed JSON {} at line {}: invalid escape in string.", self.kind, self.line)),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    /// Reads any value at `path`, appending an entry for each scalar found. `path` has one
+    /// segment per enclosing object or array, so its length is the nesting depth.
+    fn value(&mut self, path: &mut Vec<String>, entries: &mut Vec<ConfigEntry>) -> Result<(), String> {
+        self.skip_whitespace();
+        let line = self.line;
+        if matches!(self.chars.get(self.pos), Some('{' | '[')) && path.len() >= MAX_JSON_DEPTH {
+            return Err(format!("Malformed JSON {} at line {}: nested more than {} levels deep.", self.kind, line, MAX_JSON_DEPTH));
+        }
+        let value = match self.chars.get(self.pos) {
+            Some('{') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.chars.get(self.pos) == Some(&'}') {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                loop {
+                    self.skip_whitespace();
+                    path.push(self.string()?);
+                    self.expect(':')?;
+                    self.value(path, entries)?;
+                    path.pop();
+                    self.skip_whitespace();
+                    match self.chars.get(self.pos) {
+                        Some(',') => self.pos += 1,
+                        Some('}') => {
+                            self.pos += 1;
+                            return Ok(());
+                        }
+                        _ => return self.error("',' or '}'"),
+                    }
+                }
+            }
+            Some('[') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.chars.get(self.pos) == Some(&']') {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                for index in 0.. {
+                    path.push(index.to_string());
+                    self.value(path, entries)?;
+                    path.pop();
+                    self.skip_whitespace();
+                    match self.chars.get(self.pos) {
+                        Some(',') => self.pos += 1,
+                        Some(']') => break,
+                        _ => return self.error("',' or ']'"),
+                    }
+                }
+                self.pos += 1;
+                return Ok(());
+            }
+            Some('"') => self.string()?,
+            Some(c) if *c == '-' || c.is_ascii_alphanumeric() => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| *c == '-' || *c == '+' || *c == '.' || c.is_ascii_alphanumeric()) {
+                    self.pos += 1;
+                }
+                let literal: String = self.chars[start..self.pos].iter().collect();
+                if !matches!(literal.as_str(), "true" | "false" | "null") && literal.parse::<f64>().is_err() {
+                    return Err(format!("Malformed JSON {} at line {}: invalid value '{}'.", self.kind, line, literal));
+                }
+                literal
+            }
+            _ => return self.error("a value"),
+        };
+        entries.push(ConfigEntry { key: path.join("."), path: path.clone(), value, line, file: None });
+        Ok(())
+    }
+
+    /// Reads one value at the top level, which must be followed only by whitespace.
+    fn document(&mut self, entries: &mut Vec<ConfigEntry>) -> Result<(), String> {
+        self.value(&mut Vec::new(), entries)?;
+        self.skip_whitespace();
+        if self.pos < self.chars.len() {
+            self.error("end of file")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Parses a JSON config into entries. The top level must be an object; malformed JSON is fatal.
+pub fn parse_json(content: &str) -> Vec<ConfigEntry> {
+    let mut reader = JsonReader { chars: content.chars().collect(), pos: 0, line: 1, kind: "config" };
+    let mut entries = Vec::new();
+    reader.skip_whitespace();
+    let result = if reader.chars.get(reader.pos) == Some(&'{') {
+        reader.document(&mut entries)
+    } else {
+        reader.error("a top-level '{'")
+    };
+    if let Err(message) = result {
+        fatal_error(&message);
+    }
+    entries
+}
+
+/// Parses a JSON report written with `-f json`, for `--baseline-report`. Each scalar becomes an
+/// entry whose path starts with the finding's index in the top-level array, such as `0.key`, or
+/// with `findings` and the index for a report written with `--score`, such as `findings.0.key`.
+/// Returns a description of the problem if the report is not a JSON array or object.
+pub fn parse_json_report(content: &str) -> Result<Vec<ConfigEntry>, String> {
+    let mut reader = JsonReader { chars: content.chars().collect(), pos: 0, line: 1, kind: "report" };
+    let mut entries = Vec::new();
+    reader.skip_whitespace();
+    if !matches!(reader.chars.get(reader.pos), Some('[' | '{')) {
+        return reader.error("a top-level '[' or '{'");
+    }
+    reader.document(&mut entries)?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `depth` nested arrays around a single value.
+    fn nested_arrays(depth: usize) -> String {
+        "[".repeat(depth) + "1" + &"]".repeat(depth)
+    }
+
+    #[test]
+    fn json_nesting_limit() {
+        let entries = parse_json_report(&nested_arrays(MAX_JSON_DEPTH)).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path.len(), MAX_JSON_DEPTH);
+        let message = parse_json_report(&nested_arrays(MAX_JSON_DEPTH + 1)).unwrap_err();
+        assert_eq!(message, "Malformed JSON report at line 1: nested more than 128 levels deep.");
+    }
+
+    #[test]
+    fn deeply_nested_json_is_an_error_not_a_stack_overflow() {
+        let content = "{\"a\":".repeat(200_000) + "1" + &"}".repeat(200_000);
+        assert!(parse_json_report(&content).unwrap_err().contains("nested more than 128 levels deep"));
+        assert!(parse_json_report(&"[".repeat(200_000)).is_err());
+    }
+}
@@ -17,19 +17,110 @@
 // - CLI-Only Interface: Focuses on the core security validation logic.
 // - One Tool = One Problem: Specifically addresses secure configuration parsing and linting.
 
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::io::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod formats;
+
+use formats::ConfigFormat;
 
 // Constants for output formatting
 const INFO_PREFIX: &str = "[INFO] ";
 const ERROR_PREFIX: &str = "[ERROR] ";
 const WARNING_PREFIX: &str = "[WARNING] ";
 
+// URL schemes checked by the `secure_url` rule, centralized for easy auditing.
+// Each insecure scheme is paired with the secure equivalent suggested in findings.
+const INSECURE_URL_SCHEMES: &[(&str, &str)] = &[
+    ("http://", "https://"),
+    ("ftp://", "ftps:// or sftp://"),
+    ("telnet://", "ssh://"),
+    ("ws://", "wss://"),
+];
+const SECURE_URL_SCHEMES: &[&str] = &["https://", "ftps://", "sftp://", "wss://"];
+
+// Protocol versions known to the `min_tls` rule, oldest first. Each name is the form used in
+// findings; the index orders the versions.
+const TLS_VERSIONS: &[&str] = &["SSLv2", "SSLv3", "TLSv1.0", "TLSv1.1", "TLSv1.2", "TLSv1.3"];
+
+// Cipher name components flagged by the `no_weak_ciphers` rule, centralized for easy auditing.
+// Matched case-insensitively against the '-', '_' and '+' separated parts of each cipher name, so
+// both OpenSSL (`EXP-RC4-MD5`) and IANA (`TLS_RSA_WITH_RC4_128_SHA`) spellings are caught.
+// Each component is paired with the reason given in findings.
+const WEAK_CIPHERS: &[(&str, &str)] = &[
+    ("RC4", "RC4 has practical keystream biases"),
+    ("DES", "DES keys are 56 bits and can be brute-forced"),
+    ("DES40", "DES40 is export-grade DES with a 40-bit key"),
+    ("3DES", "3DES uses 64-bit blocks, which are open to the Sweet32 attack"),
+    ("NULL", "NULL ciphers do not encrypt"),
+    ("eNULL", "eNULL ciphers do not encrypt"),
+    ("aNULL", "aNULL ciphers do not authenticate the server"),
+    ("EXPORT", "export-grade ciphers use deliberately weakened keys"),
+    ("EXP", "export-grade ciphers use deliberately weakened keys"),
+    ("EXPORT40", "export-grade ciphers use deliberately weakened keys"),
+    ("EXPORT56", "export-grade ciphers use deliberately weakened keys"),
+    ("EXP1024", "export-grade ciphers use deliberately weakened keys"),
+];
+
+/// Permission checks that may follow a `path_exists:` or `dir_exists:` rule, comma-separated.
+const PATH_PERMISSIONS: &[&str] = &["readable", "writable", "executable", "private"];
+
+// Key-name fragments that mark a value as a secret for the --builtin-rules permission check,
+// matched case-insensitively against the key's last segment.
+const SECRET_KEY_PATTERNS: &[&str] = &["password", "passwd", "secret", "token", "api_key", "apikey", "private_key", "credential"];
+
+/// A credential format recognized by the `known_secret` rule: `prefix` followed by `min_len` to
+/// `max_len` characters accepted by `charset`. A token with dots, such as a JWT, must have exactly
+/// `parts` non-empty `.`-separated parts.
+struct SecretFormat {
+    name: &'static str,
+    prefix: &'static str,
+    charset: fn(&u8) -> bool,
+    min_len: usize,
+    max_len: usize,
+    parts: usize,
+}
+
+fn is_upper_alphanumeric(byte: &u8) -> bool {
+    byte.is_ascii_uppercase() || byte.is_ascii_digit()
+}
+
+fn is_token_char(byte: &u8) -> bool {
+    byte.is_ascii_alphanumeric() || *byte == b'_' || *byte == b'-'
+}
+
+fn is_jwt_char(byte: &u8) -> bool {
+    is_token_char(byte) || *byte == b'.'
+}
+
+// Credential formats flagged by the `known_secret` rule, centralized for easy auditing. Add a
+// line here to detect another format; the name is reported in findings.
+const KNOWN_SECRETS: &[SecretFormat] = &[
+    SecretFormat { name: "AWS access key ID", prefix: "AKIA", charset: is_upper_alphanumeric, min_len: 16, max_len: 16, parts: 1 },
+    SecretFormat { name: "AWS temporary access key ID", prefix: "ASIA", charset: is_upper_alphanumeric, min_len: 16, max_len: 16, parts: 1 },
+    SecretFormat { name: "GitHub personal access token", prefix: "ghp_", charset: u8::is_ascii_alphanumeric, min_len: 36, max_len: 36, parts: 1 },
+    SecretFormat { name: "GitHub OAuth access token", prefix: "gho_", charset: u8::is_ascii_alphanumeric, min_len: 36, max_len: 36, parts: 1 },
+    SecretFormat { name: "GitHub fine-grained personal access token", prefix: "github_pat_", charset: is_token_char, min_len: 82, max_len: 82, parts: 1 },
+    SecretFormat { name: "Google API key", prefix: "AIza", charset: is_token_char, min_len: 35, max_len: 35, parts: 1 },
+    SecretFormat { name: "Slack bot token", prefix: "xoxb-", charset: is_token_char, min_len: 20, max_len: 255, parts: 1 },
+    SecretFormat { name: "Stripe live secret key", prefix: "sk_live_", charset: u8::is_ascii_alphanumeric, min_len: 24, max_len: 99, parts: 1 },
+    SecretFormat { name: "JSON Web Token (JWT)", prefix: "eyJ", charset: is_jwt_char, min_len: 20, max_len: 8192, parts: 3 },
+];
+
+// Tool identity reported by --version
+const TOOL_NAME: &str = "Safe Config Parser & Linter";
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 // --- Shared Abstractions ---
 // Consistent CLI Argument Parsing: Uses `std::env::args` for CLI flags.
-// Standardized Error Handling & Exit Codes: Exits with 0 on success, non-zero on error.
+// Standardized Error Handling & Exit Codes: Exits with 0 on success, 1 on findings or errors, and
+//   4 on I/O errors (see EXIT_IO_ERROR).
 // Unified Logging/Output Format: Uses INFO, WARNING, ERROR prefixes.
 
 /// Prints an error message to stderr and exits the program with a non-zero status code.
@@ -38,6 +129,16 @@ fn fatal_error(message: &str) {
     process::exit(1);
 }
 
+/// Exit status for I/O failures, such as an input that cannot be read or an output that cannot be
+/// written, so that scripts can tell them apart from exit status 1.
+const EXIT_IO_ERROR: i32 = 4;
+
+/// Prints an I/O error message to stderr and exits with `EXIT_IO_ERROR`.
+fn io_error(message: &str) {
+    eprintln!("{}{}", ERROR_PREFIX, message);
+    process::exit(EXIT_IO_ERROR);
+}
+
 /// Prints a warning message to stderr.
 fn warn(message: &str) {
     eprintln!("{}{}", WARNING_PREFIX, message);
@@ -50,14 +151,216 @@ fn info(message: &str, verbose: bool) {
     }
 }
 
-/// Parses command-line arguments.
-/// Returns (config_file_path, schema_file_path, output_file_path, verbose)
-fn parse_args() -> (String, String, Option<String>, bool) {
-    let args: Vec<String> = env::args().collect();
+/// Set when a `--tee` destination fails. The run still finishes on the other destination,
+/// but then exits non-zero.
+static TEE_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Writer for `--tee`: copies the report to the `-o` file and to stdout. A destination that
+/// fails is dropped with a warning, so the other one still receives the complete report.
+struct TeeWriter {
+    file: Option<fs::File>,
+    file_path: String,
+    stdout: Option<io::Stdout>,
+}
+
+impl TeeWriter {
+    /// Applies `op` to each remaining destination, dropping any that fail. Errors only once
+    /// both destinations are gone.
+    fn each(&mut self, mut op: impl FnMut(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            if let Err(e) = op(file) {
+                warn(&format!("--tee: writing to the report file failed: {}", e));
+                self.file = None;
+                let _ = fs::remove_file(&self.file_path); // Never leave a truncated report behind.
+                TEE_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        if let Some(stdout) = &mut self.stdout {
+            if let Err(e) = op(stdout) {
+                warn(&format!("--tee: writing to stdout failed: {}", e));
+                self.stdout = None;
+                TEE_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        if self.file.is_none() && self.stdout.is_none() {
+            return Err(io::Error::other("both --tee destinations failed"));
+        }
+        Ok(())
+    }
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.each(|writer| writer.write_all(buf)).map(|()| buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.each(|writer| writer.flush())
+    }
+}
+
+/// Report output format selected with --format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    /// Human-readable report (default).
+    Text,
+    /// Only the number of findings, optionally restricted to a minimum severity.
+    Count(Option<Severity>),
+    /// A JSON array of findings with their severity and structured key path.
+    Json,
+}
+
+/// Parses a --format value: `text`, `count`, or `count:<severity>`.
+fn parse_format(value: &str) -> Option<OutputFormat> {
+    match value {
+        "text" => Some(OutputFormat::Text),
+        "count" => Some(OutputFormat::Count(None)),
+        "json" => Some(OutputFormat::Json),
+        _ => {
+            let severity = value.strip_prefix("count:")?.parse::<Severity>().ok()?;
+            Some(OutputFormat::Count(Some(severity)))
+        }
+    }
+}
+
+/// Command-line options controlling the linter.
+struct Options {
+    config_path: String,
+    /// Files merged over the config in order with --layer, later ones winning.
+    layers: Vec<String>,
+    /// Format given with --config-format; `None` detects it from the content.
+    config_format: Option<ConfigFormat>,
+    schema_path: Option<String>,
+    inline_schema: bool,
+    /// Schema profile selected with --profile; `None` applies only unscoped rules.
+    profile: Option<String>,
+    output_path: Option<String>,
+    /// File that receives the critical findings instead of the main report (--output-critical).
+    output_critical: Option<String>,
+    tee: bool,
+    format: OutputFormat,
+    strict: bool,
+    strict_schema: bool,
+    min_severity: Severity,
+    exit_on: Severity,
+    min_confidence: u8,
+    baseline_report: Option<String>,
+    /// Directory that relative `path_exists` and `dir_exists` values are resolved against;
+    /// `None` uses the config file's directory.
+    base_dir: Option<String>,
+    score: bool,
+    /// --fail-above threshold, in hundredths like the score itself.
+    fail_above: Option<u64>,
+    max_value_length: Option<usize>,
+    tabs_to_spaces: bool,
+    builtin_rules: bool,
+    canonicalize: bool,
+    show_source: bool,
+    explain: bool,
+    verbose: bool,
+}
+
+/// Name of the per-user defaults file, read from `$XDG_CONFIG_HOME`, or from `~/.config` when
+/// that is not set.
+const DEFAULTS_FILE_NAME: &str = "safe_config_linter.conf";
+
+/// Short flags and the long options they stand for, so that a command-line flag overrides its
+/// default however it is spelled.
+const SHORT_FLAGS: &[(&str, &str)] = &[("-c", "config"), ("-s", "schema"), ("-o", "output"), ("-f", "format"), ("-v", "verbose")];
+
+/// Groups of long options that set the same thing or rule each other out. An option from a group
+/// given on the command line drops the defaults for every option in the group.
+const OPTION_GROUPS: &[&[&str]] = &[&["exit-on", "min-severity", "fail-above"], &["schema", "inline-schema", "builtin-rules", "canonicalize"]];
+
+/// Returns the path of the defaults file, or `None` if no config directory is known.
+fn defaults_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME").filter(|home| !home.is_empty())?).join(".config"),
+    };
+    Some(dir.join(DEFAULTS_FILE_NAME))
+}
+
+/// Inserts the options of the defaults file between the program name and the command-line
+/// arguments. Each `option = value` line stands for `--option value`; `true` stands for the bare
+/// flag and `false` leaves it out. A flag given on the command line drops every default for the
+/// same option and for the rest of its `OPTION_GROUPS` entry, so the command line wins over the
+/// file and the file over the built-in defaults.
+/// A missing file is not an error, and `--no-defaults` skips the file altogether.
+fn with_defaults(mut args: Vec<String>) -> Vec<String> {
+    if args.iter().any(|arg| arg == "--no-defaults") {
+        args.retain(|arg| arg != "--no-defaults");
+        return args;
+    }
+    let Some(path) = defaults_path() else {
+        return args;
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return args,
+        Err(e) => {
+            io_error(&format!("Failed to read defaults file {:?}: {}", path, e));
+            return args;
+        }
+    };
+    let given: Vec<String> = args
+        .iter()
+        .skip(1)
+        .filter_map(|arg| match arg.strip_prefix("--") {
+            Some(long) => Some(long.to_string()),
+            None => SHORT_FLAGS.iter().find(|(short, _)| short == arg).map(|(_, long)| long.to_string()),
+        })
+        .collect();
+    let overridden: Vec<&str> = OPTION_GROUPS
+        .iter()
+        .filter(|group| group.iter().any(|option| given.iter().any(|flag| flag == option)))
+        .flat_map(|group| group.iter().copied())
+        .collect();
+    let mut defaults = Vec::new();
+    // The defaults file uses the same flat `key = value` syntax as the configs this tool lints.
+    for ConfigEntry { key, value, .. } in parse_config(&content, false) {
+        if given.contains(&key) || overridden.contains(&key.as_str()) || value == "false" {
+            continue;
+        }
+        defaults.push(format!("--{}", key));
+        if value != "true" {
+            defaults.push(value);
+        }
+    }
+    args.splice(1..1, defaults);
+    args
+}
+
+/// Parses command-line arguments into an `Options` value.
+fn parse_args() -> Options {
+    let args = with_defaults(env::args().collect());
 
     let mut config_file_path: Option<String> = None;
+    let mut layers: Vec<String> = Vec::new();
+    let mut config_format: Option<ConfigFormat> = None;
     let mut schema_file_path: Option<String> = None;
     let mut output_file_path: Option<String> = None;
+    let mut tee = false;
+    let mut output_critical: Option<String> = None;
+    let mut format = OutputFormat::Text;
+    let mut inline_schema = false;
+    let mut profile: Option<String> = None;
+    let mut strict = false;
+    let mut strict_schema = false;
+    let mut min_severity = Severity::Info;
+    let mut exit_on = Severity::Warning;
+    let mut exit_on_given = false;
+    let mut min_confidence: u8 = 0;
+    let mut baseline_report: Option<String> = None;
+    let mut base_dir: Option<String> = None;
+    let mut score = false;
+    let mut fail_above: Option<u64> = None;
+    let mut max_value_length: Option<usize> = None;
+    let mut tabs_to_spaces = false;
+    let mut builtin_rules = false;
+    let mut canonicalize = false;
+    let mut show_source = false;
+    let mut explain = false;
     let mut verbose = false;
 
     // Skip the first argument which is the program name
@@ -72,6 +375,31 @@ fn parse_args() -> (String, String, Option<String>, bool) {
                     fatal_error("Missing value for --config");
                 }
             }
+            "--layer" => {
+                i += 1;
+                if i < args.len() {
+                    layers.push(args[i].clone());
+                } else {
+                    fatal_error("Missing value for --layer");
+                }
+            }
+            "--config-format" => {
+                i += 1;
+                if i < args.len() {
+                    config_format = match args[i].as_str() {
+                        "auto" => None,
+                        value => Some(value.parse::<ConfigFormat>().unwrap_or_else(|_| {
+                            fatal_error(&format!(
+                                "Invalid value for --config-format: '{}'. Choose 'auto', 'toml', 'yaml', 'json' or 'flat'.",
+                                value
+                            ));
+                            ConfigFormat::Flat
+                        })),
+                    };
+                } else {
+                    fatal_error("Missing value for --config-format");
+                }
+            }
             "-s" | "--schema" => {
                 i += 1;
                 if i < args.len() {
@@ -88,6 +416,142 @@ fn parse_args() -> (String, String, Option<String>, bool) {
                     fatal_error("Missing value for --output");
                 }
             }
+            "-f" | "--format" => {
+                i += 1;
+                if i < args.len() {
+                    format = parse_format(&args[i]).unwrap_or_else(|| {
+                        fatal_error(&format!(
+                            "Invalid value for --format: '{}'. Choose 'text', 'json', 'count', or 'count:<info|warning|critical>'.",
+                            args[i]
+                        ));
+                        OutputFormat::Text
+                    });
+                } else {
+                    fatal_error("Missing value for --format");
+                }
+            }
+            "--inline-schema" => {
+                inline_schema = true;
+            }
+            "--profile" => {
+                i += 1;
+                if i < args.len() {
+                    profile = Some(args[i].clone());
+                } else {
+                    fatal_error("Missing value for --profile");
+                }
+            }
+            "--strict" => {
+                strict = true;
+            }
+            "--strict-schema" => {
+                strict_schema = true;
+            }
+            "--min-severity" | "--exit-on" => {
+                let flag = args[i].clone();
+                i += 1;
+                if i < args.len() {
+                    let severity = args[i].parse::<Severity>().unwrap_or_else(|_| {
+                        fatal_error(&format!(
+                            "Invalid value for {}: '{}'. Choose 'info', 'warning', or 'critical'.",
+                            flag, args[i]
+                        ));
+                        Severity::Info
+                    });
+                    if flag == "--exit-on" {
+                        exit_on = severity;
+                        exit_on_given = true;
+                    } else {
+                        min_severity = severity;
+                    }
+                } else {
+                    fatal_error(&format!("Missing value for {}", flag));
+                }
+            }
+            "--min-confidence" => {
+                i += 1;
+                if i < args.len() {
+                    min_confidence = match args[i].parse::<u8>() {
+                        Ok(n) if n <= 100 => n,
+                        _ => {
+                            fatal_error("Invalid value for --min-confidence. Must be an integer from 0 to 100.");
+                            0
+                        }
+                    };
+                } else {
+                    fatal_error("Missing value for --min-confidence");
+                }
+            }
+            "--max-value-length" => {
+                i += 1;
+                if i < args.len() {
+                    max_value_length = Some(args[i].parse::<usize>().unwrap_or_else(|_| {
+                        fatal_error("Invalid value for --max-value-length. Must be a non-negative integer.");
+                        0
+                    }));
+                } else {
+                    fatal_error("Missing value for --max-value-length");
+                }
+            }
+            "--tabs-to-spaces" => {
+                tabs_to_spaces = true;
+            }
+            "--builtin-rules" => {
+                builtin_rules = true;
+            }
+            "--canonicalize" => {
+                canonicalize = true;
+            }
+            "--show-source" => {
+                show_source = true;
+            }
+            "--baseline-report" => {
+                i += 1;
+                if i < args.len() {
+                    baseline_report = Some(args[i].clone());
+                } else {
+                    fatal_error("Missing value for --baseline-report");
+                }
+            }
+            "--base-dir" => {
+                i += 1;
+                if i < args.len() {
+                    base_dir = Some(args[i].clone());
+                } else {
+                    fatal_error("Missing value for --base-dir");
+                }
+            }
+            "--score" => {
+                score = true;
+            }
+            "--fail-above" => {
+                i += 1;
+                if i < args.len() {
+                    fail_above = match args[i].parse::<f64>() {
+                        Ok(n) if n.is_finite() && n >= 0.0 => Some((n * 100.0).round() as u64),
+                        _ => {
+                            fatal_error("Invalid value for --fail-above. Must be a non-negative number.");
+                            None
+                        }
+                    };
+                } else {
+                    fatal_error("Missing value for --fail-above");
+                }
+            }
+            "--explain" => {
+                explain = true;
+            }
+            "--output-critical" => {
+                i += 1;
+                if i < args.len() {
+                    output_critical = Some(args[i].clone());
+                } else {
+                    fatal_error("Missing value for --output-critical");
+                }
+            }
+            "--tee" => {
+                tee = true;
+            }
             "-v" | "--verbose" => {
                 verbose = true;
             }
@@ -95,6 +559,10 @@ fn parse_args() -> (String, String, Option<String>, bool) {
                 print_help();
                 process::exit(0);
             }
+            "--version" => {
+                print_version();
+                process::exit(0);
+            }
             _ => {
                 fatal_error(&format!("Unknown argument: {}", args[i]));
             }
@@ -109,13 +577,88 @@ fn parse_args() -> (String, String, Option<String>, bool) {
         // for `unwrap_or_else` if the closure doesn't diverge. We add a dummy value.
         String::new()
     });
-    let schema_path = schema_file_path.unwrap_or_else(|| {
-        print_help();
-        fatal_error("Schema file path is required.");
-        String::new()
-    });
+    if tee && output_file_path.is_none() {
+        fatal_error("--tee requires an output file given with -o.");
+    }
+    if let Some(path) = &output_critical {
+        if canonicalize {
+            fatal_error("--output-critical cannot be combined with --canonicalize, which does not report findings.");
+        }
+        if output_file_path.as_ref() == Some(path) {
+            fatal_error("--output-critical must name a different file than -o.");
+        }
+    }
+    if show_source && (canonicalize || format != OutputFormat::Text) {
+        fatal_error("--show-source only applies to the text report.");
+    }
+    if explain && (canonicalize || matches!(format, OutputFormat::Count(_))) {
+        fatal_error("--explain only applies to the text and JSON reports.");
+    }
+    if baseline_report.is_some() && canonicalize {
+        fatal_error("--baseline-report cannot be combined with --canonicalize, which does not report findings.");
+    }
+    if score && (canonicalize || matches!(format, OutputFormat::Count(_))) {
+        fatal_error("--score only applies to the text and JSON reports.");
+    }
+    if fail_above.is_some() && !score {
+        fatal_error("--fail-above requires --score.");
+    }
+    if fail_above.is_some() && exit_on_given {
+        fatal_error("--fail-above replaces --exit-on as the exit status check; give only one of them.");
+    }
+    if base_dir.is_some() && schema_file_path.is_none() && !inline_schema {
+        fatal_error("--base-dir resolves paths for schema rules given with -s or --inline-schema.");
+    }
+    if profile.is_some() && schema_file_path.is_none() && !inline_schema {
+        fatal_error("--profile selects rules from a schema given with -s or --inline-schema.");
+    }
+    if canonicalize {
+        if schema_file_path.is_some() || inline_schema || builtin_rules || strict_schema || format != OutputFormat::Text {
+            fatal_error("--canonicalize cannot be combined with -s, --inline-schema, --builtin-rules, --strict-schema or -f.");
+        }
+    } else if schema_file_path.is_none() && !inline_schema {
+        if !builtin_rules {
+            print_help();
+            fatal_error("Schema file path is required (or use --inline-schema or --builtin-rules).");
+        }
+        if strict_schema {
+            fatal_error("--strict-schema requires a schema given with -s or --inline-schema.");
+        }
+    }
+
+    Options {
+        config_path,
+        layers,
+        config_format,
+        schema_path: schema_file_path,
+        inline_schema,
+        profile,
+        output_path: output_file_path,
+        output_critical,
+        tee,
+        format,
+        strict,
+        strict_schema,
+        min_severity,
+        exit_on,
+        min_confidence,
+        baseline_report,
+        base_dir,
+        score,
+        fail_above,
+        max_value_length,
+        tabs_to_spaces,
+        builtin_rules,
+        canonicalize,
+        show_source,
+        explain,
+        verbose,
+    }
+}
 
-    (config_path, schema_path, output_file_path, verbose)
+/// Prints the tool name and version.
+fn print_version() {
+    println!("{} {}", TOOL_NAME, TOOL_VERSION);
 }
 
 /// Prints the help message for the tool.
@@ -123,14 +666,70 @@ fn print_help() {
     println!(
         "Safe Config Parser & Linter
 
-Usage: safe_config_linter -c <CONFIG_FILE> -s <SCHEMA_FILE> [-o <OUTPUT_FILE>] [-v | --verbose] [--help]
+Usage: safe_config_linter -c <CONFIG_FILE> [--layer <FILE>]... [--config-format <FORMAT>] (-s <SCHEMA_FILE> | --inline-schema | --builtin-rules | --canonicalize) [--profile <NAME>] [-o <OUTPUT_FILE> [--tee]] [--output-critical <FILE>] [-f <FORMAT>] [--strict] [--strict-schema] [--min-severity <SEVERITY>] [--exit-on <SEVERITY>] [--min-confidence <N>] [--baseline-report <FILE>] [--base-dir <DIR>] [--score [--fail-above <N>]] [--max-value-length <N>] [--tabs-to-spaces] [--show-source] [--explain] [-v | --verbose] [--no-defaults] [--help] [--version]
 
 Arguments:
   -c, --config <FILE>    Path to the configuration file to parse and lint.
+  --layer <FILE>         (Optional, repeatable) Merge FILE over the config, as an environment override layer.
+                         Layers apply in order, and a key set by a later layer replaces every earlier value
+                         of it. The merged config is linted. With -v, each value's layer is reported.
+  --config-format <FMT>  (Optional) Config syntax: 'auto' (default) detects it from the content; 'toml', 'yaml',
+                         'json' or 'flat' (plain key=value lines) override the detection.
   -s, --schema <FILE>    Path to the security schema file for validation.
+  --inline-schema        Read schema rules from '#@rule key = rule' annotations in the config file itself.
+                         May be combined with -s, in which case both sets of rules apply.
+  --profile <NAME>       (Optional) Also apply the schema rules under a '[profile:NAME]' header, overriding
+                         unscoped rules for the same keys. Without it, only unscoped rules apply.
+  --builtin-rules        Apply built-in best-practice checks chosen by key name, without a schema: default
+                         passwords, insecure URL schemes, debug flags set to true, and secrets in world-readable
+                         files. May be combined with -s and --inline-schema.
+  --canonicalize         Instead of linting, print the config in a canonical form for diffing: includes
+                         expanded, comments dropped, one 'dotted.key = value' line per entry, sorted by key.
   -o, --output <FILE>    (Optional) Path to save the linting report. If not provided, output is printed to stdout.
+  --tee                  (Optional) With -o, also print the report to stdout. If one destination fails, the
+                         other still receives the full report, and the tool exits non-zero.
+  --output-critical <FILE> (Optional) Write critical findings to FILE, as a separate report in the same format, and
+                         only the other findings to -o (or stdout). --score is printed in the main report only.
+  -f, --format <FORMAT>  (Optional) Report format: 'text' (default), 'json' for a JSON array of findings with
+                         their key paths, 'count' to print only the number of findings, or
+                         'count:<info|warning|critical>' to count findings at or above that severity.
+  --strict               (Optional) Escalate informational findings (e.g. an empty config) to warnings.
+  --strict-schema        (Optional) Closed-world policy: report config keys without a schema rule as critical.
+  --min-severity <SEV>   (Optional) Only report findings at or above SEV: info (default), warning, critical.
+  --exit-on <SEV>        (Optional) Minimum severity that causes a non-zero exit (default: warning).
+                         Applies to all findings, including those hidden by --min-severity.
+  --min-confidence <N>   (Optional) Drop findings whose confidence (0-100) is below N, for the report and the exit
+                         code. Exact checks have confidence 100; --builtin-rules heuristics score lower.
+  --baseline-report <FILE> (Optional) Only report findings that are not in FILE, a report saved earlier with
+                         -f json. Findings are matched by key and rule, so reworded messages still match. The
+                         exit code only reflects the new findings.
+  --base-dir <DIR>       (Optional) Directory against which relative paths checked by 'path_exists' and
+                         'dir_exists' rules are resolved (default: the directory of the config file).
+  --score                (Optional) Print a risk score after the report: the sum over all findings of 10 per
+                         critical, 3 per warning and 1 per info finding, each times its confidence / 100. In
+                         JSON, the report becomes {{\"risk_score\": N, \"findings\": [...]}}.
+  --fail-above <N>       (Optional) With --score, exit non-zero only if the score is above N, instead of by
+                         --exit-on severity.
+  --max-value-length <N> (Optional) Warn on any config value longer than N characters. Keys with their own
+                         'max_value_length:<N|none>' schema rule use that instead.
+  --tabs-to-spaces       (Optional) Collapse runs of tabs and spaces inside unquoted config values to a single
+                         space before validation. Quoted values are left untouched.
+  --show-source          (Optional) Print the config line each finding refers to, with one line of context on
+                         either side, beneath the finding. Findings without a source line (e.g. missing keys)
+                         are printed without one. Text format only.
+  --explain              (Optional) Follow each finding with why it matters and how to fix it. In JSON, adds an
+                         \"explanation\" field. Not available with -f count.
   -v, --verbose          (Optional) Enable verbose output.
-  --help                 Display this help message."
+  --no-defaults          Ignore the defaults file described below.
+  --help                 Display this help message.
+  --version              Display the tool version.
+
+Defaults file: default options may be set in $XDG_CONFIG_HOME/safe_config_linter.conf
+(or ~/.config/safe_config_linter.conf), one 'option = value' per line (e.g. 'verbose = true').
+Options given on the command line override them, and a missing file is ignored.
+
+Exit status: 0 if no finding reaches --exit-on (or the score is not above --fail-above), 1 if one does or on invalid arguments or a malformed file,
+4 if a config, schema or wordlist cannot be read or the report cannot be written."
     );
 }
 
@@ -138,11 +737,68 @@ Arguments:
 fn read_file_content(file_path: &str) -> String {
     fs::read_to_string(file_path)
         .unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to read file {}: {}", file_path, e));
+            io_error(&format!("Failed to read file {}: {}", file_path, e));
             String::new() // Unreachable, but satisfies type checker
         })
 }
 
+/// Where a line of expanded input came from: `file` is `None` for the file named on the
+/// command line and the include path for lines inlined by an `include` directive.
+#[derive(Debug, Clone)]
+struct LineOrigin {
+    file: Option<String>,
+    line: usize,
+}
+
+/// Returns the file an `include <path>` directive line refers to, if `trimmed` is one.
+/// The path may be quoted; lines containing '=' are ordinary `include = ...` keys.
+fn include_target(trimmed: &str) -> Option<String> {
+    let target = trimmed.strip_prefix("include ").filter(|_| !trimmed.contains('='))?;
+    Some(clean_value(target)).filter(|t| !t.is_empty())
+}
+
+/// Reads a config or schema file, recursively inlining `include <path>` directives in place.
+/// Relative include paths are resolved against the including file's directory. Returns the
+/// expanded text and, for each of its lines, where that line came from. An include cycle is fatal.
+fn read_with_includes(file_path: &str) -> (String, Vec<LineOrigin>) {
+    let mut lines = Vec::new();
+    let mut origins = Vec::new();
+    expand_includes(Path::new(file_path), None, &mut Vec::new(), &mut lines, &mut origins);
+    (lines.join("\n"), origins)
+}
+
+/// Appends the lines of `path` to `lines`, expanding includes. `stack` holds the canonical
+/// paths of the files currently being expanded, for cycle detection.
+fn expand_includes(
+    path: &Path,
+    display: Option<String>,
+    stack: &mut Vec<PathBuf>,
+    lines: &mut Vec<String>,
+    origins: &mut Vec<LineOrigin>,
+) {
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if let Some(start) = stack.iter().position(|p| *p == canonical) {
+        let cycle: Vec<String> = stack[start..].iter().chain([&canonical]).map(|p| p.display().to_string()).collect();
+        fatal_error(&format!("Include cycle detected: {}", cycle.join(" -> ")));
+    }
+    stack.push(canonical);
+
+    let content = read_file_content(&path.to_string_lossy());
+    for (index, line) in content.lines().enumerate() {
+        match include_target(line.trim()) {
+            Some(target) => {
+                let target_path = path.parent().unwrap_or(Path::new("")).join(&target);
+                expand_includes(&target_path, Some(target_path.display().to_string()), stack, lines, origins);
+            }
+            None => {
+                lines.push(line.to_string());
+                origins.push(LineOrigin { file: display.clone(), line: index + 1 });
+            }
+        }
+    }
+    stack.pop();
+}
+
 /// Cleans a value by removing surrounding quotes if present.
 fn clean_value(value: &str) -> String {
     let trimmed = value.trim();
@@ -154,187 +810,1937 @@ fn clean_value(value: &str) -> String {
     }
 }
 
-/// Parses a configuration file (simplified for demonstration, assumes key-value pairs).
-/// In a real tool, this would handle TOML, YAML, JSON. For now, it's a basic parser.
-fn parse_config(content: &str) -> Vec<(String, String)> {
-    content
-        .lines()
-        .filter_map(|line| {
-            let trimmed = line.trim();
-            if trimmed.starts_with('#') || trimmed.is_empty() {
-                None // Skip comments and empty lines
-            } else {
-                let parts: Vec<&str> = trimmed.splitn(2, '=').collect();
-                if parts.len() == 2 {
-                    Some((parts[0].trim().to_string(), clean_value(parts[1])))
-                } else {
-                    warn(&format!("Skipping malformed config line: {}", trimmed));
-                    None
-                }
-            }
-        })
-        .collect()
+/// Collapses each run of whitespace (tabs included) inside an unquoted value to a single space,
+/// so `TLS_A\t\tTLS_B` compares equal to `TLS_A TLS_B`. Quoted values are returned unchanged.
+fn normalize_whitespace(value: &str) -> String {
+    let trimmed = value.trim();
+    if (trimmed.starts_with('\"') && trimmed.ends_with('\"')) ||
+       (trimmed.starts_with('\'') && trimmed.ends_with('\'')) {
+        trimmed.to_string()
+    } else {
+        trimmed.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
 }
 
-/// Parses a schema file (simplified for demonstration, assumes key-value pairs representing rules).
-fn parse_schema(content: &str) -> Vec<(String, String)> {
-    // Similar to parse_config, but specific to schema rules.
-    // For this basic demo, assume schema lines are "key=expected_value" or "key=rule_type"
-    parse_config(content)
+/// A parsed configuration entry. `path` holds the key's segments, including any enclosing
+/// `[table]` headers; `key` is the dotted form used to match schema rules and shown in reports.
+/// `line` is the 1-based line number the entry was read from, within `file` if it came from an
+/// included file (see `locate_entries`).
+#[derive(Debug, Clone)]
+struct ConfigEntry {
+    key: String,
+    path: Vec<String>,
+    value: String,
+    line: usize,
+    file: Option<String>,
 }
 
-/// Validates the configuration against the schema.
-/// This is a highly simplified validation for demonstration.
-/// A real linter would have complex rule engines.
-fn validate_config(
-    config: &[(String, String)],
-    schema: &[(String, String)],
-    _verbose: bool,
-) -> Vec<String> {
-    let mut warnings = Vec::new();
+impl ConfigEntry {
+    /// Human-readable location, e.g. `line 4` or `rules/allow.toml line 2`.
+    fn location(&self) -> String {
+        match &self.file {
+            Some(file) => format!("{} line {}", file, self.line),
+            None => format!("line {}", self.line),
+        }
+    }
+}
 
-    // Collect schema rules into a more accessible map
-    let schema_map: std::collections::HashMap<String, String> =
-        schema.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-
-    // Check for keys in config that are not in schema (potential unknown/unmanaged settings)
-    for (config_key, _) in config {
-        if !schema_map.contains_key(config_key) {
-            warnings.push(format!(
-                "Config key '{}' not found in schema. Consider defining its security posture.",
-                config_key
-            ));
+/// Maps entries parsed from expanded text back to the file and line they came from.
+fn locate_entries(entries: &mut [ConfigEntry], origins: &[LineOrigin]) {
+    for entry in entries {
+        if let Some(origin) = origins.get(entry.line - 1) {
+            entry.line = origin.line;
+            entry.file = origin.file.clone();
         }
     }
+}
 
-    // Basic validation: iterate through schema rules and apply them to config
-    for (schema_key, schema_rule) in schema {
-        match config.iter().find(|(k, _)| k == schema_key) {
-            Some((_, config_value)) => {
-                match schema_rule.as_str() {
-                    "https://" => { // database_url rule
-                        if !config_value.starts_with("https://") {
-                            warnings.push(format!(
-                                "Insecure setting: '{}' should use HTTPS (starts with 'https://').",
-                                schema_key
-                            ));
-                        }
-                    }
-                    "false" => { // debug_mode rule
-                        if config_value == "true" {
-                            warnings.push(format!(
-                                "Insecure setting: '{}' should be 'false' in production.",
-                                schema_key
-                            ));
-                        }
-                    }
-                    "no_default_password" => { // admin_password rule
-                        if config_value == "password123" {
-                            warnings.push(format!(
-                                "Critical: '{}' uses default password 'password123'. Change immediately!",
-                                schema_key
-                            ));
-                        }
-                    }
-                    "INFO" => { // log_level rule
-                        if config_value != "INFO" {
-                            warnings.push(format!(
-                                "Logging level: '{}' is not 'INFO'. Consider 'INFO' for standard operation.",
-                                schema_key
-                            ));
-                        }
-                    }
-                    "min_length_8" => { // api_key_length rule
-                        if let Ok(length) = config_value.parse::<usize>() {
-                            if length < 8 {
-                                warnings.push(format!(
-                                    "Weak setting: '{}' has length {}. Recommended minimum: 8.",
-                                    schema_key, length
-                                ));
-                            }
-                        } else {
-                            warnings.push(format!(
-                                "Schema rule for '{}' expects an integer length, but config value '{}' is not a valid integer.",
-                                schema_key, config_value
-                            ));
-                        }
-                    }
-                    _ => {
-                        // Generic check for exact value match if no specific rule type is recognized
-                        if config_value != schema_rule {
-                            warnings.push(format!(
-                                "Config key '{}' value '{}' does not match schema rule '{}'.",
-                                schema_key, config_value, schema_rule
-                            ));
-                        }
-                    }
-                }
+/// Reads and parses one config file in the --config-format syntax, or the one detected from its
+/// content. Returns the entries and the expanded text, for --inline-schema.
+fn load_config(config_path: &str, options: &Options) -> (Vec<ConfigEntry>, String) {
+    info(&format!("Loading configuration from: {}", config_path), options.verbose);
+    let (config_content, config_origins) = read_with_includes(config_path);
+    let config_format = options.config_format.unwrap_or_else(|| formats::detect_config_format(&config_content));
+    let how = if options.config_format.is_some() { "set by --config-format" } else { "detected from content" };
+    info(&format!("Config format: {} ({}).", config_format.name(), how), options.verbose);
+    let mut config = match config_format {
+        ConfigFormat::Yaml => formats::parse_yaml(&config_content, options.tabs_to_spaces),
+        ConfigFormat::Json => formats::parse_json(&config_content),
+        ConfigFormat::Toml | ConfigFormat::Flat => parse_config(&config_content, options.tabs_to_spaces),
+    };
+    locate_entries(&mut config, &config_origins);
+    (config, config_content)
+}
+
+/// Merges a --layer over the config: every occurrence of a key the layer sets is replaced by the
+/// layer's entries for it, at the position of the first one, so ordering rules still see the
+/// original order. Keys new in the layer are appended. The locations of replaced entries are
+/// recorded in `overridden`, for the verbose trace.
+fn merge_layer(config: Vec<ConfigEntry>, layer: Vec<ConfigEntry>, overridden: &mut HashMap<String, Vec<String>>) -> Vec<ConfigEntry> {
+    let layer_keys: HashSet<String> = layer.iter().map(|e| e.key.clone()).collect();
+    let mut placed = HashSet::new();
+    let mut merged = Vec::with_capacity(config.len() + layer.len());
+    for entry in config {
+        if !layer_keys.contains(&entry.key) {
+            merged.push(entry);
+            continue;
+        }
+        overridden.entry(entry.key.clone()).or_default().push(entry.location());
+        if placed.insert(entry.key.clone()) {
+            merged.extend(layer.iter().filter(|e| e.key == entry.key).cloned());
+        }
+    }
+    merged.extend(layer.into_iter().filter(|e| !placed.contains(&e.key)));
+    merged
+}
+
+/// Splits a dotted key such as `server."listen address".port` into its segments and returns them
+/// with the text after the key. A segment may be quoted with `"` or `'` to hold spaces, '.' or
+/// '=', and its quotes are removed. A bare segment ends at the next '.' or '=' and is trimmed.
+/// Returns `None` if a quote is not closed.
+fn parse_key_path(text: &str) -> Option<(Vec<String>, &str)> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start();
+        let segment;
+        match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let close = rest[1..].find(quote)? + 1;
+                segment = rest[1..close].to_string();
+                rest = rest[close + 1..].trim_start();
             }
-            None => {
-                // Key from schema is missing in config
-                warnings.push(format!(
-                    "Missing configuration key: '{}' as defined in schema.",
-                    schema_key
-                ));
+            _ => {
+                let end = rest.find(['.', '=']).unwrap_or(rest.len());
+                segment = rest[..end].trim().to_string();
+                rest = &rest[end..];
             }
         }
+        segments.push(segment);
+        match rest.strip_prefix('.') {
+            Some(after_dot) => rest = after_dot,
+            None => return Some((segments, rest)),
+        }
     }
-
-    warnings
 }
 
-/// Writes the report to the specified output file or stdout.
-fn write_report(output_path: Option<&str>, warnings: &[String], verbose: bool) {
-    let mut writer: Box<dyn Write> = match output_path {
-        Some(path) => Box::new(fs::File::create(path).unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to create output file {}: {}", path, e));
-            process::exit(1); // Diverging function, never returns
-        })),
-        None => Box::new(io::stdout()),
-    };
-
-    if warnings.is_empty() {
-        writeln!(writer, "No security warnings or misconfigurations found.").unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to write to report: {}", e));
-        });
-        info("Configuration is compliant with the provided schema.", verbose);
-    } else {
-        writeln!(writer, "Security Linter Report:").unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to write to report: {}", e));
-        });
-        for warning in warnings {
-            writeln!(writer, "- {}", warning).unwrap_or_else(|e| {
-                fatal_error(&format!("Failed to write to report: {}", e));
-            });
+/// Parses a configuration file (simplified for demonstration, assumes key-value pairs).
+/// `include` directives must already have been expanded (see `read_with_includes`).
+/// TOML-style `[table.subtable]` headers and dotted keys nest the following keys, so
+/// `[database.replica]` followed by `password = x` yields the path `database.replica.password`.
+/// Key segments may be quoted, as in `"key with spaces" = x` (see `parse_key_path`).
+/// With `tabs_to_spaces`, internal whitespace in unquoted values is normalized (see `normalize_whitespace`).
+/// In a real tool, this would handle TOML, YAML, JSON in full. For now, it's a basic parser.
+fn parse_config(content: &str, tabs_to_spaces: bool) -> Vec<ConfigEntry> {
+    let mut table: Vec<String> = Vec::new(); // Path of the current [table]
+    let mut entries = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') || trimmed.is_empty() {
+            continue; // Skip comments and empty lines
+        }
+        if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            match parse_key_path(header) {
+                Some((path, "")) => table = path,
+                _ => warn(&format!("Skipping malformed table header: {}", trimmed)),
+            }
+            continue;
+        }
+        let key_and_value = parse_key_path(trimmed).and_then(|(key, rest)| Some((key, rest.strip_prefix('=')?)));
+        match key_and_value {
+            Some((key, value)) => {
+                let mut path = table.clone();
+                path.extend(key);
+                let value = if tabs_to_spaces { clean_value(&normalize_whitespace(value)) } else { clean_value(value) };
+                entries.push(ConfigEntry { key: path.join("."), path, value, line: line_index + 1, file: None });
+            }
+            None => warn(&format!("Skipping malformed config line: {}", trimmed)),
         }
-        info(&format!("Found {} potential security issues.", warnings.len()), verbose);
     }
+    entries
 }
 
-/// The main entry point for the application.
-/// Parses arguments, reads config and schema, validates the config, and reports findings.
-fn main() {
-    let (config_path, schema_path, output_path, verbose) = parse_args();
+/// Reports keys that differ only by case, such as `Debug_Mode` and `debug_mode`. Many config
+/// formats and loaders match keys case-insensitively, so one of the two settings is likely to be
+/// silently ignored. Each distinct spelling is reported once, against the first spelling seen.
+fn check_case_duplicates(config: &[ConfigEntry]) -> Vec<Finding> {
+    let mut spellings: HashMap<String, Vec<&ConfigEntry>> = HashMap::new();
+    let mut findings = Vec::new();
+    for entry in config {
+        let seen = spellings.entry(entry.key.to_lowercase()).or_default();
+        if seen.iter().any(|e| e.key == entry.key) {
+            continue;
+        }
+        if let Some(first) = seen.first() {
+            findings.push(
+                Finding::warning(format!(
+                    "Config keys '{}' ({}) and '{}' ({}) differ only by case. Case-insensitive loaders will treat them as the same setting.",
+                    first.key,
+                    first.location(),
+                    entry.key,
+                    entry.location()
+                ))
+                .for_rule("case_duplicate")
+                .at_entry(entry),
+            );
+        }
+        seen.push(entry);
+    }
+    findings
+}
 
-    info(&format!("Loading configuration from: {}", config_path), verbose);
-    let config_content = read_file_content(&config_path);
-    let config = parse_config(&config_content);
-    info("Configuration loaded and parsed.", verbose);
+/// Returns the structured path of `key`: the config entry's own path when the key is present,
+/// otherwise the key's dotted segments (e.g. for a missing key).
+fn key_path(key: &str, config: &[ConfigEntry]) -> Vec<String> {
+    match config.iter().find(|e| e.key == key) {
+        Some(entry) => entry.path.clone(),
+        None => key.split('.').map(str::to_string).collect(),
+    }
+}
 
-    info(&format!("Loading schema from: {}", schema_path), verbose);
-    let schema_content = read_file_content(&schema_path);
-    let schema = parse_schema(&schema_content);
-    info("Schema loaded and parsed.", verbose);
+/// A single schema rule, optionally documented with a human-readable description.
+/// `severity` and `message` override the severity and message of the findings the rule raises.
+#[derive(Debug)]
+struct SchemaRule {
+    key: String,
+    rule: String,
+    description: Option<String>,
+    severity: Option<Severity>,
+    message: Option<String>,
+}
+
+impl SchemaRule {
+    /// Builds a rule from a schema line's value: the rule, then optional `;`-separated parts.
+    /// The rule may end in a ` @LEVEL` severity annotation. A part of the form `severity=LEVEL`
+    /// or `message=TEMPLATE` sets that attribute, `severity=` taking precedence over `@LEVEL`;
+    /// the other parts form the description. `message=` takes the rest of the line, so a
+    /// template may itself contain ';'.
+    fn parse(key: String, value: &str) -> SchemaRule {
+        let (rule, tail) = match value.split_once(';') {
+            Some((rule, tail)) => (clean_value(rule), Some(tail)),
+            None => (value.to_string(), None),
+        };
+        let (rule, mut severity) = split_severity_annotation(rule, &key);
+        let (mut description, mut message) = (Vec::new(), None);
+        let mut rest = tail;
+        while let Some(part) = rest {
+            let (current, next) = match part.split_once(';') {
+                Some((current, next)) => (current, Some(next)),
+                None => (part, None),
+            };
+            rest = next;
+            if let Some(template) = part.trim_start().strip_prefix("message=") {
+                message = Some(clean_value(template));
+                break;
+            } else if let Some(level) = current.trim_start().strip_prefix("severity=") {
+                severity = parse_rule_severity(level.trim(), &key).or(severity);
+            } else {
+                description.push(current);
+            }
+        }
+        let description = description.join(";").trim().to_string();
+        SchemaRule {
+            key,
+            rule,
+            description: (!description.is_empty()).then_some(description),
+            severity,
+            message: message.filter(|m| !m.is_empty()),
+        }
+    }
+
+    /// Renders the rule's message template for `value`, the checked key's config value if it is
+    /// set. `{key}` and `{value}` are replaced; any other placeholder, or `{value}` for a missing
+    /// key, is left as written.
+    fn render_message(&self, value: Option<&str>) -> Option<String> {
+        let template = self.message.as_ref()?.replace("{key}", &self.key);
+        Some(match value {
+            Some(value) => template.replace("{value}", value),
+            None => template,
+        })
+    }
+}
+
+/// Parses the severity given to a schema rule, warning about and ignoring an unknown level.
+fn parse_rule_severity(level: &str, key: &str) -> Option<Severity> {
+    level.parse::<Severity>().ok().or_else(|| {
+        warn(&format!(
+            "Ignoring invalid severity '{}' for schema rule '{}' (expected info, warning or critical).",
+            level, key
+        ));
+        None
+    })
+}
+
+/// Splits a trailing ` @LEVEL` severity annotation off a rule, as in
+/// `no_default_password @critical`. The '@' must follow whitespace and be followed by letters
+/// only, so rules such as `weak_password:@file:PATH` are left alone.
+fn split_severity_annotation(rule: String, key: &str) -> (String, Option<Severity>) {
+    let Some((rest, level)) = rule.rsplit_once('@') else {
+        return (rule, None);
+    };
+    if !rest.ends_with(char::is_whitespace) || level.is_empty() || !level.chars().all(|c| c.is_ascii_alphabetic()) {
+        return (rule, None);
+    }
+    (clean_value(rest), parse_rule_severity(level, key))
+}
+
+/// Returns the profile name of a `[profile:NAME]` schema header line.
+fn profile_header(trimmed: &str) -> Option<&str> {
+    let header = trimmed.strip_prefix('[')?.strip_suffix(']')?;
+    Some(header.trim().strip_prefix("profile:")?.trim())
+}
+
+/// Parses a schema, applying the rules of `profile` on top of the unscoped ones.
+/// Rules under a `[profile:NAME]` header apply only when NAME is selected with `--profile`, up to
+/// the next header of any kind. A profile rule replaces an unscoped rule for the same key, so
+/// `debug_mode = any` in a dev profile relaxes a shared `debug_mode = false`; ordering rules are
+/// added to the shared ones instead. Returns the rules and the names of all profiles found.
+fn parse_schema(content: &str, profile: Option<&str>) -> (Vec<SchemaRule>, Vec<String>) {
+    let (mut shared, mut selected) = (Vec::new(), Vec::new());
+    let mut profiles: Vec<String> = Vec::new();
+    let mut current: Option<&str> = None; // Profile of the current section, if any
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            current = profile_header(trimmed);
+            if let Some(name) = current {
+                if !profiles.iter().any(|p| p == name) {
+                    profiles.push(name.to_string());
+                }
+                continue;
+            }
+        }
+        match current {
+            None => shared.push(line),
+            Some(name) if Some(name) == profile => selected.push(line),
+            Some(_) => {}
+        }
+    }
+    let overrides = parse_schema_rules(&selected.join("\n"));
+    let mut rules: Vec<SchemaRule> = parse_schema_rules(&shared.join("\n"))
+        .into_iter()
+        .filter(|rule| rule.key == "order:" || !overrides.iter().any(|o| o.key == rule.key))
+        .collect();
+    rules.extend(overrides);
+    (rules, profiles)
+}
+
+/// Parses schema rule lines (simplified for demonstration, assumes key-value pairs representing rules).
+fn parse_schema_rules(content: &str) -> Vec<SchemaRule> {
+    // Similar to parse_config, but specific to schema rules.
+    // For this basic demo, assume schema lines are "key=expected_value" or "key=rule_type",
+    // optionally annotated " @LEVEL" and followed by "; description" documenting the rule and by
+    // "; severity=LEVEL" and "; message=TEMPLATE" overriding its findings (see SchemaRule::parse).
+    // Ordering rules (`order: A before B`) have no '=', so they are picked out first and
+    // carried as rules under the key `order:`.
+    let (order_lines, rule_lines): (Vec<&str>, Vec<&str>) =
+        content.lines().partition(|line| line.trim().starts_with("order:"));
+    let order_entries = order_lines
+        .iter()
+        .map(|line| ("order:".to_string(), clean_value(&line.trim()["order:".len()..])));
+    parse_config(&rule_lines.join("\n"), false)
+        .into_iter()
+        .map(|ConfigEntry { key, value, .. }| (key, value))
+        .chain(order_entries)
+        .map(|(key, value)| SchemaRule::parse(key, &value))
+        .collect()
+}
+
+/// Extracts schema rules embedded in the config as `#@rule key = rule` annotations.
+/// Annotations use the same syntax as schema file lines, including `; description` and
+/// `#@rule [profile:NAME]` headers.
+fn parse_inline_schema(content: &str, profile: Option<&str>) -> (Vec<SchemaRule>, Vec<String>) {
+    let annotations: Vec<&str> = content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("#@rule"))
+        .collect();
+    parse_schema(&annotations.join("\n"), profile)
+}
+
+/// Severity of a linter finding. By default, `Warning` and above cause a non-zero exit code (see `--exit-on`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    /// Lowercase name, as accepted on the command line.
+    fn name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A single linter finding with its severity.
+/// `path` is the structured key path the finding refers to (empty if it concerns the whole file).
+/// `source` pins the finding to a specific config line (file, line) when the key occurs more
+/// than once; otherwise `--show-source` uses the key's first occurrence.
+/// `confidence` (0-100) is how certain the finding is: 100 for exact checks such as schema
+/// rules, lower for heuristics that guess from key names or value statistics.
+/// `rule` names the check that raised the finding, for `--explain` (see `EXPLANATIONS`).
+#[derive(Debug, Clone)]
+struct Finding {
+    severity: Severity,
+    message: String,
+    path: Vec<String>,
+    source: Option<(Option<String>, usize)>,
+    confidence: u8,
+    rule: Option<&'static str>,
+}
+
+impl Finding {
+    fn new(severity: Severity, message: String) -> Self {
+        Finding { severity, message, path: Vec::new(), source: None, confidence: 100, rule: None }
+    }
+
+    /// Records which check raised the finding, so `--explain` can look up its rationale.
+    fn for_rule(mut self, rule: &'static str) -> Self {
+        self.rule = Some(rule);
+        self
+    }
+
+    /// The `--explain` rationale and remediation for the finding's rule, if it has one.
+    fn explanation(&self) -> Option<&'static str> {
+        let rule = self.rule?;
+        EXPLANATIONS.iter().find(|(name, _)| *name == rule).map(|(_, text)| *text)
+    }
+
+    /// Sets the confidence of a heuristic finding.
+    fn with_confidence(mut self, confidence: u8) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Attaches the structured key path the finding refers to.
+    fn at(mut self, path: Vec<String>) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Attaches the key path and exact source line of `entry`.
+    fn at_entry(mut self, entry: &ConfigEntry) -> Self {
+        self.path = entry.path.clone();
+        self.source = Some((entry.file.clone(), entry.line));
+        self
+    }
+
+    fn warning(message: String) -> Self {
+        Finding::new(Severity::Warning, message)
+    }
+
+    /// Appends a schema rule's description to the message, if one was documented.
+    fn describe(&mut self, description: Option<&str>) {
+        if let Some(description) = description {
+            self.message.push_str(&format!(" ({})", description));
+        }
+    }
+}
+
+/// Why each check matters and how to fix a finding, printed by `--explain`. Keyed by the
+/// schema rule type or, for checks without a schema rule, by the name given to `Finding::for_rule`.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    ("https://", "Plain HTTP sends credentials and data unencrypted, where anyone on the network path can read or alter them. Point the setting at an https:// endpoint."),
+    ("secure_url", "Schemes such as http://, ftp:// and telnet:// carry traffic in clear text and allow downgrade attacks. Use the encrypted scheme the finding suggests."),
+    ("false", "Debug mode can leak stack traces, internal paths and configuration details to attackers, and often disables safety checks. Set it to false in production."),
+    ("no_default_password", "Vendor default passwords are published and are the first thing attackers try. Set a unique, randomly generated password and keep it in a secret store."),
+    ("weak_password", "Passwords from public wordlists fall to dictionary attacks within seconds. Replace it with a long, randomly generated password."),
+    ("INFO", "DEBUG logging can write secrets and personal data to log files, while WARN or ERROR alone hides the events needed to investigate an incident. Use INFO for normal operation."),
+    ("min_length_8", "Short keys can be brute-forced. Use a length of at least 8; longer is better for secrets."),
+    ("deprecated", "Deprecated settings may be ignored by newer versions, silently dropping the protection they provided. Move the value to the replacement key."),
+    ("no_empty", "An empty value often makes software fall back to an insecure default or disables the feature entirely. Set an explicit value or remove the key."),
+    ("max_value_length", "Unusually long values can hide injected payloads, serialized objects or pasted secrets. Check the value and keep it in a separate file if it is legitimately large."),
+    ("address", "A malformed address may be rejected at startup, or worse, parsed differently than intended (e.g. leading zeros read as octal). Write the address in the standard form shown."),
+    ("min_tls", "SSLv3, TLS 1.0 and TLS 1.1 have known attacks such as POODLE and BEAST and are deprecated by RFC 8996. Allow only TLS 1.2 and later."),
+    ("no_weak_ciphers", "Weak ciphers let an attacker who records the traffic decrypt it, or skip encryption or authentication altogether. Remove them or exclude them with '!', e.g. 'HIGH:!aNULL:!RC4:!3DES'."),
+    ("path_exists", "A referenced file that is missing, such as a certificate or key, makes the service fail at startup or fall back to an insecure default, and loose permissions can expose a private key. Deploy the file at the configured path with the permissions the rule requires, e.g. 'chmod 600' for 'private'."),
+    ("dir_exists", "A missing directory, such as a certificate store or log directory, makes the service fail at startup or silently skip the feature. Create the directory at the configured path with the permissions the rule requires."),
+    ("known_secret", "A credential in a recognized format, such as an AWS access key or a GitHub token, is live until revoked, and anyone with a copy of the file, its backups or its version history can use it. Revoke the credential with its provider, then load the replacement from a secret store or environment variable."),
+    ("forbidden", "Some settings, such as debug backdoors or remote shells, are dangerous at any value, because a later change or a loader that ignores the value can turn them on. Remove the key entirely."),
+    ("power_of_two", "Buffer sizes, alignments and hash table sizes are often required to be powers of two; other values may be rejected at startup, silently rounded, or leave allocations misaligned. Use one of the powers of two given in the finding."),
+    ("multiple_of", "Sizes such as block, page or key lengths must be whole multiples of a base unit, or the software may truncate, pad or misalign them. Round the value to a multiple of the given base."),
+    ("exact", "The schema pins this setting to a single approved value. Use that value, or update the schema if the change was reviewed."),
+    ("missing", "The schema requires this setting; without it the software uses a built-in default that may not be secure. Add the key with an approved value."),
+    ("unknown_key", "Settings the schema does not know about have no reviewed security posture and may be typos of real keys. Add a rule for the key or remove it."),
+    ("strict_schema", "Under a closed-world policy every setting must be reviewed. Add a schema rule for the key or remove it from the config."),
+    ("empty_config", "An empty config usually means templating or deployment failed, leaving the software on its defaults. Check that the file rendered correctly."),
+    ("case_duplicate", "Loaders that ignore case keep only one of the two settings, so the other is silently lost. Remove or rename one of them."),
+    ("equals_key", "Settings that must agree, such as a certificate name and a server name, cause failed handshakes or disabled checks when they drift apart. Change one of the two values so they match."),
+    ("host_of", "A public URL that points at a different host than the server, such as a redirect or callback URL, can send users or tokens to a stale or foreign host. Make the URL's host match the server's host name."),
+    ("dependency", "Some settings are only safe together, such as TLS and a certificate path. Set the required key as stated."),
+    ("order", "Rules evaluated top to bottom, such as firewall rules, never reach an entry placed after a catch-all. Move the key above the other."),
+    ("entropy", "Random-looking values are usually API keys, tokens or passwords, which leak with the file through backups and version control. Move the secret to a secret store or environment variable."),
+    ("world_readable", "Any local user or compromised service can read secrets from a world-readable file. Restrict the file, e.g. with 'chmod 600', and make it owned by the service account."),
+];
+
+/// A cross-key dependency rule: when `antecedent_key` equals `antecedent_value`,
+/// `consequent_key` must be present (and equal `consequent_value`, if given).
+/// Written in the schema as `requires:tls_enabled=true => tls_cert_path`.
+#[derive(Debug)]
+struct DependencyRule {
+    antecedent_key: String,
+    antecedent_value: String,
+    consequent_key: String,
+    consequent_value: Option<String>,
+    description: Option<String>,
+    severity: Option<Severity>,
+}
+
+/// A structural rule: every occurrence of `first` must appear before any occurrence of
+/// `second`, in the order the config is read (with includes inlined). Written in the schema as `order: rule_allow before rule_deny`.
+#[derive(Debug)]
+struct OrderRule {
+    first: String,
+    second: String,
+    description: Option<String>,
+    severity: Option<Severity>,
+}
+
+/// Parses the `A before B` body of an `order:` schema rule.
+fn parse_order_rule(rule: &str) -> Option<OrderRule> {
+    let (first, second) = rule.split_once(" before ")?;
+    let (first, second) = (first.trim(), second.trim());
+    if first.is_empty() || second.is_empty() || first.contains(char::is_whitespace) || second.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(OrderRule { first: first.to_string(), second: second.to_string(), description: None, severity: None })
+}
+
+/// Checks an ordering rule against the order of the config's entries, which follows the file
+/// (with includes inlined). Keys that are absent are not reported here; missing keys are the job
+/// of per-key rules.
+fn check_order(rule: &OrderRule, config: &[ConfigEntry]) -> Option<Finding> {
+    let last_first = config.iter().rposition(|e| e.key == rule.first)?;
+    let first_second = config.iter().position(|e| e.key == rule.second)?;
+    if last_first < first_second {
+        return None;
+    }
+    let mut finding = Finding::warning(format!(
+        "Ordering violation: '{}' ({}) must appear before '{}' ({}).",
+        rule.first,
+        config[last_first].location(),
+        rule.second,
+        config[first_second].location()
+    )).for_rule("order");
+    if let Some(severity) = rule.severity {
+        finding.severity = severity;
+    }
+    finding.describe(rule.description.as_deref());
+    Some(finding.at_entry(&config[last_first]))
+}
+
+/// Parses a dependency rule from a schema entry whose key starts with `requires:`.
+/// `antecedent_key` is the key with the prefix stripped; `rule` is the text after the first '='.
+fn parse_dependency_rule(antecedent_key: &str, rule: &str) -> Option<DependencyRule> {
+    let (antecedent_value, consequent) = rule.split_once("=>")?;
+    let (consequent_key, consequent_value) = match consequent.split_once('=') {
+        Some((k, v)) => (k.trim(), Some(clean_value(v))),
+        None => (consequent.trim(), None),
+    };
+    if antecedent_key.trim().is_empty() || consequent_key.is_empty() {
+        return None;
+    }
+    Some(DependencyRule {
+        antecedent_key: antecedent_key.trim().to_string(),
+        antecedent_value: clean_value(antecedent_value),
+        consequent_key: consequent_key.to_string(),
+        consequent_value,
+        description: None,
+        severity: None,
+    })
+}
+
+/// Checks a value against an `ipv4`, `ipv6` or `cidr` rule, returning a finding that names the
+/// expected form if it does not parse. A `cidr` value is an IPv4 or IPv6 address followed by a
+/// `/prefix` of at most 32 or 128 bits respectively.
+fn check_address(key: &str, value: &str, rule: &str) -> Option<Finding> {
+    let expected = match rule {
+        "ipv4" if value.parse::<Ipv4Addr>().is_err() => {
+            "an IPv4 address: four decimal octets from 0 to 255 without leading zeros, such as 192.168.1.10"
+        }
+        "ipv6" if value.parse::<Ipv6Addr>().is_err() => "an IPv6 address, such as 2001:db8::1 or ::1",
+        "cidr" => {
+            let valid = value.split_once('/').is_some_and(|(address, prefix)| {
+                let max_prefix = match address.parse::<IpAddr>() {
+                    Ok(IpAddr::V4(_)) => 32,
+                    Ok(IpAddr::V6(_)) => 128,
+                    Err(_) => return false,
+                };
+                prefix.parse::<u8>().is_ok_and(|bits| bits <= max_prefix && !prefix.starts_with('+'))
+            });
+            if valid {
+                return None;
+            }
+            "a CIDR block: an IPv4 address with a /0-/32 prefix, such as 10.0.0.0/8, or an IPv6 address with a /0-/128 prefix, such as 2001:db8::/32"
+        }
+        _ => return None,
+    };
+    Some(Finding::warning(format!(
+        "Invalid address: '{}' value '{}' is not {}.",
+        key, value, expected
+    )).for_rule("address"))
+}
+
+/// Parses a protocol version such as `TLSv1.2`, `TLS1_2`, `tls 1.2`, `1.2` (TLS) or `SSLv3`,
+/// returning its index in `TLS_VERSIONS`.
+fn parse_tls_version(text: &str) -> Option<usize> {
+    let normalized = text.trim().to_lowercase().replace([' ', '_'], ".").replace("..", ".");
+    let (protocol, number) = match normalized.strip_prefix("ssl") {
+        Some(rest) => ("SSLv", rest),
+        None => ("TLSv", normalized.strip_prefix("tls").unwrap_or(&normalized)),
+    };
+    let number = number.trim_start_matches(['v', '.']);
+    let number = match number {
+        "1" if protocol == "TLSv" => "1.0",
+        "2.0" | "3.0" if protocol == "SSLv" => &number[..1],
+        number => number,
+    };
+    TLS_VERSIONS.iter().position(|&version| version == format!("{}{}", protocol, number))
+}
+
+/// Checks a value against a `min_tls:<VERSION>` rule. The value is a single protocol version or
+/// a list of enabled versions separated by spaces, commas or colons, as in `TLSv1.2 TLSv1.3`.
+/// Entries starting with '-' or '!' disable a version and are skipped; a leading '+' is ignored.
+fn check_min_tls(key: &str, value: &str, rule: &str) -> Option<Finding> {
+    let Some(minimum) = parse_tls_version(&rule["min_tls:".len()..]) else {
+        return Some(Finding::warning(format!(
+            "Malformed min_tls rule '{}={}'. Expected 'min_tls:<VERSION>', such as 'min_tls:1.2'.",
+            key, rule
+        )));
+    };
+    // `TLS 1.2` is one version, not the two entries `TLS` and `1.2`.
+    let lowered = value.to_lowercase().replace("tls ", "tls").replace("ssl ", "ssl");
+    let mut too_old = Vec::new();
+    for entry in lowered.split([' ', ',', ':']).filter(|entry| !entry.is_empty()) {
+        if entry.starts_with(['-', '!']) {
+            continue;
+        }
+        match parse_tls_version(entry.trim_start_matches('+')) {
+            Some(version) if version < minimum => too_old.push(TLS_VERSIONS[version]),
+            Some(_) => {}
+            None => {
+                return Some(Finding::warning(format!(
+                    "Unrecognized TLS version: '{}' value '{}' contains '{}', which is not a TLS or SSL version.",
+                    key, value, entry
+                )).for_rule("min_tls"))
+            }
+        }
+    }
+    if too_old.is_empty() {
+        return None;
+    }
+    Some(Finding::warning(format!(
+        "Outdated TLS version: '{}' allows {} (below the required minimum of {}).",
+        key,
+        too_old.join(", "),
+        TLS_VERSIONS[minimum]
+    )).for_rule("min_tls"))
+}
+
+/// Checks a cipher list against `WEAK_CIPHERS`. Ciphers are separated by colons, commas or
+/// spaces, as in OpenSSL cipher strings; entries starting with '!' or '-' remove ciphers and are
+/// skipped, so `HIGH:!aNULL:!RC4` passes.
+fn check_weak_ciphers(key: &str, value: &str) -> Option<Finding> {
+    let mut weak: Vec<String> = Vec::new();
+    for cipher in value.split([':', ',', ' ']).filter(|cipher| !cipher.is_empty()) {
+        if cipher.starts_with(['!', '-']) {
+            continue;
+        }
+        let cipher = cipher.trim_start_matches('+');
+        // OpenSSL names 3DES suites `DES-CBC3-...`; they are reported as 3DES, not single DES.
+        let reason = cipher
+            .to_uppercase()
+            .replace("DES-CBC3", "3DES")
+            .split(['-', '_', '+'])
+            .find_map(|part| WEAK_CIPHERS.iter().find(|(name, _)| name.eq_ignore_ascii_case(part)))
+            .map(|(_, reason)| reason);
+        if let Some(reason) = reason {
+            weak.push(format!("'{}' ({})", cipher, reason));
+        }
+    }
+    if weak.is_empty() {
+        return None;
+    }
+    Some(Finding::warning(format!(
+        "Weak cipher: '{}' enables {}.",
+        key,
+        weak.join(", ")
+    )).for_rule("no_weak_ciphers"))
+}
+
+/// Checks a `power_of_two` or `multiple_of:<N>` rule against an integer value, such as a buffer
+/// size or an alignment. Values that are not integers get the same warning as `min_length_8`.
+fn check_integer_shape(key: &str, value: &str, rule: &str) -> Option<Finding> {
+    let base = match rule.strip_prefix("multiple_of:") {
+        Some(base) => match base.trim().parse::<i64>() {
+            Ok(base) if base > 0 => Some(base),
+            _ => {
+                return Some(Finding::warning(format!(
+                    "Malformed multiple_of rule '{}={}'. Expected 'multiple_of:<N>' with a positive integer N.",
+                    key, rule
+                )));
+            }
+        },
+        None => None,
+    };
+    let rule_name = if base.is_some() { "multiple_of" } else { "power_of_two" };
+    let Ok(number) = value.parse::<i64>() else {
+        return Some(Finding::warning(format!(
+            "Schema rule for '{}' expects an integer, but config value '{}' is not a valid integer.",
+            key, value
+        )).for_rule(rule_name));
+    };
+    let problem = match base {
+        Some(base) if number % base != 0 => format!("is not a multiple of {}", base),
+        Some(_) => return None,
+        None if number <= 0 => "is not a power of two".to_string(),
+        None if (number as u64).is_power_of_two() => return None,
+        None => {
+            // Suggest the powers of two on either side of the value.
+            let lower = 1u64 << (63 - (number as u64).leading_zeros());
+            format!("is not a power of two (it lies between {} and {})", lower, lower * 2)
+        }
+    };
+    Some(Finding::warning(format!(
+        "Invalid size: '{}' value {} {}.",
+        key, number, problem
+    )).for_rule(rule_name))
+}
+
+/// Checks a `known_secret` rule: reports every format in `KNOWN_SECRETS` that occurs in the value,
+/// on its own or embedded, as in `Authorization: Bearer eyJ...`. A token must not continue a
+/// longer word, so `XAKIA...` is not an AWS key. Only the format's prefix is echoed, never the
+/// secret itself.
+fn check_known_secrets(key: &str, value: &str) -> Vec<Finding> {
+    let bytes = value.as_bytes();
+    let found = |format: &SecretFormat| {
+        value.match_indices(format.prefix).any(|(start, _)| {
+            if start > 0 && (bytes[start - 1].is_ascii_alphanumeric() || (format.charset)(&bytes[start - 1])) {
+                return false;
+            }
+            let body_start = start + format.prefix.len();
+            let body_len = bytes[body_start..].iter().take_while(|b| (format.charset)(b)).count();
+            let token = &value[start..body_start + body_len];
+            (format.min_len..=format.max_len).contains(&body_len)
+                && token.split('.').count() == format.parts
+                && token.split('.').all(|part| !part.is_empty())
+        })
+    };
+    KNOWN_SECRETS
+        .iter()
+        .filter(|format| found(format))
+        .map(|format| {
+            Finding::new(Severity::Critical, format!(
+                "Known secret: '{}' contains a token in the {} format (starting '{}'). Revoke it and move the secret to a secret store.",
+                key, format.name, format.prefix
+            )).for_rule("known_secret")
+        })
+        .collect()
+}
+
+/// Checks a `path_exists` or `dir_exists` rule: the value must name an existing file or
+/// directory, respectively. The rule may add permission checks from `PATH_PERMISSIONS` after a
+/// colon, as in `path_exists:readable,private`. Relative values are resolved against `base_dir`.
+fn check_path(key: &str, value: &str, rule: &str, base_dir: &Path) -> Option<Finding> {
+    let (kind, checks) = rule.split_once(':').unwrap_or((rule, ""));
+    let checks: Vec<&str> = checks.split(',').map(str::trim).filter(|check| !check.is_empty()).collect();
+    if let Some(unknown) = checks.iter().find(|check| !PATH_PERMISSIONS.contains(check)) {
+        return Some(Finding::warning(format!(
+            "Malformed {} rule '{}={}'. Unknown permission check '{}'; expected one of {}.",
+            kind, key, rule, unknown, PATH_PERMISSIONS.join(", ")
+        )));
+    }
+
+    let resolved = base_dir.join(value);
+    let shown = if resolved == Path::new(value) {
+        format!("'{}'", value)
+    } else {
+        format!("'{}' (resolved to '{}')", value, resolved.display())
+    };
+    // `for_rule` needs a static name, which `kind` borrowed from the schema is not.
+    let (want_dir, name) = if kind == "dir_exists" { (true, "dir_exists") } else { (false, "path_exists") };
+    let metadata = match fs::metadata(&resolved) {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            return Some(Finding::warning(format!(
+                "Missing path: '{}' refers to {}, which does not exist.",
+                key, shown
+            )).for_rule(name))
+        }
+    };
+    if metadata.is_dir() != want_dir {
+        return Some(Finding::warning(format!(
+            "Wrong path type: '{}' refers to {}, which is {}.",
+            key,
+            shown,
+            if want_dir { "not a directory" } else { "a directory, not a file" }
+        )).for_rule(name));
+    }
+    // Opening a FIFO or device for the readable and writable checks could block forever, so
+    // only regular files are opened.
+    let opens = checks.iter().any(|check| matches!(*check, "readable" | "writable"));
+    if opens && !metadata.is_file() && !metadata.is_dir() {
+        return Some(Finding::warning(format!(
+            "Wrong path type: '{}' refers to {}, which is not a regular file.",
+            key, shown
+        )).for_rule(name));
+    }
+
+    let problems: Vec<String> = checks
+        .iter()
+        .filter_map(|check| permission_problem(&resolved, &metadata, check))
+        .collect();
+    if problems.is_empty() {
+        return None;
+    }
+    Some(Finding::warning(format!(
+        "Path permissions: '{}' refers to {}, which is {}.",
+        key,
+        shown,
+        problems.join(" and ")
+    )).for_rule(name))
+}
+
+/// Describes how `path` fails a permission check from `PATH_PERMISSIONS`, or returns `None` if
+/// it passes. `path` must be a regular file or a directory. `readable` is tested by opening the
+/// file or listing the directory, and a file's `writable` by opening it in append mode, so
+/// nothing is changed. Both reflect the user running the linter. A directory's `writable` only
+/// checks that it is not read-only for everyone: the standard library cannot tell whether the
+/// owner is the current user, so a root-owned directory with mode 755 passes for any user.
+/// `executable` and `private` are checked against the permission bits, and only on Unix.
+fn permission_problem(path: &Path, metadata: &fs::Metadata, check: &str) -> Option<String> {
+    let failed = match check {
+        "readable" if metadata.is_dir() => fs::read_dir(path).is_err(),
+        "readable" => fs::File::open(path).is_err(),
+        // Listing or opening cannot test this without creating a file in the directory.
+        "writable" if metadata.is_dir() => metadata.permissions().readonly(),
+        "writable" => fs::OpenOptions::new().append(true).open(path).is_err(),
+        "executable" => permission_mode(metadata).is_some_and(|mode| mode & 0o111 == 0),
+        "private" => {
+            return permission_mode(metadata)
+                .filter(|mode| mode & 0o077 != 0)
+                .map(|mode| format!("accessible to other users (mode {:o})", mode))
+        }
+        _ => false,
+    };
+    failed.then(|| format!("not {}", check))
+}
+
+/// Returns the permission bits of a file.
+#[cfg(unix)]
+fn permission_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o777)
+}
+
+/// Permission bits are only available on Unix.
+#[cfg(not(unix))]
+fn permission_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// Returns the host part of a URL or `host[:port][/path]` value: without scheme, user info, port,
+/// path, query or fragment, and without the brackets of an IPv6 literal.
+fn url_host(value: &str) -> &str {
+    let rest = value.split_once("://").map_or(value, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    }
+}
+
+/// Checks an `equals_key:<OTHER>` or `host_of:<OTHER>` rule, which relate the value to that of
+/// another config key: `equals_key` requires the two values to be equal, and `host_of` requires
+/// the host of this key's URL to equal the other value, ignoring case. A missing other key is
+/// reported, since the relationship cannot be checked.
+fn check_key_reference(key: &str, value: &str, rule: &str, config: &[ConfigEntry]) -> Option<Finding> {
+    let (kind, other_key) = rule.split_once(':').unwrap_or((rule, ""));
+    let other_key = other_key.trim();
+    let name = if kind == "host_of" { "host_of" } else { "equals_key" };
+    if other_key.is_empty() {
+        return Some(Finding::warning(format!(
+            "Malformed {} rule '{}={}'. Expected '{}:<KEY>', naming another config key.",
+            kind, key, rule, kind
+        )));
+    }
+    let Some(other_value) = config.iter().find(|e| e.key == other_key).map(|e| e.value.as_str()) else {
+        return Some(Finding::warning(format!(
+            "Missing reference: '{}' must match '{}', which is not set.",
+            key, other_key
+        )).for_rule(name));
+    };
+
+    if name == "equals_key" {
+        if value == other_value {
+            return None;
+        }
+        return Some(Finding::warning(format!(
+            "Mismatched value: '{}' is '{}', but must equal '{}', which is '{}'.",
+            key, value, other_key, other_value
+        )).for_rule(name));
+    }
+    let host = url_host(value);
+    if host.eq_ignore_ascii_case(other_value) {
+        return None;
+    }
+    Some(Finding::warning(format!(
+        "Mismatched host: '{}' is '{}', whose host '{}' must equal '{}', which is '{}'.",
+        key, value, host, other_key, other_value
+    )).for_rule(name))
+}
+
+/// Evaluates a dependency rule against the config, returning a finding if it is violated.
+fn check_dependency(rule: &DependencyRule, config: &[ConfigEntry]) -> Option<Finding> {
+    let antecedent_holds = config
+        .iter()
+        .any(|e| e.key == rule.antecedent_key && e.value == rule.antecedent_value);
+    if !antecedent_holds {
+        return None;
+    }
+
+    let mut finding = match (config.iter().find(|e| e.key == rule.consequent_key).map(|e| &e.value), &rule.consequent_value) {
+        (None, _) => Some(Finding::warning(format!(
+            "Dependency violation: '{}' is '{}', which requires '{}' to be set.",
+            rule.antecedent_key, rule.antecedent_value, rule.consequent_key
+        )).for_rule("dependency")),
+        (Some(actual), Some(expected)) if actual != expected => Some(Finding::warning(format!(
+            "Dependency violation: '{}' is '{}', which requires '{}' to be '{}' (found '{}').",
+            rule.antecedent_key, rule.antecedent_value, rule.consequent_key, expected, actual
+        )).for_rule("dependency")),
+        _ => None,
+    }?;
+    if let Some(severity) = rule.severity {
+        finding.severity = severity;
+    }
+    finding.describe(rule.description.as_deref());
+    Some(finding.at(key_path(&rule.consequent_key, config)))
+}
+
+/// Weak password wordlists loaded by `weak_password:@file:` rules, keyed by path.
+type WordlistCache = HashMap<String, HashSet<String>>;
+
+/// Returns the wordlist at `path`, reading it on first use. Each non-empty line is one password.
+/// A missing or unreadable wordlist is fatal, since silently skipping it would weaken the check.
+fn load_wordlist<'a>(wordlists: &'a mut WordlistCache, path: &str) -> &'a HashSet<String> {
+    wordlists.entry(path.to_string()).or_insert_with(|| {
+        let content = fs::read_to_string(path).unwrap_or_else(|e| {
+            io_error(&format!("Failed to read weak password list {}: {}", path, e));
+            String::new()
+        });
+        content
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+/// Returns an overlong-value finding if `value` is longer than `limit` characters.
+fn check_value_length(key: &str, value: &str, limit: usize) -> Option<Finding> {
+    let length = value.chars().count();
+    (length > limit).then(|| {
+        Finding::warning(format!(
+            "Overlong value: '{}' is {} characters long (limit {}). Long values may hide injected payloads or serialized blobs.",
+            key, length, limit
+        )).for_rule("max_value_length")
+    })
+}
+
+/// Applies the global `--max-value-length` limit to every key without its own
+/// `max_value_length` schema rule.
+fn check_value_lengths(config: &[ConfigEntry], schema: &[SchemaRule], max_value_length: Option<usize>) -> Vec<Finding> {
+    let Some(limit) = max_value_length else {
+        return Vec::new();
+    };
+    config
+        .iter()
+        .filter(|entry| {
+            !schema
+                .iter()
+                .any(|rule| rule.key == entry.key && rule.rule.starts_with("max_value_length:"))
+        })
+        .filter_map(|entry| check_value_length(&entry.key, &entry.value, limit).map(|f| f.at(entry.path.clone())))
+        .collect()
+}
+
+/// Applies a single (non-dependency) schema rule to the config, pushing any findings.
+fn apply_rule(
+    schema_key: &str,
+    schema_rule: &str,
+    config: &[ConfigEntry],
+    wordlists: &mut WordlistCache,
+    base_dir: &Path,
+    findings: &mut Vec<Finding>,
+) {
+    // Deprecated keys are known but discouraged: warn when present, and never report
+    // them as missing since their absence is the desired state.
+    if let Some(replacement) = schema_rule.strip_prefix("deprecated:") {
+        if config.iter().any(|e| e.key == schema_key) {
+            findings.push(Finding::warning(format!(
+                "Deprecated setting: '{}' is deprecated. Use '{}' instead.",
+                schema_key, replacement
+            )).for_rule("deprecated"));
+        }
+        return;
+    }
+
+    // Forbidden keys invert the presence check: any occurrence is reported, whatever its value,
+    // and absence is the desired state.
+    if schema_rule == "forbidden" {
+        for entry in config.iter().filter(|e| e.key == schema_key) {
+            findings.push(Finding::new(Severity::Critical, format!(
+                "Forbidden setting: '{}' is set ({}). This key must not appear in the config; remove it.",
+                schema_key,
+                entry.location()
+            )).for_rule("forbidden").at_entry(entry));
+        }
+        return;
+    }
+
+    // A secret is best absent, so a missing or empty key passes.
+    if schema_rule == "known_secret" {
+        if let Some(entry) = config.iter().find(|e| e.key == schema_key) {
+            findings.extend(check_known_secrets(schema_key, &entry.value));
+        }
+        return;
+    }
+
+    match config.iter().find(|e| e.key == schema_key).map(|e| &e.value) {
+        // A key that is set but empty (`api_key =`) is reported as such, rather than as whatever
+        // confusing failure the rule would produce for an empty string. `no_empty` exists purely
+        // for this check; an empty exact-match rule (`key=`) explicitly expects an empty value.
+        Some(config_value)
+            if config_value.trim().is_empty()
+                && !schema_rule.is_empty()
+                && schema_rule != "any"
+                && !schema_rule.starts_with("max_value_length:") =>
+        {
+            findings.push(Finding::warning(format!(
+                "Empty value: '{}' is set but empty.",
+                schema_key
+            )).for_rule("no_empty"));
+        }
+        Some(_) if schema_rule == "no_empty" || schema_rule == "any" => {}
+        Some(config_value) if schema_rule.starts_with("max_value_length:") => {
+            match schema_rule["max_value_length:".len()..].trim() {
+                "none" => {} // Explicit opt-out from --max-value-length
+                limit => match limit.parse::<usize>() {
+                    Ok(limit) => findings.extend(check_value_length(schema_key, config_value, limit)),
+                    Err(_) => findings.push(Finding::warning(format!(
+                        "Malformed max_value_length rule '{}={}'. Expected 'max_value_length:<N>' or 'max_value_length:none'.",
+                        schema_key, schema_rule
+                    ))),
+                },
+            }
+        }
+        Some(config_value) if schema_rule == "power_of_two" || schema_rule.starts_with("multiple_of:") => {
+            findings.extend(check_integer_shape(schema_key, config_value, schema_rule));
+        }
+        Some(config_value) if schema_rule.starts_with("min_tls:") => {
+            findings.extend(check_min_tls(schema_key, config_value, schema_rule));
+        }
+        Some(config_value) if matches!(schema_rule.split(':').next(), Some("path_exists" | "dir_exists")) => {
+            findings.extend(check_path(schema_key, config_value, schema_rule, base_dir));
+        }
+        Some(config_value) if schema_rule.starts_with("equals_key:") || schema_rule.starts_with("host_of:") => {
+            findings.extend(check_key_reference(schema_key, config_value, schema_rule, config));
+        }
+        Some(config_value) if schema_rule.starts_with("weak_password:") => {
+            match schema_rule["weak_password:".len()..].strip_prefix("@file:") {
+                Some(path) => {
+                    // Only compare the value; never echo a secret into the report.
+                    if load_wordlist(wordlists, path).contains(config_value.as_str()) {
+                        findings.push(Finding::new(Severity::Critical, format!(
+                            "Critical: '{}' uses a password found in weak password list '{}'. Change immediately!",
+                            schema_key, path
+                        )).for_rule("weak_password"));
+                    }
+                }
+                None => findings.push(Finding::warning(format!(
+                    "Malformed weak_password rule '{}={}'. Expected 'weak_password:@file:PATH'.",
+                    schema_key, schema_rule
+                ))),
+            }
+        }
+        Some(config_value) => {
+            match schema_rule {
+                "https://" => { // database_url rule
+                    if !config_value.starts_with("https://") {
+                        findings.push(Finding::warning(format!(
+                            "Insecure setting: '{}' should use HTTPS (starts with 'https://').",
+                            schema_key
+                        )).for_rule("https://"));
+                    }
+                }
+                "secure_url" => { // generic URL scheme downgrade rule
+                    let lowered = config_value.to_lowercase();
+                    if let Some((scheme, secure)) = INSECURE_URL_SCHEMES
+                        .iter()
+                        .find(|(scheme, _)| lowered.starts_with(scheme))
+                    {
+                        findings.push(Finding::warning(format!(
+                            "Insecure setting: '{}' uses insecure scheme '{}'. Use '{}' instead.",
+                            schema_key, scheme, secure
+                        )).for_rule("secure_url"));
+                    } else if !SECURE_URL_SCHEMES.iter().any(|scheme| lowered.starts_with(scheme)) {
+                        findings.push(Finding::warning(format!(
+                            "Insecure setting: '{}' value '{}' does not use a recognized secure scheme ({}).",
+                            schema_key, config_value, SECURE_URL_SCHEMES.join(", ")
+                        )).for_rule("secure_url"));
+                    }
+                }
+                "false" => { // debug_mode rule
+                    if config_value == "true" {
+                        findings.push(Finding::warning(format!(
+                            "Insecure setting: '{}' should be 'false' in production.",
+                            schema_key
+                        )).for_rule("false"));
+                    }
+                }
+                "no_default_password" => { // admin_password rule
+                    if config_value == "password123" {
+                        findings.push(Finding::new(Severity::Critical, format!(
+                            "Critical: '{}' uses default password 'password123'. Change immediately!",
+                            schema_key
+                        )).for_rule("no_default_password"));
+                    }
+                }
+                "INFO" => { // log_level rule
+                    if config_value != "INFO" {
+                        findings.push(Finding::warning(format!(
+                            "Logging level: '{}' is not 'INFO'. Consider 'INFO' for standard operation.",
+                            schema_key
+                        )).for_rule("INFO"));
+                    }
+                }
+                "ipv4" | "ipv6" | "cidr" => findings.extend(check_address(schema_key, config_value, schema_rule)),
+                "no_weak_ciphers" => findings.extend(check_weak_ciphers(schema_key, config_value)),
+                "min_length_8" => { // api_key_length rule
+                    if let Ok(length) = config_value.parse::<usize>() {
+                        if length < 8 {
+                            findings.push(Finding::warning(format!(
+                                "Weak setting: '{}' has length {}. Recommended minimum: 8.",
+                                schema_key, length
+                            )).for_rule("min_length_8"));
+                        }
+                    } else {
+                        findings.push(Finding::warning(format!(
+                            "Schema rule for '{}' expects an integer length, but config value '{}' is not a valid integer.",
+                            schema_key, config_value
+                        )).for_rule("min_length_8"));
+                    }
+                }
+                _ => {
+                    // Generic check for exact value match if no specific rule type is recognized
+                    if config_value != schema_rule {
+                        findings.push(Finding::warning(format!(
+                            "Config key '{}' value '{}' does not match schema rule '{}'.",
+                            schema_key, config_value, schema_rule
+                        )).for_rule("exact"));
+                    }
+                }
+            }
+        }
+        None => {
+            // Key from schema is missing in config
+            findings.push(Finding::warning(format!(
+                "Missing configuration key: '{}' as defined in schema.",
+                schema_key
+            )).for_rule("missing"));
+        }
+    }
+}
+
+/// Validates the configuration against the schema.
+/// This is a highly simplified validation for demonstration.
+/// A real linter would have complex rule engines.
+fn validate_config(
+    config: &[ConfigEntry],
+    schema: &[SchemaRule],
+    strict_schema: bool,
+    max_value_length: Option<usize>,
+    base_dir: &Path,
+    _verbose: bool,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    // An empty config (blank or all comments) against a non-empty schema usually means the
+    // config failed to render, rather than being genuinely compliant.
+    if config.is_empty() && !schema.is_empty() {
+        findings.push(Finding::new(
+            Severity::Info,
+            format!(
+                "Empty configuration: no keys were parsed from the config, but the schema defines {} rule(s). The config may not have rendered correctly.",
+                schema.len()
+            ),
+        ).for_rule("empty_config"));
+    }
+
+    // Keys that differ only by case are reported regardless of the schema; values are still
+    // validated under each spelling as written.
+    findings.extend(check_case_duplicates(config));
+
+    // Collect schema rules into a more accessible map
+    let schema_map: HashMap<String, String> =
+        schema.iter().map(|r| (r.key.clone(), r.rule.clone())).collect();
+
+    // Dependency rules live under `requires:` keys; parse them up front so the keys they
+    // reference count as known to the schema.
+    let mut dependency_rules = Vec::new();
+    for entry in schema {
+        if let Some(antecedent_key) = entry.key.strip_prefix("requires:") {
+            match parse_dependency_rule(antecedent_key, &entry.rule) {
+                Some(rule) => dependency_rules.push(DependencyRule { description: entry.description.clone(), severity: entry.severity, ..rule }),
+                None => findings.push(Finding::warning(format!(
+                    "Malformed dependency rule '{}={}'. Expected 'requires:KEY=VALUE => OTHER_KEY[=VALUE]'.",
+                    entry.key, entry.rule
+                ))),
+            }
+        }
+    }
+    let is_dependency_key = |key: &str| {
+        dependency_rules
+            .iter()
+            .any(|r| r.antecedent_key == key || r.consequent_key == key)
+    };
+
+    // Ordering rules live under the `order:` key; keys they reference also count as known.
+    let mut order_rules = Vec::new();
+    for entry in schema.iter().filter(|entry| entry.key == "order:") {
+        match parse_order_rule(&entry.rule) {
+            Some(rule) => order_rules.push(OrderRule { description: entry.description.clone(), severity: entry.severity, ..rule }),
+            None => findings.push(Finding::warning(format!(
+                "Malformed ordering rule 'order: {}'. Expected 'order: KEY before OTHER_KEY'.",
+                entry.rule
+            ))),
+        }
+    }
+    let is_order_key = |key: &str| order_rules.iter().any(|r| r.first == key || r.second == key);
+
+    // Check for keys in config that are not in schema (potential unknown/unmanaged settings).
+    // Under --strict-schema every setting must be sanctioned by the schema.
+    for entry in config {
+        let config_key = &entry.key;
+        if !schema_map.contains_key(config_key) && !is_dependency_key(config_key) && !is_order_key(config_key) {
+            let finding = if strict_schema {
+                Finding::new(
+                    Severity::Critical,
+                    format!(
+                        "Config key '{}' is not sanctioned by the schema (--strict-schema requires every key to have a rule).",
+                        config_key
+                    ),
+                )
+                .for_rule("strict_schema")
+            } else {
+                Finding::warning(format!(
+                    "Config key '{}' not found in schema. Consider defining its security posture.",
+                    config_key
+                )).for_rule("unknown_key")
+            };
+            findings.push(finding.at(entry.path.clone()));
+        }
+    }
+
+    // Basic validation: iterate through schema rules and apply them to config
+    let mut wordlists = WordlistCache::new();
+    for entry in schema {
+        // Dependency and ordering rules are evaluated separately below.
+        if entry.key.starts_with("requires:") || entry.key == "order:" {
+            continue;
+        }
+
+        let first_new = findings.len();
+        apply_rule(&entry.key, &entry.rule, config, &mut wordlists, base_dir, &mut findings);
+        let path = key_path(&entry.key, config);
+        let value = config.iter().find(|e| e.key == entry.key).map(|e| e.value.as_str());
+        let message = entry.render_message(value);
+        for finding in &mut findings[first_new..] {
+            if let Some(message) = &message {
+                finding.message = message.clone();
+            }
+            if let Some(severity) = entry.severity {
+                finding.severity = severity;
+            }
+            finding.describe(entry.description.as_deref());
+            finding.path = path.clone();
+        }
+    }
+
+    // Global value length limit; keys with their own max_value_length rule were checked above.
+    findings.extend(check_value_lengths(config, schema, max_value_length));
+
+    // Relational validation: check each dependency rule against the config.
+    findings.extend(dependency_rules.iter().filter_map(|rule| check_dependency(rule, config)));
+
+    // Structural validation: check that ordered keys appear in the required sequence.
+    findings.extend(order_rules.iter().filter_map(|rule| check_order(rule, config)));
+
+    findings
+}
+
+/// Confidence that a key `name` (lowercased) refers to `word`: 90 if the word is a whole part of
+/// the name (`password`, `admin_password`, `db-password`), 60 if it is only embedded in a longer
+/// word (`passwordless_login`), or `None` if it does not occur. `suffix_only` requires the word
+/// to end the name, as for `url` in `callback_url`.
+fn name_confidence(name: &str, word: &str, suffix_only: bool) -> Option<u8> {
+    let is_boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+    let matches: Vec<usize> = name
+        .match_indices(word)
+        .map(|(start, _)| start)
+        .filter(|start| !suffix_only || start + word.len() == name.len())
+        .collect();
+    let whole = matches
+        .iter()
+        .any(|&start| is_boundary(name[..start].chars().next_back()) && is_boundary(name[start + word.len()..].chars().next()));
+    if whole {
+        Some(90)
+    } else {
+        (!matches.is_empty()).then_some(60)
+    }
+}
+
+/// Picks the schema rule that `--builtin-rules` applies to a key, from its last path segment:
+/// password keys get `no_default_password`, debug flags get `false`, and keys ending in `url`,
+/// `uri` or `endpoint` get `secure_url`. Returns the rule with the confidence of the name match.
+fn builtin_rule(path: &[String]) -> Option<(&'static str, u8)> {
+    let name = path.last()?.to_lowercase();
+    let best = |words: &[&str], suffix_only: bool| words.iter().filter_map(|word| name_confidence(&name, word, suffix_only)).max();
+    if let Some(confidence) = best(&["password", "passwd"], false) {
+        Some(("no_default_password", confidence))
+    } else if let Some(confidence) = best(&["debug"], false) {
+        Some(("false", confidence))
+    } else {
+        best(&["url", "uri", "endpoint"], true).map(|confidence| ("secure_url", confidence))
+    }
+}
+
+/// Minimum length of a value checked by the `--builtin-rules` entropy heuristic.
+const ENTROPY_MIN_LENGTH: usize = 16;
+
+/// Shannon entropy (bits per character) from which a value is reported as a possible secret.
+/// Prose and identifiers stay well below it; random tokens, keys and base64 blobs exceed it.
+const ENTROPY_THRESHOLD: f64 = 3.5;
+
+/// Returns the Shannon entropy of `value` in bits per character.
+fn shannon_entropy(value: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in value.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let length = value.chars().count() as f64;
+    counts.values().map(|&count| count as f64 / length).map(|p| -p * p.log2()).sum()
+}
+
+/// Reports a value that looks like a hard-coded secret: at least `ENTROPY_MIN_LENGTH`
+/// characters with no whitespace, not a URL, and with an entropy of at least
+/// `ENTROPY_THRESHOLD`. Confidence rises from 50 at the threshold to 100 at 5 bits per character.
+fn check_entropy(entry: &ConfigEntry) -> Option<Finding> {
+    let value = &entry.value;
+    if value.chars().count() < ENTROPY_MIN_LENGTH || value.contains(char::is_whitespace) || value.contains("://") {
+        return None;
+    }
+    let entropy = shannon_entropy(value);
+    if entropy < ENTROPY_THRESHOLD {
+        return None;
+    }
+    let confidence = (50.0 + (entropy - ENTROPY_THRESHOLD) / (5.0 - ENTROPY_THRESHOLD) * 50.0).min(100.0) as u8;
+    Some(
+        Finding::warning(format!(
+            "Possible hard-coded secret: the value of '{}' looks random ({:.2} bits of entropy per character). Move it to a secret store.",
+            entry.key, entropy
+        )).for_rule("entropy")
+        .at(entry.path.clone())
+        .with_confidence(confidence),
+    )
+}
+
+/// Returns the permission bits of `path` if it is readable by all users.
+#[cfg(unix)]
+fn world_readable_mode(path: &str) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path).ok()?.permissions().mode() & 0o777;
+    (mode & 0o004 != 0).then_some(mode)
+}
+
+/// File permissions are only checked on Unix.
+#[cfg(not(unix))]
+fn world_readable_mode(_path: &str) -> Option<u32> {
+    None
+}
+
+/// Runs the `--builtin-rules` heuristics: the schema rule detectors chosen by key name (see
+/// `builtin_rule`), a check for random-looking values (see `check_entropy`), and a check that
+/// files holding secret-looking keys are not world-readable. Findings from the rule detectors
+/// are marked `(built-in rule)`. Each finding's confidence reflects how strongly it matched.
+fn builtin_findings(config: &[ConfigEntry], config_path: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut wordlists = WordlistCache::new();
+    let mut seen_keys = HashSet::new();
+    let mut checked_files = HashSet::new();
+    for entry in config {
+        // apply_rule checks a key's first occurrence, so each key is checked once.
+        if !seen_keys.insert(entry.key.as_str()) {
+            continue;
+        }
+        if let Some((rule, confidence)) = builtin_rule(&entry.path) {
+            let first_new = findings.len();
+            // Built-in rules never check paths, so there is no directory to resolve them against.
+            apply_rule(&entry.key, rule, config, &mut wordlists, Path::new(""), &mut findings);
+            for finding in &mut findings[first_new..] {
+                finding.describe(Some("built-in rule"));
+                finding.path = entry.path.clone();
+                finding.confidence = confidence;
+            }
+        }
+        findings.extend(check_entropy(entry));
+
+        let name = entry.path.last().map(|n| n.to_lowercase()).unwrap_or_default();
+        let Some(confidence) = SECRET_KEY_PATTERNS.iter().filter_map(|pattern| name_confidence(&name, pattern, false)).max() else {
+            continue;
+        };
+        let file = entry.file.as_deref().unwrap_or(config_path);
+        if !checked_files.insert(file) {
+            continue; // Each file is reported at most once, for its first secret.
+        }
+        if let Some(mode) = world_readable_mode(file) {
+            findings.push(
+                Finding::warning(format!(
+                    "World-readable secret: '{}' is stored in {}, which is readable by all users (mode {:04o}). Restrict access, e.g. with 'chmod 600'.",
+                    entry.key, file, mode
+                ))
+                .for_rule("world_readable")
+                .at(entry.path.clone())
+                .with_confidence(confidence),
+            );
+        }
+    }
+    findings
+}
+
+/// Encodes a string as a JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Renders a finding as a single-line JSON object. `key` is the dotted path (or null for
+/// file-level findings) and `path` the same path as an array of segments.
+fn finding_to_json(finding: &Finding, explain: bool) -> String {
+    let key = if finding.path.is_empty() { "null".to_string() } else { json_string(&finding.path.join(".")) };
+    let segments: Vec<String> = finding.path.iter().map(|segment| json_string(segment)).collect();
+    let rule = finding.rule.map(json_string).unwrap_or_else(|| "null".to_string());
+    let explanation = match finding.explanation() {
+        Some(text) if explain => format!(", \"explanation\": {}", json_string(text)),
+        _ => String::new(),
+    };
+    format!(
+        "{{\"severity\": {}, \"key\": {}, \"path\": [{}], \"rule\": {}, \"confidence\": {}, \"message\": {}{}}}",
+        json_string(finding.severity.name()),
+        key,
+        segments.join(", "),
+        rule,
+        finding.confidence,
+        json_string(&finding.message),
+        explanation
+    )
+}
+
+/// Weight of a finding of each severity in the `--score` risk score.
+fn severity_weight(severity: Severity) -> u64 {
+    match severity {
+        Severity::Critical => 10,
+        Severity::Warning => 3,
+        Severity::Info => 1,
+    }
+}
+
+/// The `--score` risk score, in hundredths: the sum over all findings of the severity weight
+/// times the confidence percentage. Integer arithmetic keeps it exact and independent of the
+/// order of the findings, so the same findings always give the same score.
+fn risk_score(findings: &[Finding]) -> u64 {
+    findings.iter().map(|f| severity_weight(f.severity) * f.confidence as u64).sum()
+}
+
+/// Formats a score in hundredths with two decimals, e.g. `2350` as `23.50`.
+fn format_score(hundredths: u64) -> String {
+    format!("{}.{:02}", hundredths / 100, hundredths % 100)
+}
+
+/// The identity of a finding for `--baseline-report`: its dotted key and its rule, as written in
+/// a JSON report (`null` for either if absent). The message is left out so that rewording it does
+/// not turn known findings into new ones.
+fn baseline_key(finding: &Finding) -> (String, String) {
+    let key = if finding.path.is_empty() { "null".to_string() } else { finding.path.join(".") };
+    (key, finding.rule.unwrap_or("null").to_string())
+}
+
+/// Reads the (key, rule) pairs of the findings in a saved `-f json` report. Keys and rules are
+/// read as written, so a JSON `null` becomes `"null"`, matching `baseline_key`.
+fn read_baseline_report(path: &str, verbose: bool) -> HashSet<(String, String)> {
+    let content = read_file_content(path);
+    let entries = formats::parse_json_report(&content).unwrap_or_else(|message| {
+        fatal_error(&format!("Invalid baseline report {}: {}", path, message));
+        Vec::new()
+    });
+    // Each finding's fields are entries such as `3.key` and `3.rule`, under `findings.` in a
+    // report written with --score.
+    let mut findings: Vec<(String, String)> = Vec::new();
+    for entry in entries {
+        let path = match entry.path.split_first() {
+            Some((first, rest)) if first == "findings" => rest,
+            _ => entry.path.as_slice(),
+        };
+        let [index, field] = path else {
+            continue; // Nested values such as `3.path.0`
+        };
+        let Ok(index) = index.parse::<usize>() else {
+            continue;
+        };
+        if findings.len() <= index {
+            findings.resize(index + 1, ("null".to_string(), "null".to_string()));
+        }
+        match field.as_str() {
+            "key" => findings[index].0 = entry.value,
+            "rule" => findings[index].1 = entry.value,
+            _ => {}
+        }
+    }
+    info(&format!("Loaded {} finding(s) from baseline report {}.", findings.len(), path), verbose);
+    findings.into_iter().collect()
+}
+
+/// Opens the `-o` file (copied to stdout under `--tee`), or stdout if no file was given.
+fn open_output(output_path: Option<&str>, tee: bool) -> Box<dyn Write> {
+    match output_path {
+        Some(path) => {
+            let file = fs::File::create(path).unwrap_or_else(|e| {
+                io_error(&format!("Failed to create output file {}: {}", path, e));
+                process::exit(1); // Diverging function, never returns
+            });
+            if tee {
+                Box::new(TeeWriter { file: Some(file), file_path: path.to_string(), stdout: Some(io::stdout()) })
+            } else {
+                Box::new(file)
+            }
+        }
+        None => Box::new(io::stdout()),
+    }
+}
+
+/// Formats a parsed value so that `parse_config` reads it back unchanged. Values are written
+/// bare unless that would lose something: empty values, surrounding whitespace, a value that is
+/// itself wrapped in quotes, and internal tabs or runs of spaces (which `--tabs-to-spaces` would
+/// collapse) are wrapped in double quotes. `clean_value` strips exactly one pair of quotes, so no
+/// escaping is needed.
+fn canonical_value(value: &str) -> String {
+    let wrapped_in_quotes = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"')) || (value.starts_with('\'') && value.ends_with('\'')));
+    if value.is_empty() || value.trim() != value || wrapped_in_quotes || value.contains('\t') || value.contains("  ") {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats a key path for the canonical form, quoting segments that would not read back as the
+/// same bare segment, such as empty segments or ones containing spaces, '.', '=' or quotes.
+fn canonical_key(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| {
+            let needs_quotes = segment.is_empty()
+                || segment.contains(|c: char| c.is_whitespace() || matches!(c, '.' | '=' | '"' | '\'' | '#' | '[' | ']'));
+            match (needs_quotes, segment.contains('"')) {
+                (false, _) => segment.clone(),
+                (true, false) => format!("\"{}\"", segment),
+                (true, true) => format!("'{}'", segment),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Writes the config in canonical form: one `dotted.key = value` line per entry, sorted by key
+/// path, with tables flattened into dotted keys and comments dropped. Entries with the same key
+/// keep their original relative order, since which one wins depends on the consumer.
+fn write_canonical(output_path: Option<&str>, tee: bool, config: &[ConfigEntry]) {
+    let mut writer = open_output(output_path, tee);
+    let mut entries: Vec<&ConfigEntry> = config.iter().collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path)); // Stable: duplicates stay in file order
+    for entry in entries {
+        writeln!(writer, "{} = {}", canonical_key(&entry.path), canonical_value(&entry.value)).unwrap_or_else(|e| {
+            io_error(&format!("Failed to write canonical config: {}", e));
+        });
+    }
+}
+
+/// Config files read for `--show-source` snippets, keyed by path. `None` marks an unreadable file.
+type SourceCache = HashMap<String, Option<Vec<String>>>;
+
+/// Renders the `--show-source` snippet for a finding: its config line, marked with `>`, with one
+/// line of context either side. The line is the finding's pinned source, or else the first
+/// occurrence of its key. Returns `None` for findings with no config line, such as missing keys.
+fn source_snippet(finding: &Finding, config: &[ConfigEntry], config_path: &str, cache: &mut SourceCache) -> Option<String> {
+    let (file, line) = match &finding.source {
+        Some(source) => source.clone(),
+        None if !finding.path.is_empty() => {
+            let entry = config.iter().find(|e| e.path == finding.path)?;
+            (entry.file.clone(), entry.line)
+        }
+        None => return None,
+    };
+    let file = file.unwrap_or_else(|| config_path.to_string());
+    let lines = cache
+        .entry(file.clone())
+        .or_insert_with(|| fs::read_to_string(&file).ok().map(|c| c.lines().map(str::to_string).collect()))
+        .as_ref()
+        .filter(|lines| line >= 1 && line <= lines.len())?;
+    let first = line.saturating_sub(1).max(1);
+    let last = (line + 1).min(lines.len());
+    let width = last.to_string().len();
+    let mut snippet = format!("    --> {}:{}", file, line);
+    for number in first..=last {
+        let marker = if number == line { '>' } else { '|' };
+        snippet.push_str(&format!("\n    {:>width$} {} {}", number, marker, lines[number - 1], width = width));
+    }
+    Some(snippet)
+}
+
+/// Writes a report of `findings` to `writer` (the `-o` file, stdout or the `--output-critical` file), in the `-f` format.
+/// With `source`, the config and its path, each finding is followed by its `--show-source` snippet.
+/// With `--explain`, each finding is followed by its rationale, where one exists.
+/// With `score`, the `--score` risk score follows the findings.
+fn write_report(mut writer: Box<dyn Write>, options: &Options, findings: &[Finding], source: Option<(&[ConfigEntry], &str)>, score: Option<u64>) {
+    let (format, explain, verbose) = (options.format, options.explain, options.verbose);
+
+    if let OutputFormat::Count(min_severity) = format {
+        // Count mode emits only the integer so scripts can capture it cheaply.
+        let count = findings
+            .iter()
+            .filter(|f| min_severity.is_none_or(|min| f.severity >= min))
+            .count();
+        writeln!(writer, "{}", count).unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to report: {}", e));
+        });
+        return;
+    }
+
+    if format == OutputFormat::Json {
+        let rows: Vec<String> = findings.iter().map(|finding| finding_to_json(finding, explain)).collect();
+        let json = if rows.is_empty() { "[]".to_string() } else { format!("[\n  {}\n]", rows.join(",\n  ")) };
+        // With --score, the array is wrapped in an object that also carries the score.
+        let json = match score {
+            Some(score) => format!("{{\"risk_score\": {}, \"findings\": {}}}", format_score(score), json),
+            None => json,
+        };
+        writeln!(writer, "{}", json).unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to report: {}", e));
+        });
+        return;
+    }
+
+    if findings.is_empty() {
+        writeln!(writer, "No security warnings or misconfigurations found.").unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to report: {}", e));
+        });
+        info("Configuration is compliant with the provided schema.", verbose);
+    } else {
+        writeln!(writer, "Security Linter Report:").unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to report: {}", e));
+        });
+        let mut cache = SourceCache::new();
+        for finding in findings {
+            // Certain findings are printed as before; heuristic ones show their confidence.
+            let confidence = if finding.confidence < 100 { format!(" [confidence {}]", finding.confidence) } else { String::new() };
+            writeln!(writer, "- {}{}", finding.message, confidence).unwrap_or_else(|e| {
+                io_error(&format!("Failed to write to report: {}", e));
+            });
+            if let Some(text) = finding.explanation().filter(|_| explain) {
+                writeln!(writer, "  Why: {}", text).unwrap_or_else(|e| {
+                    io_error(&format!("Failed to write to report: {}", e));
+                });
+            }
+            let snippet = source.and_then(|(config, config_path)| source_snippet(finding, config, config_path, &mut cache));
+            if let Some(snippet) = snippet {
+                writeln!(writer, "{}", snippet).unwrap_or_else(|e| {
+                    io_error(&format!("Failed to write to report: {}", e));
+                });
+            }
+        }
+        info(&format!("Found {} potential security issues.", findings.len()), verbose);
+    }
+    if let Some(score) = score {
+        writeln!(writer, "Risk score: {}", format_score(score)).unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to report: {}", e));
+        });
+    }
+}
+
+/// The main entry point for the application.
+/// Parses arguments, reads config and schema, validates the config, and reports findings.
+fn main() {
+    let options = parse_args();
+    let (config_path, verbose) = (&options.config_path, options.verbose);
+
+    let (mut config, config_content) = load_config(config_path, &options);
+    if !options.layers.is_empty() {
+        // With several files, every entry names its file, so findings show which layer to fix.
+        let label = |entries: &mut Vec<ConfigEntry>, path: &str| {
+            for entry in entries.iter_mut().filter(|e| e.file.is_none()) {
+                entry.file = Some(path.to_string());
+            }
+        };
+        label(&mut config, config_path);
+        let mut overridden: HashMap<String, Vec<String>> = HashMap::new();
+        for layer_path in &options.layers {
+            let (mut layer, _) = load_config(layer_path, &options);
+            label(&mut layer, layer_path);
+            config = merge_layer(config, layer, &mut overridden);
+        }
+        info(&format!("Merged {} layer(s) into {} effective entries.", options.layers.len() + 1, config.len()), verbose);
+        for entry in &config {
+            let overrides = overridden
+                .get(&entry.key)
+                .map(|previous| format!(" (overrides {})", previous.join(", ")))
+                .unwrap_or_default();
+            info(&format!("'{}' is set by {}{}.", entry.key, entry.location(), overrides), verbose);
+        }
+    }
+    info("Configuration loaded and parsed.", verbose);
+
+    if options.canonicalize {
+        info(&format!("Writing {} entries in canonical form.", config.len()), verbose);
+        write_canonical(options.output_path.as_deref(), options.tee, &config);
+        if TEE_FAILED.load(Ordering::Relaxed) {
+            io_error("--tee: one output destination failed, so its output is incomplete (see warning above).");
+        }
+        process::exit(0);
+    }
+
+    let profile = options.profile.as_deref();
+    let mut schema = Vec::new();
+    let mut profiles: Vec<String> = Vec::new();
+    if let Some(schema_path) = &options.schema_path {
+        info(&format!("Loading schema from: {}", schema_path), verbose);
+        let (schema_content, _) = read_with_includes(schema_path);
+        let (rules, names) = parse_schema(&schema_content, profile);
+        schema.extend(rules);
+        profiles.extend(names);
+    }
+    if options.inline_schema {
+        info("Loading inline schema annotations from the configuration.", verbose);
+        let (inline_rules, names) = parse_inline_schema(&config_content, profile);
+        info(&format!("Found {} inline schema rule(s).", inline_rules.len()), verbose);
+        schema.extend(inline_rules);
+        profiles.extend(names);
+    }
+    if !profiles.is_empty() {
+        info(&format!("Schema profiles: {}.", profiles.join(", ")), verbose);
+    }
+    if let Some(name) = profile {
+        if profiles.iter().any(|p| p == name) {
+            info(&format!("Applying profile '{}' on top of the unscoped rules.", name), verbose);
+        } else {
+            warn(&format!(
+                "Profile '{}' is not defined in the schema, so only unscoped rules apply. Defined profiles: {}.",
+                name,
+                if profiles.is_empty() { "none".to_string() } else { profiles.join(", ") }
+            ));
+        }
+    }
+    info("Schema loaded and parsed.", verbose);
 
     info("Starting configuration validation...", verbose);
-    let warnings = validate_config(&config, &schema, verbose);
+    let mut findings = if options.schema_path.is_some() || options.inline_schema {
+        // Relative paths in the config are usually relative to the config file itself.
+        let base_dir = match &options.base_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => Path::new(config_path).parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        info(&format!("Resolving relative paths against: {:?}", base_dir), verbose);
+        validate_config(&config, &schema, options.strict_schema, options.max_value_length, &base_dir, verbose)
+    } else {
+        // Without a schema no key can be unknown; only the schema-independent checks apply.
+        let mut findings = check_case_duplicates(&config);
+        findings.extend(check_value_lengths(&config, &[], options.max_value_length));
+        findings
+    };
+    if options.builtin_rules {
+        info("Applying built-in best-practice rules.", verbose);
+        findings.extend(builtin_findings(&config, config_path));
+    }
+    if options.strict {
+        // Under --strict, informational findings are escalated to warnings.
+        for finding in findings.iter_mut().filter(|f| f.severity == Severity::Info) {
+            finding.severity = Severity::Warning;
+        }
+    }
     info("Validation complete.", verbose);
 
+    // Unlike --min-severity, --min-confidence drops findings entirely, exit status included.
+    let before_confidence = findings.len();
+    findings.retain(|f| f.confidence >= options.min_confidence);
+    if findings.len() < before_confidence {
+        info(
+            &format!(
+                "{} finding(s) below --min-confidence {} were dropped.",
+                before_confidence - findings.len(),
+                options.min_confidence
+            ),
+            verbose,
+        );
+    }
+
+    if let Some(path) = &options.baseline_report {
+        let baseline = read_baseline_report(path, verbose);
+        let before_baseline = findings.len();
+        findings.retain(|f| !baseline.contains(&baseline_key(f)));
+        info(
+            &format!("{} finding(s) already in baseline report {} were dropped.", before_baseline - findings.len(), path),
+            verbose,
+        );
+    }
+
+    // The exit status and score are decided on all findings; --min-severity only controls what
+    // is printed.
+    let score = options.score.then(|| risk_score(&findings));
+    let should_fail = match (score, options.fail_above) {
+        (Some(score), Some(limit)) => {
+            if score > limit {
+                warn(&format!("Risk score {} is above --fail-above {}.", format_score(score), format_score(limit)));
+            }
+            score > limit
+        }
+        _ => findings.iter().any(|f| f.severity >= options.exit_on),
+    };
+    let total_findings = findings.len();
+    findings.retain(|f| f.severity >= options.min_severity);
+    if findings.len() < total_findings {
+        info(
+            &format!(
+                "{} finding(s) below --min-severity {:?} were not reported.",
+                total_findings - findings.len(),
+                options.min_severity
+            ),
+            verbose,
+        );
+    }
+
     info("Generating report...", verbose);
-    write_report(output_path.as_deref(), &warnings, verbose);
+    let source = options.show_source.then_some((config.as_slice(), config_path.as_str()));
+    let report = open_output(options.output_path.as_deref(), options.tee);
+    match &options.output_critical {
+        Some(path) => {
+            // Each destination gets a complete report of its own findings.
+            let (critical, other): (Vec<Finding>, Vec<Finding>) =
+                findings.into_iter().partition(|f| f.severity == Severity::Critical);
+            info(&format!("Writing {} critical finding(s) to {}.", critical.len(), path), verbose);
+            write_report(report, &options, &other, source, score);
+            write_report(open_output(Some(path), false), &options, &critical, source, None);
+        }
+        None => write_report(report, &options, &findings, source, score),
+    }
+    if TEE_FAILED.load(Ordering::Relaxed) {
+        io_error("--tee: one output destination failed, so its output is incomplete (see warning above).");
+    }
     info("Report generated successfully.", verbose);
 
-    if !warnings.is_empty() {
-        process::exit(1); // Exit with error if warnings were found
+    if should_fail {
+        process::exit(1); // Exit with error if findings reached the --exit-on severity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The value parsed for `key`, which must be present exactly once.
+    fn value_of<'a>(entries: &'a [ConfigEntry], key: &str) -> &'a str {
+        let matching: Vec<&ConfigEntry> = entries.iter().filter(|e| e.key == key).collect();
+        assert_eq!(matching.len(), 1, "entries for {}: {:?}", key, entries);
+        &matching[0].value
+    }
+
+    /// The lines of `sample_input/tabbed_config.toml` that hold values.
+    const TABBED: &str = "log_level\t=\tINFO\nallowed_ciphers\t=\tTLS_AES_256_GCM_SHA384\t\tTLS_CHACHA20_POLY1305_SHA256\nmotd = \"Authorized\tuse only\"\n";
+
+    #[test]
+    fn tabs_to_spaces_collapses_unquoted_values() {
+        let entries = parse_config(TABBED, true);
+        assert_eq!(value_of(&entries, "log_level"), "INFO");
+        assert_eq!(value_of(&entries, "allowed_ciphers"), "TLS_AES_256_GCM_SHA384 TLS_CHACHA20_POLY1305_SHA256");
+        assert_eq!(value_of(&entries, "motd"), "Authorized\tuse only");
+        let entries = parse_config("spaced =  a \t \t b   c \n", true);
+        assert_eq!(value_of(&entries, "spaced"), "a b c");
+    }
+
+    #[test]
+    fn values_keep_internal_tabs_by_default() {
+        let entries = parse_config(TABBED, false);
+        assert_eq!(value_of(&entries, "log_level"), "INFO");
+        assert_eq!(value_of(&entries, "allowed_ciphers"), "TLS_AES_256_GCM_SHA384\t\tTLS_CHACHA20_POLY1305_SHA256");
+        assert_eq!(value_of(&entries, "motd"), "Authorized\tuse only");
+    }
+
+    #[test]
+    fn quoted_keys_keep_spaces_dots_and_equals() {
+        let content = "\"display name\" = \"Payments API\"\n\"a=b\" = enabled\nserver.\"listen address\" = 0.0.0.0\n'ssh \"legacy\" mode' = true\n\n[\"tls.settings\"]\n\"min version\" = TLSv1.0\n";
+        let entries = parse_config(content, false);
+        let parsed: Vec<(Vec<&str>, &str)> = entries
+            .iter()
+            .map(|e| (e.path.iter().map(String::as_str).collect(), e.value.as_str()))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                (vec!["display name"], "Payments API"),
+                (vec!["a=b"], "enabled"),
+                (vec!["server", "listen address"], "0.0.0.0"),
+                (vec!["ssh \"legacy\" mode"], "true"),
+                (vec!["tls.settings", "min version"], "TLSv1.0"),
+            ]
+        );
+        assert_eq!(value_of(&entries, "tls.settings.min version"), "TLSv1.0");
+        assert_eq!(entries[4].line, 7);
+    }
+
+    #[test]
+    fn unclosed_quoted_key_is_skipped() {
+        let entries = parse_config("\"open key = x\n\"=\" = y\n", false);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, ["="]);
+        assert_eq!(entries[0].value, "y");
     }
 }
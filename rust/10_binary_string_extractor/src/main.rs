@@ -14,27 +14,152 @@
 // - CLI-Only Interface: Prioritizes the string extraction logic.
 // - One Tool = One Problem: Solely focused on extracting strings from binary data.
 
+use std::collections::HashMap;
 use std::env;
-use std::fs::File;
-use std::io::{self, BufReader, Read, Write};
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+mod signature;
+use signature::{HeldMatches, Signature};
 
 // Constants for output formatting
 const INFO_PREFIX: &str = "[INFO] ";
 const ERROR_PREFIX: &str = "[ERROR] ";
+const WARNING_PREFIX: &str = "[WARNING] ";
+
+// Tool identity reported by --version
+const TOOL_NAME: &str = "Binary String Extractor";
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // --- Shared Abstractions ---
 // Consistent CLI Argument Parsing: Uses `std::env::args` for CLI flags.
-// Standardized Error Handling & Exit Codes: Exits with 0 on success, non-zero on error.
-// Unified Logging/Output Format: Uses INFO, ERROR prefixes.
+// Standardized Error Handling & Exit Codes: Exits with 0 on success, 1 on errors, and 4 on I/O
+//   errors (see EXIT_IO_ERROR).
+// Unified Logging/Output Format: Uses INFO, WARNING, ERROR prefixes.
 
 /// Prints an error message to stderr and exits the program with a non-zero status code.
 fn fatal_error(message: &str) -> ! {
     eprintln!("{}{}", ERROR_PREFIX, message);
+    discard_partial_output();
     process::exit(1);
 }
 
+/// Exit status for I/O failures, such as an input that cannot be read or an output that cannot be
+/// written, so that scripts can tell them apart from exit status 1.
+const EXIT_IO_ERROR: i32 = 4;
+
+/// Prints an I/O error message to stderr and exits with `EXIT_IO_ERROR`.
+fn io_error(message: &str) -> ! {
+    eprintln!("{}{}", ERROR_PREFIX, message);
+    discard_partial_output();
+    process::exit(EXIT_IO_ERROR);
+}
+
+/// Path of the in-progress `-o` file, if any. `fatal_error` and `io_error` remove it so that a
+/// failed run never leaves a truncated file behind.
+static PARTIAL_OUTPUT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Removes the in-progress output file registered by `create_output`, if any.
+fn discard_partial_output() {
+    // try_lock: never block (or deadlock) on the way out of the process.
+    if let Ok(mut partial) = PARTIAL_OUTPUT.try_lock() {
+        if let Some(path) = partial.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Creates a buffered writer for the `-o` file. Output goes to `<FILE>.partial` and is only
+/// renamed to `<FILE>` by `finish_output` once everything has been written and flushed.
+fn create_output(path: &Path) -> BufWriter<File> {
+    let mut partial_name = path.file_name().unwrap_or_default().to_os_string();
+    partial_name.push(".partial");
+    let partial_path = path.with_file_name(partial_name);
+    let file = File::create(&partial_path).unwrap_or_else(|e| {
+        io_error(&format!("Failed to create output file {:?}: {}", partial_path, e));
+    });
+    *PARTIAL_OUTPUT.lock().unwrap_or_else(|e| e.into_inner()) = Some(partial_path);
+    BufWriter::new(file)
+}
+
+/// Set when a `--tee` destination fails. The run still finishes on the other destination,
+/// but `finish_output` then exits non-zero.
+static TEE_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Writer for `--tee`: copies all output to the `-o` file and to stdout. A destination that
+/// fails is dropped with a warning, so the other one still receives the complete output.
+struct TeeWriter {
+    file: Option<BufWriter<File>>,
+    stdout: Option<io::Stdout>,
+}
+
+impl TeeWriter {
+    /// Applies `op` to each remaining destination, dropping any that fail. Errors only once
+    /// both destinations are gone.
+    fn each(&mut self, mut op: impl FnMut(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            if let Err(e) = op(file) {
+                warn(&format!("--tee: writing to the output file failed: {}", e));
+                self.file = None;
+                discard_partial_output(); // Never leave a truncated file behind.
+                TEE_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        if let Some(stdout) = &mut self.stdout {
+            if let Err(e) = op(stdout) {
+                warn(&format!("--tee: writing to stdout failed: {}", e));
+                self.stdout = None;
+                TEE_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        if self.file.is_none() && self.stdout.is_none() {
+            return Err(io::Error::other("both --tee destinations failed"));
+        }
+        Ok(())
+    }
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.each(|writer| writer.write_all(buf)).map(|()| buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.each(|writer| writer.flush())
+    }
+}
+
+/// Flushes the output and, for `-o` files, moves the completed file into place.
+/// Exits non-zero if a `--tee` destination failed along the way.
+fn finish_output(mut writer: Box<dyn Write>, output_path: Option<&Path>) {
+    writer.flush().unwrap_or_else(|e| {
+        io_error(&format!("Failed to write to output: {}", e));
+    });
+    drop(writer); // Close the file before renaming it.
+
+    let partial = PARTIAL_OUTPUT.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let (Some(partial_path), Some(path)) = (partial, output_path) {
+        if let Err(e) = fs::rename(&partial_path, path) {
+            let _ = fs::remove_file(&partial_path);
+            io_error(&format!("Failed to move completed output into place at {:?}: {}", path, e));
+        }
+    }
+    if TEE_FAILED.load(Ordering::Relaxed) {
+        io_error("--tee: one output destination failed, so its output is incomplete (see warning above).");
+    }
+}
+
+/// Prints a warning message to stderr.
+fn warn(message: &str) {
+    eprintln!("{}{}", WARNING_PREFIX, message);
+}
+
 /// Prints an informational message to stderr if verbose mode is enabled.
 fn info(message: &str, verbose: bool) {
     if verbose {
@@ -42,14 +167,202 @@ fn info(message: &str, verbose: bool) {
     }
 }
 
-/// Parses command-line arguments.
-/// Returns (input_file_path, output_file_path, min_length, verbose)
-fn parse_args() -> (PathBuf, Option<PathBuf>, usize, bool) {
-    let args: Vec<String> = env::args().collect();
+/// Parses a byte bound given in hexadecimal (`0x20`) or decimal (`32`) notation.
+fn parse_byte_bound(value: &str, flag: &str) -> u8 {
+    let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => value.parse::<u8>(),
+    };
+    parsed.unwrap_or_else(|_| {
+        fatal_error(&format!(
+            "Invalid value for {}: '{}'. Must be a byte value between 0 and 255 (decimal or 0x-prefixed hex).",
+            flag, value
+        ));
+    })
+}
+
+/// Parses a byte count such as `4096`, `64K`, `16M` or `2G` (binary multiples). Zero is rejected.
+fn parse_byte_size(value: &str, flag: &str) -> u64 {
+    let (digits, multiplier) = match value.to_ascii_uppercase().chars().last() {
+        Some('K') => (&value[..value.len() - 1], 1 << 10),
+        Some('M') => (&value[..value.len() - 1], 1 << 20),
+        Some('G') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| {
+            fatal_error(&format!(
+                "Invalid value for {}: '{}'. Must be a positive byte count, optionally with a K, M or G suffix.",
+                flag, value
+            ));
+        })
+}
+
+/// Command-line options controlling the extraction.
+struct Options {
+    input_path: PathBuf,
+    output_path: Option<PathBuf>,
+    tee: bool,
+    min_length: usize,
+    min_byte: u8,
+    max_byte: u8,
+    record_delimiter: Option<u8>,
+    recursive: bool,
+    filter: Option<String>,
+    max_strings: Option<usize>,
+    quiet: bool,
+    encoding: Encoding,
+    /// With UTF-16, also accept characters above U+00FF (--wide-chars).
+    wide_chars: bool,
+    output_encoding: OutputEncoding,
+    format: OutputFormat,
+    offsets: Option<OffsetStyle>,
+    benchmark: bool,
+    length_histogram: bool,
+    detect_mojibake: bool,
+    decode: bool,
+    entropy_min: Option<f64>,
+    max_input_bytes: Option<u64>,
+    head_bytes: Option<u64>,
+    /// Bytes of surrounding input shown on each side of a string with --context-chars.
+    context_chars: Option<u64>,
+    region_analysis: bool,
+    window_size: usize,
+    low_ratio: f64,
+    signature: Option<Signature>,
+    /// Number of files scanned at once with --recursive.
+    threads: usize,
+    /// Offsets read from --offsets-file, in the order given.
+    target_offsets: Option<Vec<u64>>,
+    verbose: bool,
+}
+
+/// Name of the per-user defaults file, read from `$XDG_CONFIG_HOME`, or from `~/.config` when
+/// that is not set.
+const DEFAULTS_FILE_NAME: &str = "binary_string_extractor.conf";
+
+/// Short flags and the long options they stand for, so that a command-line flag overrides its
+/// default however it is spelled.
+const SHORT_FLAGS: &[(&str, &str)] = &[("-i", "input"), ("-o", "output"), ("-m", "min-length"), ("-r", "recursive"), ("-f", "filter"), ("-n", "max-strings"), ("-q", "quiet"), ("-e", "encoding"), ("-t", "radix"), ("-v", "verbose")];
+
+/// Groups of long options that set the same thing or rule each other out. An option from a group
+/// given on the command line drops the defaults for every option in the group.
+const OPTION_GROUPS: &[&[&str]] = &[&["offsets", "radix"], &["region-analysis", "signature", "length-histogram", "offsets-file"]];
+
+/// Returns the path of the defaults file, or `None` if no config directory is known.
+fn defaults_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME").filter(|home| !home.is_empty())?).join(".config"),
+    };
+    Some(dir.join(DEFAULTS_FILE_NAME))
+}
+
+/// Inserts the options of the defaults file between the program name and the command-line
+/// arguments. Each `option = value` line stands for `--option value`; `true` stands for the bare
+/// flag and `false` leaves it out. A flag given on the command line drops every default for the
+/// same option and for the rest of its `OPTION_GROUPS` entry, so the command line wins over the
+/// file and the file over the built-in defaults.
+/// A missing file is not an error, and `--no-defaults` skips the file altogether.
+fn with_defaults(mut args: Vec<String>) -> Vec<String> {
+    if args.iter().any(|arg| arg == "--no-defaults") {
+        args.retain(|arg| arg != "--no-defaults");
+        return args;
+    }
+    let Some(path) = defaults_path() else {
+        return args;
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return args,
+        Err(e) => io_error(&format!("Failed to read defaults file {:?}: {}", path, e)),
+    };
+    let given: Vec<String> = args
+        .iter()
+        .skip(1)
+        .filter_map(|arg| match arg.strip_prefix("--") {
+            Some(long) => Some(long.to_string()),
+            None => SHORT_FLAGS.iter().find(|(short, _)| short == arg).map(|(_, long)| long.to_string()),
+        })
+        .collect();
+    let overridden: Vec<&str> = OPTION_GROUPS
+        .iter()
+        .filter(|group| group.iter().any(|option| given.iter().any(|flag| flag == option)))
+        .flat_map(|group| group.iter().copied())
+        .collect();
+    let mut defaults = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            fatal_error(&format!(
+                "Malformed line {} in defaults file {:?}: expected 'option = value'.",
+                line_index + 1,
+                path
+            ));
+        };
+        let key = key.trim();
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        if given.iter().any(|flag| flag == key) || overridden.contains(&key) || value == "false" {
+            continue;
+        }
+        defaults.push(format!("--{}", key));
+        if value != "true" {
+            defaults.push(value.to_string());
+        }
+    }
+    args.splice(1..1, defaults);
+    args
+}
+
+/// Parses command-line arguments, after those of the defaults file, into an `Options` value.
+fn parse_args() -> Options {
+    options_from_args(with_defaults(env::args().collect()))
+}
+
+/// Parses an argument list, starting with the program name, into an `Options` value.
+fn options_from_args(args: Vec<String>) -> Options {
 
     let mut input_file_path: Option<PathBuf> = None;
     let mut output_file_path: Option<PathBuf> = None;
+    let mut tee = false;
     let mut min_length: usize = 4; // Default minimum string length
+    let mut min_byte: u8 = 0x20; // Default printable range: space ...
+    let mut max_byte: u8 = 0x7E; // ... through tilde
+    let mut record_delimiter: Option<u8> = None;
+    let mut recursive = false;
+    let mut filter: Option<String> = None;
+    let mut max_strings: Option<usize> = None;
+    let mut quiet = false;
+    let mut wide_chars = false;
+    let mut encoding = Encoding::Ascii;
+    let mut output_encoding = OutputEncoding::Utf8;
+    let mut format = OutputFormat::Text;
+    let mut offsets: Option<OffsetStyle> = None;
+    let mut benchmark = false;
+    let mut length_histogram = false;
+    let mut detect_mojibake = false;
+    let mut decode = false;
+    let mut entropy_min: Option<f64> = None;
+    let mut max_input_bytes: Option<u64> = None;
+    let mut head_bytes: Option<u64> = None;
+    let mut context_chars: Option<u64> = None;
+    let mut threads: usize = 1;
+    let mut region_analysis = false;
+    let mut window_size: Option<usize> = None;
+    let mut low_ratio: Option<f64> = None;
+    let mut signature: Option<Signature> = None;
+    let mut offsets_file: Option<PathBuf> = None;
     let mut verbose = false;
 
     // Skip the first argument which is the program name
@@ -85,6 +398,220 @@ fn parse_args() -> (PathBuf, Option<PathBuf>, usize, bool) {
                     fatal_error("Missing value for --min-length");
                 }
             }
+            "--min-byte" => {
+                i += 1;
+                if i < args.len() {
+                    min_byte = parse_byte_bound(&args[i], "--min-byte");
+                } else {
+                    fatal_error("Missing value for --min-byte");
+                }
+            }
+            "--max-byte" => {
+                i += 1;
+                if i < args.len() {
+                    max_byte = parse_byte_bound(&args[i], "--max-byte");
+                } else {
+                    fatal_error("Missing value for --max-byte");
+                }
+            }
+            "--record-delimiter" => {
+                i += 1;
+                if i < args.len() {
+                    record_delimiter = Some(parse_byte_bound(&args[i], "--record-delimiter"));
+                } else {
+                    fatal_error("Missing value for --record-delimiter");
+                }
+            }
+            "-r" | "--recursive" => {
+                recursive = true;
+            }
+            "-f" | "--filter" => {
+                i += 1;
+                if i < args.len() {
+                    filter = Some(args[i].clone());
+                } else {
+                    fatal_error("Missing value for --filter");
+                }
+            }
+            "-n" | "--max-strings" => {
+                i += 1;
+                if i < args.len() {
+                    max_strings = Some(args[i].parse::<usize>().unwrap_or_else(|_| {
+                        fatal_error("Invalid value for --max-strings. Must be a non-negative integer.");
+                    }));
+                } else {
+                    fatal_error("Missing value for --max-strings");
+                }
+            }
+            "-q" | "--quiet" => {
+                quiet = true;
+            }
+            "-e" | "--encoding" => {
+                i += 1;
+                if i < args.len() {
+                    encoding = match args[i].to_lowercase().as_str() {
+                        "ascii" => Encoding::Ascii,
+                        "utf8" | "utf-8" => Encoding::Utf8,
+                        "utf16le" | "utf-16le" => Encoding::Utf16Le,
+                        "utf16be" | "utf-16be" => Encoding::Utf16Be,
+                        other => fatal_error(&format!(
+                            "Invalid value for --encoding: '{}'. Choose 'ascii', 'utf8', 'utf16le' or 'utf16be'.",
+                            other
+                        )),
+                    };
+                } else {
+                    fatal_error("Missing value for --encoding");
+                }
+            }
+            "--wide-chars" => {
+                wide_chars = true;
+            }
+            "--output-encoding" => {
+                i += 1;
+                if i < args.len() {
+                    output_encoding = match args[i].to_lowercase().as_str() {
+                        "utf8" | "utf-8" => OutputEncoding::Utf8,
+                        "original" => OutputEncoding::Original,
+                        other => fatal_error(&format!(
+                            "Invalid value for --output-encoding: '{}'. Choose 'utf8' or 'original'.",
+                            other
+                        )),
+                    };
+                } else {
+                    fatal_error("Missing value for --output-encoding");
+                }
+            }
+            "--format" => {
+                i += 1;
+                if i < args.len() {
+                    format = match args[i].to_lowercase().as_str() {
+                        "text" => OutputFormat::Text,
+                        "csv" => OutputFormat::Csv,
+                        other => fatal_error(&format!("Invalid value for --format: '{}'. Choose 'text' or 'csv'.", other)),
+                    };
+                } else {
+                    fatal_error("Missing value for --format");
+                }
+            }
+            "--offsets" => {
+                offsets = offsets.or(Some(OffsetStyle::PrefixedHex));
+            }
+            "-t" | "--radix" => {
+                i += 1;
+                if i < args.len() {
+                    offsets = Some(match args[i].as_str() {
+                        "d" => OffsetStyle::Decimal,
+                        "o" => OffsetStyle::Octal,
+                        "x" => OffsetStyle::Hex,
+                        other => fatal_error(&format!("Invalid value for --radix: '{}'. Choose 'd', 'o' or 'x'.", other)),
+                    });
+                } else {
+                    fatal_error("Missing value for --radix");
+                }
+            }
+            "--benchmark" => {
+                benchmark = true;
+            }
+            "--length-histogram" => {
+                length_histogram = true;
+            }
+            "--detect-mojibake" => {
+                detect_mojibake = true;
+            }
+            "--decode" => {
+                decode = true;
+            }
+            "--context-chars" => {
+                i += 1;
+                if i < args.len() {
+                    context_chars = Some(args[i].parse::<u64>().ok().filter(|&n| n > 0).unwrap_or_else(|| {
+                        fatal_error("Invalid value for --context-chars. Must be a positive number of bytes.");
+                    }));
+                } else {
+                    fatal_error("Missing value for --context-chars");
+                }
+            }
+            "--signature" => {
+                i += 1;
+                if i < args.len() {
+                    signature = Some(Signature::parse(&args[i]).unwrap_or_else(|e| {
+                        fatal_error(&format!("Invalid --signature '{}': {}", args[i], e));
+                    }));
+                } else {
+                    fatal_error("Missing value for --signature");
+                }
+            }
+            "--tee" => {
+                tee = true;
+            }
+            "--region-analysis" => {
+                region_analysis = true;
+            }
+            "--window-size" => {
+                i += 1;
+                if i < args.len() {
+                    window_size = Some(usize::try_from(parse_byte_size(&args[i], "--window-size")).unwrap_or(usize::MAX));
+                } else {
+                    fatal_error("Missing value for --window-size");
+                }
+            }
+            "--low-ratio" => {
+                i += 1;
+                if i < args.len() {
+                    low_ratio = match args[i].parse::<f64>() {
+                        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Some(ratio),
+                        _ => fatal_error("Invalid value for --low-ratio. Must be a number between 0 and 1, e.g. 0.5."),
+                    };
+                } else {
+                    fatal_error("Missing value for --low-ratio");
+                }
+            }
+            "--entropy-min" => {
+                i += 1;
+                if i < args.len() {
+                    entropy_min = match args[i].parse::<f64>() {
+                        Ok(bits) if bits > 0.0 && bits.is_finite() => Some(bits),
+                        _ => fatal_error("Invalid value for --entropy-min. Must be a positive number of bits per character, e.g. 4.0."),
+                    };
+                } else {
+                    fatal_error("Missing value for --entropy-min");
+                }
+            }
+            "--max-input-bytes" => {
+                i += 1;
+                if i < args.len() {
+                    max_input_bytes = Some(parse_byte_size(&args[i], "--max-input-bytes"));
+                } else {
+                    fatal_error("Missing value for --max-input-bytes");
+                }
+            }
+            "--offsets-file" => {
+                i += 1;
+                if i < args.len() {
+                    offsets_file = Some(PathBuf::from(&args[i]));
+                } else {
+                    fatal_error("Missing value for --offsets-file");
+                }
+            }
+            "--head-bytes" => {
+                i += 1;
+                if i < args.len() {
+                    head_bytes = Some(parse_byte_size(&args[i], "--head-bytes"));
+                } else {
+                    fatal_error("Missing value for --head-bytes");
+                }
+            }
+            "--threads" => {
+                i += 1;
+                if i < args.len() {
+                    threads = match args[i].parse::<usize>() {
+                        Ok(n) if n >= 1 => n,
+                        _ => fatal_error("Invalid value for --threads. Must be a positive integer."),
+                    };
+                } else {
+                    fatal_error("Missing value for --threads");
+                }
+            }
             "-v" | "--verbose" => {
                 verbose = true;
             }
@@ -92,6 +619,10 @@ fn parse_args() -> (PathBuf, Option<PathBuf>, usize, bool) {
                 print_help();
                 process::exit(0);
             }
+            "--version" => {
+                print_version();
+                process::exit(0);
+            }
             _ => {
                 fatal_error(&format!("Unknown argument: {}", args[i]));
             }
@@ -104,7 +635,198 @@ fn parse_args() -> (PathBuf, Option<PathBuf>, usize, bool) {
         fatal_error("Input file path is required.");
     });
 
-    (input_path, output_file_path, min_length, verbose)
+    if recursive && input_path.as_os_str() == "-" {
+        fatal_error("--recursive cannot be used when reading from stdin.");
+    }
+
+    if length_histogram && (benchmark || format == OutputFormat::Csv) {
+        fatal_error("--length-histogram cannot be combined with --benchmark or --format csv.");
+    }
+
+    if offsets.is_some() && (format == OutputFormat::Csv || length_histogram || region_analysis) {
+        fatal_error(
+            "--offsets and --radix only apply to text output of strings, not to --format csv (which always has an offset column), --length-histogram or --region-analysis.",
+        );
+    }
+
+    if detect_mojibake && length_histogram {
+        fatal_error("--detect-mojibake cannot be combined with --length-histogram, which does not print strings.");
+    }
+
+    if decode && length_histogram {
+        fatal_error("--decode cannot be combined with --length-histogram, which does not print strings.");
+    }
+
+    if region_analysis
+        && (recursive
+            || filter.is_some()
+            || max_strings.is_some()
+            || benchmark
+            || length_histogram
+            || detect_mojibake
+            || decode
+            || entropy_min.is_some())
+    {
+        fatal_error(
+            "--region-analysis cannot be combined with --recursive, --filter, --max-strings, --benchmark, --length-histogram, --detect-mojibake, --decode or --entropy-min.",
+        );
+    }
+    if region_analysis && encoding != Encoding::Ascii {
+        fatal_error("--region-analysis measures single printable bytes and only supports --encoding ascii.");
+    }
+    if !region_analysis && (window_size.is_some() || low_ratio.is_some()) {
+        fatal_error("--window-size and --low-ratio only apply to --region-analysis.");
+    }
+
+    if signature.is_some()
+        && (region_analysis
+            || filter.is_some()
+            || max_strings.is_some()
+            || quiet
+            || benchmark
+            || length_histogram
+            || detect_mojibake
+            || decode
+            || entropy_min.is_some()
+            || offsets.is_some())
+    {
+        fatal_error(
+            "--signature cannot be combined with --region-analysis, --filter, --max-strings, --quiet, --benchmark, --length-histogram, --detect-mojibake, --decode, --entropy-min, --offsets or --radix.",
+        );
+    }
+    if signature.is_some() && encoding != Encoding::Ascii {
+        fatal_error("--signature matches raw bytes and only supports --encoding ascii.");
+    }
+
+    if offsets_file.is_some() {
+        if recursive || input_path.as_os_str() == "-" {
+            fatal_error("--offsets-file seeks within a single input file and cannot be used with --recursive or stdin.");
+        }
+        if region_analysis || signature.is_some() || length_histogram || benchmark || head_bytes.is_some() || max_input_bytes.is_some() {
+            fatal_error(
+                "--offsets-file cannot be combined with --region-analysis, --signature, --length-histogram, --benchmark, --head-bytes or --max-input-bytes.",
+            );
+        }
+        if encoding != Encoding::Ascii {
+            fatal_error("--offsets-file only supports --encoding ascii.");
+        }
+    }
+    let target_offsets = offsets_file.map(|path| read_offsets_file(&path));
+
+    if quiet {
+        if filter.is_none() {
+            fatal_error("--quiet requires --filter: it reports whether a string containing the filter text exists.");
+        }
+        if output_file_path.is_some() || benchmark || length_histogram || region_analysis {
+            fatal_error("--quiet writes no output and cannot be combined with -o, --benchmark, --length-histogram or --region-analysis.");
+        }
+        // The first match decides the exit status, so there is no need to read further.
+        max_strings = Some(1);
+    }
+
+    if context_chars.is_some() {
+        if input_path.as_os_str() == "-" {
+            fatal_error("--context-chars re-reads the bytes around each string and needs a seekable input file, not stdin.");
+        }
+        if signature.is_some() || region_analysis || length_histogram || benchmark || quiet {
+            fatal_error("--context-chars cannot be combined with --signature, --region-analysis, --length-histogram, --benchmark or --quiet, which do not print strings.");
+        }
+    }
+
+    if threads > 1 {
+        if !recursive {
+            fatal_error("--threads scans several files at once and requires --recursive.");
+        }
+        if max_strings.is_some() || max_input_bytes.is_some() {
+            fatal_error("--threads cannot be combined with --max-strings, --quiet or --max-input-bytes, which stop after the files read so far.");
+        }
+    }
+
+    if wide_chars && !matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be) {
+        fatal_error("--wide-chars only applies to --encoding utf16le or utf16be.");
+    }
+
+    if tee && output_file_path.is_none() {
+        fatal_error("--tee requires an output file given with -o.");
+    }
+
+    if min_byte > max_byte {
+        fatal_error(&format!(
+            "--min-byte (0x{:02X}) must be less than or equal to --max-byte (0x{:02X}).",
+            min_byte, max_byte
+        ));
+    }
+    if let Some(delimiter) = record_delimiter.filter(|d| !(min_byte..=max_byte).contains(d)) {
+        warn(&format!(
+            "--record-delimiter 0x{:02X} is outside the printable range (0x{:02X}-0x{:02X}), so it already ends strings.",
+            delimiter, min_byte, max_byte
+        ));
+    }
+
+    Options {
+        input_path,
+        output_path: output_file_path,
+        tee,
+        min_length,
+        min_byte,
+        max_byte,
+        record_delimiter,
+        recursive,
+        filter,
+        max_strings,
+        quiet,
+        encoding,
+        wide_chars,
+        output_encoding,
+        format,
+        offsets,
+        benchmark,
+        length_histogram,
+        detect_mojibake,
+        decode,
+        entropy_min,
+        max_input_bytes,
+        head_bytes,
+        context_chars,
+        region_analysis,
+        window_size: window_size.unwrap_or(4096),
+        low_ratio: low_ratio.unwrap_or(0.5),
+        signature,
+        threads,
+        target_offsets,
+        verbose,
+    }
+}
+
+/// Reads an `--offsets-file`: one byte offset per line, in decimal or in hex with a `0x` prefix.
+/// Blank lines and lines starting with '#' are skipped.
+fn read_offsets_file(path: &Path) -> Vec<u64> {
+    let content = fs::read_to_string(path).unwrap_or_else(|e| {
+        io_error(&format!("Failed to read offsets file {:?}: {}", path, e));
+    });
+    let mut offsets = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = match line.strip_prefix("0x").or_else(|| line.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => line.parse::<u64>(),
+        };
+        offsets.push(parsed.unwrap_or_else(|_| {
+            fatal_error(&format!("Invalid offset '{}' on line {} of {:?}. Use decimal or 0x-prefixed hex.", line, index + 1, path));
+        }));
+    }
+    if offsets.is_empty() {
+        fatal_error(&format!("Offsets file {:?} contains no offsets.", path));
+    }
+    offsets
+}
+
+/// Prints the tool name and version.
+fn print_version() {
+    println!("{} {}", TOOL_NAME, TOOL_VERSION);
 }
 
 /// Prints the help message for the tool.
@@ -112,28 +834,427 @@ fn print_help() {
     println!(
         "Binary String Extractor
 
-Usage: binary_string_extractor -i <INPUT_FILE> [-o <OUTPUT_FILE>] [-m <MIN_LENGTH>] [-v | --verbose] [--help]
+Usage: binary_string_extractor -i <INPUT_FILE> [-o <OUTPUT_FILE> [--tee]] [-m <MIN_LENGTH>] [--min-byte <BYTE>] [--max-byte <BYTE>] [--record-delimiter <BYTE>] [-r | --recursive] [-f <TEXT>] [-n <COUNT>] [-q | --quiet] [-e <ENCODING> [--wide-chars]] [--output-encoding <ENCODING>] [--format text|csv] [--offsets | -t, --radix d|o|x] [--benchmark] [--length-histogram] [--detect-mojibake] [--decode] [--entropy-min <BITS>] [--max-input-bytes <N>] [--head-bytes <N>] [--context-chars <N>] [--region-analysis [--window-size <N>] [--low-ratio <R>]] [--signature <HEX>] [--offsets-file <FILE>] [--threads <N>] [-v | --verbose] [--no-defaults] [--help] [--version]
 
 Arguments:
-  -i, --input <FILE>        Path to the binary input file to extract strings from.
+  -i, --input <PATH>        Path to the binary input file (or directory with --recursive) to extract strings from.
+                            Use '-' to read from stdin.
   -o, --output <FILE>       (Optional) Path to save the extracted strings. If not provided, output is printed to stdout.
+  --tee                     (Optional) With -o, also print the output to stdout. If one destination fails, the
+                            other still receives everything, and the tool exits non-zero.
   -m, --min-length <LENGTH> (Optional) Minimum length of strings to extract (default: 4).
+  --min-byte <BYTE>         (Optional) Lowest byte value treated as printable, decimal or hex (default: 0x20).
+  --max-byte <BYTE>         (Optional) Highest byte value treated as printable, decimal or hex (default: 0x7E).
+  --record-delimiter <BYTE> (Optional) Byte that ends a string even when it is printable (e.g. 0x1F, or 0x7C for
+                            '|'), so delimited records come out as separate strings. With UTF-16 it matches the
+                            character U+00NN. Add --min-byte 0 --max-byte 0xFF to split on the delimiter alone.
+  -r, --recursive           (Optional) Treat the input as a directory and extract strings from every regular file
+                            beneath it. Output lines are prefixed with the file path when more than one file is scanned.
+  -f, --filter <TEXT>       (Optional) Only report strings containing TEXT.
+  -n, --max-strings <COUNT> (Optional) Stop extraction after COUNT strings have been found (across all files).
+  -q, --quiet               (Optional) With --filter, print nothing and stop at the first matching string. Exits 0
+                            if a match was found and 1 if not, for use as a presence check in build scripts.
+  -e, --encoding <ENC>      (Optional) Character encoding to search for: 'ascii' (default), 'utf8', 'utf16le' or
+                            'utf16be'. With UTF-16, each code unit must be a character from U+0000 to U+00FF
+                            in the --min-byte..--max-byte range, and invalid surrogates end the string. With
+                            'utf8', runs of well-formed UTF-8 are extracted, and -m counts characters, not
+                            bytes. Multi-byte characters are printable unless they are control characters; an
+                            invalid byte sequence ends the current string.
+  --wide-chars              (Optional) With --encoding utf16le or utf16be, also accept non-control characters
+                            above U+00FF, such as CJK or Cyrillic text, and surrogate pairs. Random binary data
+                            then also yields strings of such characters.
+  --output-encoding <ENC>   (Optional) 'utf8' (default) writes UTF-8; 'original' writes each line in the
+                            --encoding it was extracted with (Latin-1 bytes for 'ascii').
+  --format <text|csv>       (Optional) 'text' (default) prints one string per line. 'csv' prints
+                            'offset,length,encoding,value' rows after a header (with a leading 'file' column
+                            when several files are scanned). Offsets are byte offsets; length is in characters.
+  --offsets                 (Optional) In text output, print each string's byte offset before it, in hex with
+                            a '0x' prefix (e.g. '0x0000001A RUNTIME').
+  -t, --radix <d|o|x>       (Optional) Like --offsets, but formatted as by strings(1) -t: decimal, octal or hex,
+                            without a prefix and right-aligned to 7 characters (e.g. '     1a RUNTIME').
+  --benchmark               (Optional) Run extraction without writing strings and report throughput
+                            (MB/s and strings/s) to stderr.
+  --length-histogram        (Optional) Instead of the strings, print a bar chart of how many strings fall into
+                            each power-of-two length bucket (e.g. 4-7, 8-15, 16-31).
+  --detect-mojibake         (Optional) Tag strings containing double-encoding artifacts (UTF-8 read as Latin-1 or
+                            Windows-1252, e.g. 'Ã©' for 'é') with 'MOJIBAKE: ', or a 'mojibake' CSV column.
+                            Characters above 0x7E must be allowed with --max-byte (e.g. 0xFF).
+  --decode                  (Optional) Also decode '\\xNN' and '%NN' escapes in each string (e.g. '\\x68\\x74' or
+                            '%2F'). When that changes a string, a 'DECODED: ' line with the decoded form follows it,
+                            or a 'decoded' CSV column is filled. --filter also matches the decoded form. Escapes of
+                            control bytes are left as written.
+  --entropy-min <BITS>      (Optional) Only report strings whose Shannon entropy is at least BITS per character, such
+                            as keys, tokens or encoded blobs. Prose and paths score about 4, random tokens 4.5 or more. Each
+                            is tagged 'ENTROPY <E>: ', or an 'entropy' CSV column is added. Strings shorter than {}
+                            characters are never reported, since their entropy cannot reach typical thresholds.
+  --context-chars <N>       (Optional) After each string, print a 'CONTEXT: ' line (or a 'context' CSV column) with
+                            the string between '[' and ']' and up to N raw bytes of input on either side, with
+                            non-printable bytes, '\\', '[' and ']' escaped as '\\xNN'. Needs a seekable input file.
+  --region-analysis         (Optional) Instead of the strings, print a table of fixed-size windows with each window's
+                            printable-byte ratio and string count. Windows below --low-ratio are marked LOW: likely
+                            packed, encrypted or compressed data (or padding, shown as 'all 0xNN').
+  --window-size <N>         (Optional) Window size in bytes for --region-analysis (default: 4096). Accepts K and M.
+  --low-ratio <R>           (Optional) Printable ratio (0-1) below which a window is marked LOW (default: 0.5).
+  --signature <HEX>         (Optional) Instead of the strings, print the offset and bytes of every match of a byte
+                            signature, given as hex bytes with '??' for any byte (e.g. '4D 5A ?? ?? 50 45').
+                            Matches spanning read chunks are found, and overlapping matches are all reported.
+  --offsets-file <FILE>     (Optional) Only extract the strings starting at or spanning the byte offsets listed in
+                            FILE (one per line, decimal or 0x hex), reading just the bytes around each offset.
+                            Needs a single seekable input file. Offsets past the end are skipped with a warning.
+  --max-input-bytes <N>     (Optional) Fail with an error once more than N bytes have been read across all inputs.
+                            Accepts K, M and G suffixes (e.g. 2G). Default: unlimited.
+  --head-bytes <N>          (Optional) Read only the first N bytes of each input (including stdin) and ignore the
+                            rest, for quick triage of file headers. Accepts K, M and G suffixes (e.g. 64K).
+  --threads <N>             (Optional) With --recursive, scan up to N files at once (default: 1). The output is
+                            the same as with one thread, in path order; only --verbose and warning messages may
+                            appear in a different order. Cannot be combined with --max-strings, --quiet or
+                            --max-input-bytes.
   -v, --verbose             (Optional) Enable verbose output.
-  --help                    Display this help message."
+  --no-defaults             Ignore the defaults file described below.
+  --help                    Display this help message.
+  --version                 Display the tool version.
+
+Defaults file: default options may be set in $XDG_CONFIG_HOME/binary_string_extractor.conf
+(or ~/.config/binary_string_extractor.conf), one 'option = value' per line (e.g. 'verbose = true').
+Options given on the command line override them, and a missing file is ignored.
+
+Exit status: 0 on success, 1 on invalid arguments or other errors (e.g. --max-input-bytes exceeded),
+4 if an input cannot be read or the output cannot be written. With --quiet, 0 means a matching string
+was found and 1 that none was (or that the arguments were invalid).",
+        ENTROPY_MIN_LENGTH
     );
 }
 
-/// Extracts printable ASCII strings from a Read stream.
-fn extract_strings<R: Read>(reader: &mut R, min_len: usize, verbose: bool) -> Vec<String> {
-    let mut current_string_bytes = Vec::new();
-    let mut strings = Vec::new();
+/// Character encoding in which strings are searched for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// Single-byte characters (ASCII, or Latin-1 with a widened byte range).
+    Ascii,
+    /// Well-formed UTF-8, including multi-byte characters.
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// Encoding used when writing extracted strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputEncoding {
+    /// Always write UTF-8 (default).
+    Utf8,
+    /// Write each line in the encoding it was extracted from.
+    Original,
+}
+
+/// Layout of the extracted strings in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    /// One string per line (default).
+    Text,
+    /// `offset,length,encoding,value` rows with a header.
+    Csv,
+}
+
+impl Encoding {
+    /// Name of the encoding as accepted by `--encoding`.
+    fn name(&self) -> &'static str {
+        match self {
+            Encoding::Ascii => "ascii",
+            Encoding::Utf8 => "utf8",
+            Encoding::Utf16Le => "utf16le",
+            Encoding::Utf16Be => "utf16be",
+        }
+    }
+}
+
+/// How string offsets are printed in text output, chosen with `--offsets` or `--radix`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OffsetStyle {
+    /// `--offsets`: hex with a `0x` prefix, padded to 8 digits as in `--region-analysis`.
+    PrefixedHex,
+    /// `--radix d|o|x`: as `strings -t d|o|x` prints them, right-aligned to 7 characters.
+    Decimal,
+    Octal,
+    Hex,
+}
+
+impl OffsetStyle {
+    /// Formats `offset`, including the space that separates it from the string.
+    fn format(&self, offset: u64) -> String {
+        match self {
+            OffsetStyle::PrefixedHex => format!("0x{:08X} ", offset),
+            OffsetStyle::Decimal => format!("{:>7} ", offset),
+            OffsetStyle::Octal => format!("{:>7o} ", offset),
+            OffsetStyle::Hex => format!("{:>7x} ", offset),
+        }
+    }
+}
+
+/// A string found in the input, with its position.
+struct ExtractedString {
+    /// Byte offset of the string's first character within the input.
+    offset: u64,
+    /// Length in characters.
+    length: usize,
+    value: String,
+    /// Shannon entropy in bits per character, computed only with `--entropy-min`.
+    entropy: Option<f64>,
+    /// The string with the input bytes around it, escaped, filled in only with `--context-chars`.
+    context: Option<String>,
+}
+
+/// Shortest string considered by `--entropy-min`. A string of `n` characters has an entropy of at
+/// most log2(n) bits per character, so shorter strings could only pass low thresholds, and their
+/// entropy says little: `abcd` already reaches the maximum of 2 bits.
+const ENTROPY_MIN_LENGTH: usize = 16;
+
+/// Returns the Shannon entropy of `value` in bits per character, computed as in the config
+/// linter's `--builtin-rules` secret heuristic.
+fn shannon_entropy(value: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in value.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let length = value.chars().count() as f64;
+    counts.values().map(|&count| count as f64 / length).map(|p| -p * p.log2()).sum()
+}
+
+/// Returns true if a decoded character counts as printable.
+/// Characters below U+0100 must fall within the configured byte range and not be the
+/// `--record-delimiter`. U+FFFD, which stands for undecodable input, is never printable.
+/// With `--encoding utf8`, the byte range applies to ASCII characters only, since every character
+/// from U+0080 on is encoded in several bytes, and any other non-control character is accepted.
+/// With UTF-16, characters above U+00FF are only accepted with `--wide-chars`, since almost any
+/// pair of binary bytes forms one.
+fn is_printable(c: char, options: &Options) -> bool {
+    if c == char::REPLACEMENT_CHARACTER {
+        return false;
+    }
+    match u8::try_from(c as u32) {
+        Ok(byte) if options.encoding != Encoding::Utf8 || byte.is_ascii() => (options.min_byte..=options.max_byte).contains(&byte) && options.record_delimiter != Some(byte),
+        _ => (options.encoding == Encoding::Utf8 || options.wide_chars) && !c.is_control(),
+    }
+}
+
+/// Accumulates printable characters into strings, applying the minimum length,
+/// `--filter` and string limit options.
+struct StringCollector<'a> {
+    options: &'a Options,
+    limit: Option<usize>,
+    current: String,
+    current_len: usize, // Length in characters, not UTF-8 bytes
+    current_offset: u64,
+    strings: Vec<ExtractedString>,
+}
+
+impl<'a> StringCollector<'a> {
+    fn new(options: &'a Options, limit: Option<usize>) -> Self {
+        StringCollector { options, limit, current: String::new(), current_len: 0, current_offset: 0, strings: Vec::new() }
+    }
+
+    /// Adds a decoded character that starts at byte `offset`, ending the current run if it is
+    /// not printable. Returns true once the string limit has been reached.
+    fn push_char(&mut self, c: char, offset: u64) -> bool {
+        if is_printable(c, self.options) {
+            if self.current_len == 0 {
+                self.current_offset = offset;
+            }
+            self.current.push(c);
+            self.current_len += 1;
+            false
+        } else {
+            self.end_run()
+        }
+    }
+
+    /// Ends the current run, keeping it if it passes the length and filter checks.
+    /// Returns true once the string limit has been reached.
+    fn end_run(&mut self) -> bool {
+        let keep = self.current_len >= self.options.min_length
+            && match &self.options.filter {
+                Some(filter) => {
+                    self.current.contains(filter.as_str())
+                        || (self.options.decode
+                            && decode_escapes(&self.current).is_some_and(|decoded| decoded.contains(filter.as_str())))
+                }
+                None => true,
+            };
+        // Entropy is only computed for strings that passed the other checks.
+        let entropy = match self.options.entropy_min {
+            Some(_) if keep && self.current_len >= ENTROPY_MIN_LENGTH => Some(shannon_entropy(&self.current)),
+            _ => None,
+        };
+        let keep = keep && self.options.entropy_min.is_none_or(|min| entropy.is_some_and(|entropy| entropy >= min));
+        if keep {
+            self.strings.push(ExtractedString {
+                offset: self.current_offset,
+                length: self.current_len,
+                value: std::mem::take(&mut self.current),
+                entropy,
+                context: None,
+            });
+        }
+        self.current.clear();
+        self.current_len = 0;
+        keep && self.limit == Some(self.strings.len())
+    }
+}
+
+/// Incrementally decodes input bytes into characters for the selected encoding.
+/// UTF-8 sequences, UTF-16 code units and surrogate pairs may span read-chunk boundaries; invalid
+/// or unpaired surrogates decode to U+FFFD, which is not printable, and invalid UTF-8 ends the run.
+/// Tracks the byte offset of every decoded character for `--format csv`.
+struct Decoder {
+    encoding: Encoding,
+    position: u64, // Offset of the next byte to be fed
+    pending_byte: Option<u8>,
+    pending_high_surrogate: Option<(u16, u64)>,
+    // The bytes of a UTF-8 sequence read so far, how many it has in total, and where it started.
+    utf8_sequence: [u8; 4],
+    utf8_len: usize,
+    utf8_width: usize,
+    utf8_offset: u64,
+}
+
+impl Decoder {
+    fn new(encoding: Encoding) -> Self {
+        Decoder {
+            encoding,
+            position: 0,
+            pending_byte: None,
+            pending_high_surrogate: None,
+            utf8_sequence: [0; 4],
+            utf8_len: 0,
+            utf8_width: 0,
+            utf8_offset: 0,
+        }
+    }
+
+    /// Feeds one byte of UTF-8 input. A byte that cannot continue the pending sequence ends the
+    /// run and is then read as the start of a new character, so `E4 41` still yields `A`.
+    /// Overlong forms, surrogates and code points above U+10FFFF are invalid, as in `str::from_utf8`.
+    fn feed_utf8(&mut self, byte: u8, offset: u64, collector: &mut StringCollector) -> bool {
+        if self.utf8_len > 0 {
+            if byte & 0xC0 == 0x80 {
+                self.utf8_sequence[self.utf8_len] = byte;
+                self.utf8_len += 1;
+                if self.utf8_len < self.utf8_width {
+                    return false;
+                }
+                self.utf8_len = 0;
+                return match std::str::from_utf8(&self.utf8_sequence[..self.utf8_width]) {
+                    // A complete, valid sequence is exactly one character.
+                    Ok(text) => text.chars().next().is_some_and(|c| collector.push_char(c, self.utf8_offset)),
+                    Err(_) => collector.end_run(),
+                };
+            }
+            self.utf8_len = 0;
+            if collector.end_run() {
+                return true;
+            }
+        }
+        self.utf8_width = match byte {
+            0x00..=0x7F => return collector.push_char(byte as char, offset),
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            // Stray continuation bytes, and bytes that never occur in UTF-8.
+            _ => return collector.end_run(),
+        };
+        self.utf8_sequence[0] = byte;
+        self.utf8_len = 1;
+        self.utf8_offset = offset;
+        false
+    }
+
+    /// Feeds one byte into the collector. Returns true once the string limit has been reached.
+    fn feed(&mut self, byte: u8, collector: &mut StringCollector) -> bool {
+        let offset = self.position;
+        self.position += 1;
+        let unit = match self.encoding {
+            // Each byte maps to the Unicode code point of the same value (Latin-1), so custom
+            // printable ranges above 0x7F never produce invalid UTF-8.
+            Encoding::Ascii => return collector.push_char(byte as char, offset),
+            Encoding::Utf8 => return self.feed_utf8(byte, offset, collector),
+            Encoding::Utf16Le | Encoding::Utf16Be => match self.pending_byte.take() {
+                None => {
+                    self.pending_byte = Some(byte);
+                    return false;
+                }
+                Some(first) if self.encoding == Encoding::Utf16Le => u16::from_le_bytes([first, byte]),
+                Some(first) => u16::from_be_bytes([first, byte]),
+            },
+        };
+        let unit_offset = offset - 1; // The code unit started at the pending byte
+
+        match unit {
+            0xD800..=0xDBFF => {
+                // A high surrogate replacing an earlier unpaired one invalidates the earlier one.
+                match self.pending_high_surrogate.replace((unit, unit_offset)) {
+                    Some((_, unpaired_offset)) => collector.push_char(char::REPLACEMENT_CHARACTER, unpaired_offset),
+                    None => false,
+                }
+            }
+            0xDC00..=0xDFFF => match self.pending_high_surrogate.take() {
+                Some((high, high_offset)) => {
+                    let code_point = 0x10000 + (((high as u32) - 0xD800) << 10) + ((unit as u32) - 0xDC00);
+                    collector.push_char(char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER), high_offset)
+                }
+                None => collector.push_char(char::REPLACEMENT_CHARACTER, unit_offset),
+            },
+            _ => {
+                if let Some((_, unpaired_offset)) = self.pending_high_surrogate.take() {
+                    if collector.push_char(char::REPLACEMENT_CHARACTER, unpaired_offset) {
+                        return true;
+                    }
+                }
+                // Non-surrogate code units are always valid scalar values.
+                collector.push_char(char::from_u32(unit as u32).unwrap_or(char::REPLACEMENT_CHARACTER), unit_offset)
+            }
+        }
+    }
+
+    /// Flushes decoder state at end of input: a dangling high surrogate becomes U+FFFD (ending the run), and a
+    /// truncated UTF-8 sequence is dropped.
+    fn finish(&mut self, collector: &mut StringCollector) {
+        self.utf8_len = 0;
+        if let Some((_, unpaired_offset)) = self.pending_high_surrogate.take() {
+            collector.push_char(char::REPLACEMENT_CHARACTER, unpaired_offset);
+        }
+    }
+}
+
+/// Wraps a reader and counts the bytes read through it, for `--benchmark` reporting.
+struct CountingReader<R: Read> {
+    inner: R,
+    bytes_read: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read += n as u64;
+        Ok(n)
+    }
+}
+
+/// Extracts printable strings from a Read stream.
+/// A byte is considered printable if it lies within `min_byte..=max_byte`.
+/// Extraction stops early once `limit` strings have been found.
+fn extract_strings<R: Read>(reader: &mut R, options: &Options, limit: Option<usize>) -> Vec<ExtractedString> {
+    let verbose = options.verbose;
+    let mut collector = StringCollector::new(options, limit);
+    let mut decoder = Decoder::new(options.encoding);
+
+    if limit == Some(0) {
+        return collector.strings;
+    }
 
     let mut buffer = [0; 4096]; // Read in chunks
     info("Starting string extraction...", verbose);
 
     loop {
         let bytes_read = reader.read(&mut buffer).unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to read from input: {}", e));
+            io_error(&format!("Failed to read from input: {}", e));
         });
 
         if bytes_read == 0 {
@@ -141,68 +1262,838 @@ fn extract_strings<R: Read>(reader: &mut R, min_len: usize, verbose: bool) -> Ve
         }
 
         for &byte in &buffer[..bytes_read] {
-            // Check if the byte is a printable ASCII character (0x20 to 0x7E)
-            // or common extended ASCII characters if desired, but for this demo, keeping it simple.
-            if byte >= 0x20 && byte <= 0x7E {
-                current_string_bytes.push(byte);
-            } else {
-                // Non-printable character found, terminate current string
-                if current_string_bytes.len() >= min_len {
-                    // It's safe to unwrap here because we've filtered for valid UTF-8 range (ASCII)
-                    strings.push(String::from_utf8(current_string_bytes.clone()).unwrap());
-                }
-                current_string_bytes.clear();
+            // Non-printable characters terminate the current string inside the collector.
+            if decoder.feed(byte, &mut collector) {
+                info(&format!("Reached string limit of {}. Stopping early.", collector.strings.len()), verbose);
+                return collector.strings;
             }
         }
     }
 
     // Add any remaining string at EOF
-    if current_string_bytes.len() >= min_len {
-        strings.push(String::from_utf8(current_string_bytes).unwrap());
+    decoder.finish(&mut collector);
+    collector.end_run();
+
+    info(&format!("Finished extraction. Found {} potential strings.", collector.strings.len()), verbose);
+    collector.strings
+}
+
+/// Encodes an output line (including its newline) for the selected output encoding.
+fn encode_line(line: &str, options: &Options) -> Vec<u8> {
+    if options.output_encoding == OutputEncoding::Utf8 {
+        return line.as_bytes().to_vec();
     }
+    match options.encoding {
+        // Latin-1: characters outside the single-byte range (only possible in a file path prefix)
+        // are written as '?'.
+        Encoding::Ascii => line.chars().map(|c| u8::try_from(c as u32).unwrap_or(b'?')).collect(),
+        Encoding::Utf8 => line.as_bytes().to_vec(),
+        Encoding::Utf16Le => line.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        Encoding::Utf16Be => line.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+    }
+}
+
+/// Recursively collects regular files beneath `dir` into `files`.
+/// Symlinks are never followed, which rules out cycles; unreadable directories are skipped with a warning.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>, verbose: bool) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn(&format!("Skipping unreadable directory {:?}: {}", dir, e));
+            return;
+        }
+    };
 
-    info(&format!("Finished extraction. Found {} potential strings.", strings.len()), verbose);
-    strings
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|e| e.path())).collect();
+    paths.sort(); // Deterministic output order
+
+    for path in paths {
+        let file_type = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata.file_type(),
+            Err(e) => {
+                warn(&format!("Skipping {:?}: {}", path, e));
+                continue;
+            }
+        };
+        if file_type.is_symlink() {
+            info(&format!("Skipping symlink {:?}", path), verbose);
+        } else if file_type.is_dir() {
+            collect_files(&path, files, verbose);
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+/// Windows-1252 characters for bytes 0x80-0x9F, indexed by `byte - 0x80`. The five unassigned
+/// bytes map to the C1 control of the same value, as in Latin-1.
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Maps a character back to the byte it decodes from under Latin-1 or Windows-1252, if any.
+fn single_byte(c: char) -> Option<u8> {
+    match u8::try_from(c as u32) {
+        Ok(byte) => Some(byte),
+        Err(_) => CP1252_HIGH.iter().position(|&high| high == c).map(|i| 0x80 + i as u8),
+    }
+}
+
+/// Returns true if `text` contains the UTF-8 encoding of a non-ASCII character that was read
+/// back one byte per character as Latin-1 or Windows-1252, such as `Ã©` for `é` or `â€™` for `’`.
+fn has_mojibake(text: &str) -> bool {
+    let bytes: Vec<Option<u8>> = text.chars().map(single_byte).collect();
+    bytes.iter().enumerate().any(|(i, lead)| {
+        let width = match lead {
+            Some(0xC2..=0xDF) => 2,
+            Some(0xE0..=0xEF) => 3,
+            Some(0xF0..=0xF4) => 4,
+            _ => return false,
+        };
+        bytes
+            .get(i..i + width)
+            .and_then(|sequence| sequence.iter().copied().collect::<Option<Vec<u8>>>())
+            .is_some_and(|sequence| std::str::from_utf8(&sequence).is_ok())
+    })
+}
+
+/// Returns true if an extracted string shows double-encoding artifacts (`--detect-mojibake`).
+/// Single-byte runs hold raw bytes, so they are first decoded as UTF-8: correctly encoded text
+/// such as `é` (C3 A9) is then not flagged, while text encoded twice still reads `Ã©`.
+fn is_mojibake(value: &str, encoding: Encoding) -> bool {
+    match encoding {
+        Encoding::Ascii => {
+            let bytes: Vec<u8> = value.chars().filter_map(|c| u8::try_from(c as u32).ok()).collect();
+            has_mojibake(&String::from_utf8_lossy(&bytes))
+        }
+        Encoding::Utf8 | Encoding::Utf16Le | Encoding::Utf16Be => has_mojibake(value),
+    }
+}
+
+/// Decodes the `\xNN` and `%NN` escapes in `value` for `--decode`, such as `\x68\x74\x74\x70`
+/// or `%2Fbin%2Fsh`. Escapes of control bytes (below 0x20, and 0x7F) are left as written, so the
+/// decoded string stays on one line. Decoded bytes above 0x7F are read as UTF-8, with U+FFFD for
+/// invalid sequences. Returns `None` if `value` contains no decodable escape.
+fn decode_escapes(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut changed = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let digits_start = match bytes[i] {
+            b'\\' if bytes.get(i + 1) == Some(&b'x') => Some(i + 2),
+            b'%' => Some(i + 1),
+            _ => None,
+        };
+        let escaped = digits_start.and_then(|start| {
+            let digits = bytes.get(start..start + 2).filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))?;
+            let byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+            Some((byte, start + 2)).filter(|_| !byte.is_ascii_control())
+        });
+        match escaped {
+            Some((byte, next)) => {
+                decoded.push(byte);
+                changed = true;
+                i = next;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    changed.then(|| String::from_utf8_lossy(&decoded).into_owned())
+}
+
+/// Escapes raw input bytes for `--context-chars`: printable ASCII is kept, while other bytes and
+/// the '\\', '[' and ']' characters are written as `\xNN`, so brackets in the output always mark
+/// the string itself.
+fn escape_context(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        if (0x20..=0x7E).contains(&byte) && !matches!(byte, b'\\' | b'[' | b']') {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("\\x{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+/// With `--context-chars N`, reads the N bytes before and after each string from `path` and
+/// stores them, escaped, with the string's own bytes between '[' and ']'. Windows are clipped at
+/// the start and end of the file (and at --head-bytes), so fewer bytes are shown there.
+fn add_context(path: &Path, strings: &mut [ExtractedString], options: &Options) {
+    let Some(context) = options.context_chars.filter(|_| !strings.is_empty()) else {
+        return;
+    };
+    let mut file = File::open(path).unwrap_or_else(|e| {
+        io_error(&format!("Failed to open input file {:?} for --context-chars: {}", path, e));
+    });
+    let file_len = file.metadata().map(|m| m.len()).unwrap_or(u64::MAX);
+    let limit = file_len.min(options.head_bytes.unwrap_or(u64::MAX));
+    let mut window = Vec::new();
+    for s in strings.iter_mut() {
+        let byte_len = match options.encoding {
+            Encoding::Ascii => s.length as u64,
+            Encoding::Utf8 => s.value.len() as u64,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2 * s.value.encode_utf16().count() as u64,
+        };
+        let start = s.offset.saturating_sub(context);
+        let end = (s.offset + byte_len).saturating_add(context).min(limit).max(start);
+        window.clear();
+        let read = file
+            .seek(SeekFrom::Start(start))
+            .and_then(|_| (&mut file).take(end - start).read_to_end(&mut window));
+        if let Err(e) = read {
+            io_error(&format!("Failed to read context from {:?}: {}", path, e));
+        }
+        // The file may have shrunk since the strings were extracted.
+        let string_start = ((s.offset - start) as usize).min(window.len());
+        let string_end = (string_start + byte_len as usize).min(window.len());
+        s.context = Some(format!(
+            "{}[{}]{}",
+            escape_context(&window[..string_start]),
+            escape_context(&window[string_start..string_end]),
+            escape_context(&window[string_end..])
+        ));
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, quote or line break, doubling embedded quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Size of the reads made while looking for the edges of a string in `--offsets-file` mode.
+const OFFSET_READ_SIZE: usize = 4096;
+
+/// Extracts, for `--offsets-file`, the string starting at or spanning each offset of the file at
+/// `path`. For each offset, the file is searched backwards to the start of the printable run and
+/// read forwards to its end, so only the bytes around the offsets are read. The usual length,
+/// filter and limit checks apply, and a string holding several of the offsets is reported once.
+/// Offsets past the end of the file, or on a byte that is not printable, are skipped with a warning.
+fn extract_at_offsets(path: &Path, offsets: &[u64], options: &Options, limit: Option<usize>) -> Vec<ExtractedString> {
+    let mut file = File::open(path).unwrap_or_else(|e| {
+        io_error(&format!("Failed to open input file {:?}: {}", path, e));
+    });
+    let file_length = file.metadata().map(|m| m.len()).unwrap_or_else(|e| {
+        io_error(&format!("Failed to read metadata of {:?}: {}", path, e));
+    });
+    let mut read_at = |start: u64, length: usize| -> Vec<u8> {
+        let mut bytes = vec![0; length];
+        file.seek(SeekFrom::Start(start)).and_then(|_| file.read_exact(&mut bytes)).unwrap_or_else(|e| {
+            io_error(&format!("Failed to read from input: {}", e));
+        });
+        bytes
+    };
+    let printable = |byte: u8| is_printable(byte as char, options);
+
+    let mut collector = StringCollector::new(options, limit);
+    // Printable runs already read, as (start, end) offsets, so each is reported once.
+    let mut runs: Vec<(u64, u64)> = Vec::new();
+    for &offset in offsets {
+        if offset >= file_length {
+            warn(&format!("Offset {} (0x{:X}) is beyond the end of {:?} ({} bytes).", offset, offset, path, file_length));
+            continue;
+        }
+        if runs.iter().any(|&(start, end)| (start..end).contains(&offset)) {
+            continue;
+        }
+        if !printable(read_at(offset, 1)[0]) {
+            warn(&format!("No string at offset {} (0x{:X}): the byte there is not printable.", offset, offset));
+            continue;
+        }
+
+        // Walk back to the first byte of the run.
+        let mut start = offset;
+        while start > 0 {
+            let chunk_start = start.saturating_sub(OFFSET_READ_SIZE as u64);
+            let chunk = read_at(chunk_start, (start - chunk_start) as usize);
+            match chunk.iter().rposition(|&byte| !printable(byte)) {
+                Some(index) => {
+                    start = chunk_start + index as u64 + 1;
+                    break;
+                }
+                None => start = chunk_start,
+            }
+        }
+        // Read forwards through the end of the run.
+        let mut end = start;
+        'run: while end < file_length {
+            let length = (file_length - end).min(OFFSET_READ_SIZE as u64) as usize;
+            for byte in read_at(end, length) {
+                if !printable(byte) {
+                    break 'run;
+                }
+                collector.push_char(byte as char, end);
+                end += 1;
+            }
+        }
+        runs.push((start, end));
+        if collector.end_run() {
+            info(&format!("Reached string limit of {}. Stopping early.", collector.strings.len()), options.verbose);
+            break;
+        }
+    }
+    collector.strings
 }
 
-/// Writes extracted strings to a Write stream.
-fn write_strings<W: Write>(writer: &mut W, strings: &[String], verbose: bool) {
+/// Writes one already-formatted output line in the selected output encoding.
+fn write_line<W: Write>(writer: &mut W, line: &str, options: &Options) {
+    writer.write_all(&encode_line(line, options)).unwrap_or_else(|e| {
+        io_error(&format!("Failed to write to output: {}", e));
+    });
+}
+
+/// Writes the `--format csv` header row. A `file` column is added in multi-file mode. With
+/// `--signature`, the columns are the match offset and bytes. Otherwise, after
+/// `value` come a `mojibake` column with `--detect-mojibake`, an `entropy` column with
+/// `--entropy-min` and a `decoded` column with `--decode`, in that order.
+fn write_csv_header<W: Write>(writer: &mut W, multi_file: bool, options: &Options) {
+    let file_column = if multi_file { "file," } else { "" };
+    if options.signature.is_some() {
+        write_line(writer, &format!("{}offset,bytes\n", file_column), options);
+        return;
+    }
+    let mojibake_column = if options.detect_mojibake { ",mojibake" } else { "" };
+    let entropy_column = if options.entropy_min.is_some() { ",entropy" } else { "" };
+    let decoded_column = if options.decode { ",decoded" } else { "" };
+    let context_column = if options.context_chars.is_some() { ",context" } else { "" };
+    write_line(
+        writer,
+        &format!(
+            "{}offset,length,encoding,value{}{}{}{}\n",
+            file_column, mojibake_column, entropy_column, decoded_column, context_column
+        ),
+        options,
+    );
+}
+
+/// Writes extracted strings to a Write stream in the selected output format and encoding.
+/// When `prefix` is given (multi-file mode), each line is prefixed with it.
+fn write_strings<W: Write>(writer: &mut W, strings: &[ExtractedString], prefix: Option<&Path>, options: &Options) {
+    let verbose = options.verbose;
     info(&format!("Writing {} strings to output...", strings.len()), verbose);
     for s in strings {
-        writeln!(writer, "{}", s).unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to write to output: {}", e));
-        });
+        let mojibake = options.detect_mojibake && is_mojibake(&s.value, options.encoding);
+        let entropy = s.entropy.map(|entropy| format!("{:.2}", entropy));
+        let tag = format!(
+            "{}{}",
+            entropy.as_ref().map(|entropy| format!("ENTROPY {}: ", entropy)).unwrap_or_default(),
+            if mojibake { "MOJIBAKE: " } else { "" }
+        );
+        let offset = options.offsets.map(|style| style.format(s.offset)).unwrap_or_default();
+        let decoded = if options.decode { decode_escapes(&s.value) } else { None };
+        let text_prefix = prefix.map(|path| format!("{}: ", path.display())).unwrap_or_default();
+        let line = match options.format {
+            OutputFormat::Text => {
+                // The decoded form follows the raw string on its own line, with the same prefix and offset.
+                let decoded_line =
+                    decoded.map(|decoded| format!("{}{}DECODED: {}\n", text_prefix, offset, decoded)).unwrap_or_default();
+                // So does the context, after any decoded form.
+                let context_line = s
+                    .context
+                    .as_ref()
+                    .map(|context| format!("{}{}CONTEXT: {}\n", text_prefix, offset, context))
+                    .unwrap_or_default();
+                format!("{}{}{}{}\n{}{}", text_prefix, offset, tag, s.value, decoded_line, context_line)
+            }
+            OutputFormat::Csv => {
+                let file_field = prefix.map(|path| csv_field(&path.display().to_string()) + ",").unwrap_or_default();
+                let mojibake_field = if !options.detect_mojibake { "" } else if mojibake { ",true" } else { ",false" };
+                let entropy_field = entropy.map(|entropy| format!(",{}", entropy)).unwrap_or_default();
+                let decoded_field = if options.decode {
+                    format!(",{}", decoded.map(|decoded| csv_field(&decoded)).unwrap_or_default())
+                } else {
+                    String::new()
+                };
+                let context_field = s.context.as_ref().map(|context| format!(",{}", csv_field(context))).unwrap_or_default();
+                format!(
+                    "{}{},{},{},{}{}{}{}{}\n",
+                    file_field,
+                    s.offset,
+                    s.length,
+                    options.encoding.name(),
+                    csv_field(&s.value),
+                    mojibake_field,
+                    entropy_field,
+                    decoded_field,
+                    context_field
+                )
+            }
+        };
+        write_line(writer, &line, options);
     }
     info("Successfully wrote strings to output.", verbose);
 }
 
-/// The main entry point for the application.
-/// Parses arguments, extracts strings from the input file, and writes them to the output.
-fn main() {
-    let (input_path, output_path, min_length, verbose) = parse_args();
+/// Width in characters of the longest `--length-histogram` bar.
+const HISTOGRAM_BAR_WIDTH: usize = 50;
+
+/// Counts of extracted strings per power-of-two length bucket: index `k` holds lengths
+/// `2^k ..= 2^(k+1) - 1`.
+#[derive(Default)]
+struct LengthHistogram {
+    buckets: Vec<usize>,
+}
+
+impl LengthHistogram {
+    fn record(&mut self, length: usize) {
+        let bucket = length.max(1).ilog2() as usize;
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+    }
+
+    /// Writes the histogram as a text bar chart, starting at the bucket holding `min_length`.
+    /// Empty buckets between populated ones are shown so gaps in the distribution stay visible.
+    fn write<W: Write>(&self, writer: &mut W, options: &Options) {
+        let total: usize = self.buckets.iter().sum();
+        let largest = self.buckets.iter().copied().max().unwrap_or(0);
+        write_line(writer, &format!("String length histogram ({} strings):\n", total), options);
+
+        let first = options.min_length.ilog2() as usize;
+        for (bucket, &count) in self.buckets.iter().enumerate().skip(first) {
+            let low = (1usize << bucket).max(options.min_length);
+            let high = (1usize << (bucket + 1)) - 1;
+            // Any non-empty bucket gets at least one '#' so it remains visible.
+            let bar_len = if count == 0 { 0 } else { (count * HISTOGRAM_BAR_WIDTH).div_ceil(largest) };
+            let mut row = format!("{:>11} | {:>8}", format!("{}-{}", low, high), count);
+            if bar_len > 0 {
+                row.push(' ');
+                row.push_str(&"#".repeat(bar_len));
+            }
+            row.push('\n');
+            write_line(writer, &row, options);
+        }
+    }
+}
+
+/// Byte counts for one `--region-analysis` window.
+struct Region {
+    offset: u64,
+    total: usize,
+    printable: usize,
+    strings: usize, // Strings of at least --min-length that start in this window
+    uniform: Option<u8>, // The byte value, if every byte in the window is the same
+}
+
+impl Region {
+    fn ratio(&self) -> f64 {
+        self.printable as f64 / self.total as f64
+    }
+}
+
+/// Splits the input into `--window-size` windows and counts, for each, the bytes in the printable
+/// range and the strings of at least `--min-length` printable bytes that start in it. A string
+/// that runs past a window boundary counts towards the window it starts in.
+fn analyze_regions<R: Read>(reader: &mut R, options: &Options) -> Vec<Region> {
+    let mut regions: Vec<Region> = Vec::new();
+    let mut buffer = [0; 4096];
+    let mut offset: u64 = 0;
+    let (mut run_length, mut run_window) = (0, 0);
+    loop {
+        let bytes_read = reader.read(&mut buffer).unwrap_or_else(|e| {
+            io_error(&format!("Failed to read from input: {}", e));
+        });
+        if bytes_read == 0 {
+            break;
+        }
+        for &byte in &buffer[..bytes_read] {
+            let window = (offset / options.window_size as u64) as usize;
+            if window == regions.len() {
+                regions.push(Region { offset, total: 0, printable: 0, strings: 0, uniform: Some(byte) });
+            }
+            let region = &mut regions[window];
+            region.total += 1;
+            if region.uniform != Some(byte) {
+                region.uniform = None;
+            }
+            if is_printable(byte as char, options) {
+                region.printable += 1;
+                if run_length == 0 {
+                    run_window = window;
+                }
+                run_length += 1;
+            } else {
+                if run_length >= options.min_length {
+                    regions[run_window].strings += 1;
+                }
+                run_length = 0;
+            }
+            offset += 1;
+        }
+    }
+    if run_length >= options.min_length {
+        regions[run_window].strings += 1;
+    }
+    regions
+}
+
+/// Writes the `--region-analysis` table, or CSV rows with `--format csv`. The text table ends
+/// with a count of LOW windows.
+fn write_regions<W: Write>(writer: &mut W, regions: &[Region], options: &Options) {
+    let is_low = |region: &Region| region.ratio() < options.low_ratio;
+    if options.format == OutputFormat::Csv {
+        write_line(writer, "start,end,printable_bytes,total_bytes,ratio,strings,low,uniform_byte\n", options);
+        for region in regions {
+            let uniform = region.uniform.map(|b| format!("0x{:02X}", b)).unwrap_or_default();
+            let row = format!(
+                "{},{},{},{},{:.3},{},{},{}\n",
+                region.offset,
+                region.offset + region.total as u64 - 1,
+                region.printable,
+                region.total,
+                region.ratio(),
+                region.strings,
+                is_low(region),
+                uniform
+            );
+            write_line(writer, &row, options);
+        }
+        return;
+    }
+
+    write_line(writer, &format!("{:<23} {:>13} {:>7} {:>8}\n", "Offset range", "Printable", "Ratio", "Strings"), options);
+    for region in regions {
+        let range = format!("0x{:08X}-0x{:08X}", region.offset, region.offset + region.total as u64 - 1);
+        let counts = format!("{}/{}", region.printable, region.total);
+        let mut row = format!("{:<23} {:>13} {:>6.1}% {:>8}", range, counts, region.ratio() * 100.0, region.strings);
+        if is_low(region) {
+            row.push_str("  LOW");
+            if let Some(byte) = region.uniform {
+                row.push_str(&format!(" (all 0x{:02X})", byte));
+            }
+        }
+        row.push('\n');
+        write_line(writer, &row, options);
+    }
+    let low = regions.iter().filter(|r| is_low(r)).count();
+    let summary = format!(
+        "{} of {} window(s) below {:.1}% printable: likely packed, encrypted or compressed data, or padding.\n",
+        low,
+        regions.len(),
+        options.low_ratio * 100.0
+    );
+    write_line(writer, &summary, options);
+}
+
+/// Writes a `--signature` match as `0xOFFSET BYTES` (or an `offset,bytes` CSV row), with the
+/// bytes as space-separated hex. When `prefix` is given (multi-file mode), the line is prefixed
+/// with it.
+fn write_signature_match<W: Write>(writer: &mut W, offset: u64, bytes: &[u8], prefix: Option<&Path>, options: &Options) {
+    let bytes = bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+    let line = match options.format {
+        OutputFormat::Text => {
+            let text_prefix = prefix.map(|path| format!("{}: ", path.display())).unwrap_or_default();
+            format!("{}{}{}\n", text_prefix, OffsetStyle::PrefixedHex.format(offset), bytes)
+        }
+        OutputFormat::Csv => {
+            let file_field = prefix.map(|path| csv_field(&path.display().to_string()) + ",").unwrap_or_default();
+            format!("{}{},{}\n", file_field, offset, bytes)
+        }
+    };
+    write_line(writer, &line, options);
+}
+
+/// Prints `--benchmark` throughput figures to stderr.
+fn report_benchmark(bytes: u64, strings: usize, seconds: f64) {
+    let megabytes = bytes as f64 / (1024.0 * 1024.0);
+    // Guard against a zero elapsed time on tiny inputs.
+    let seconds = seconds.max(f64::EPSILON);
+    eprintln!(
+        "{}Benchmark: {} bytes, {} strings in {:.3} s ({:.2} MB/s, {:.0} strings/s)",
+        INFO_PREFIX,
+        bytes,
+        strings,
+        seconds,
+        megabytes / seconds,
+        strings as f64 / seconds
+    );
+}
+
+/// What scanning one input produced.
+enum FileScan {
+    Regions(Vec<Region>),
+    /// Matches held back by a `--threads` worker; empty when they were written as found.
+    Signatures(HeldMatches),
+    Strings(Vec<ExtractedString>),
+}
+
+/// Reads one input and runs the selected analysis on it. Returns the result and the number of
+/// bytes read, or `None` if a file found by --recursive could not be opened (after a warning).
+/// `budget` is what is left of --max-input-bytes and `limit` of --max-strings. Signature matches
+/// are written to `stream`, with the given line prefix, as they are found; without it they are held.
+fn scan_file(path: &Path, options: &Options, budget: Option<u64>, limit: Option<usize>, stream: Option<(&mut dyn Write, Option<&Path>)>) -> Option<(FileScan, u64)> {
+    let verbose = options.verbose;
+    let exceeded = |remaining: u64| -> ! {
+        fatal_error(&format!(
+            "Input limit exceeded: reading {:?} goes past --max-input-bytes ({} bytes across all inputs, {} remaining).",
+            path,
+            options.max_input_bytes.unwrap_or_default(),
+            remaining
+        ));
+    };
+    let input: Box<dyn Read> = if path.as_os_str() == "-" {
+        info("Reading from stdin.", verbose);
+        Box::new(io::stdin().lock())
+    } else {
+        match File::open(path) {
+            Ok(file) => {
+                // Fail up front when the file's size is known; the read limit below also covers pipes.
+                // Only the --head-bytes prefix of the file counts towards the budget.
+                if let Some(budget) = budget {
+                    let head = options.head_bytes.unwrap_or(u64::MAX);
+                    if file.metadata().is_ok_and(|m| m.is_file() && m.len().min(head) > budget) {
+                        exceeded(budget);
+                    }
+                }
+                Box::new(file)
+            }
+            Err(e) if options.recursive => {
+                warn(&format!("Skipping unreadable file {:?}: {}", path, e));
+                return None;
+            }
+            Err(e) => io_error(&format!("Failed to open input file {:?}: {}", path, e)),
+        }
+    };
+    info(&format!("Scanning file: {:?}", path), verbose);
+    // --head-bytes ends the input early; the rest of the file (or stdin) is never read.
+    let input = input.take(options.head_bytes.unwrap_or(u64::MAX));
+    // Reading one byte past the budget is enough to tell that it was exceeded.
+    let input = input.take(budget.map_or(u64::MAX, |budget| budget.saturating_add(1)));
+    let mut reader = CountingReader { inner: BufReader::new(input), bytes_read: 0 };
+
+    let scan = if options.region_analysis {
+        let regions = analyze_regions(&mut reader, options);
+        info(&format!("Analyzed {} window(s) of {} bytes.", regions.len(), options.window_size), verbose);
+        FileScan::Regions(regions)
+    } else if let Some(signature) = &options.signature {
+        let mut held = HeldMatches::default();
+        let found = match stream {
+            Some((mut writer, prefix)) => signature.scan(&mut reader, |offset, bytes| write_signature_match(&mut writer, offset, bytes, prefix, options)),
+            None => signature.scan(&mut reader, |offset, bytes| held.push(signature, offset, bytes)),
+        };
+        let found = found.unwrap_or_else(|e| {
+            io_error(&format!("Failed to read from input: {}", e));
+        });
+        info(&format!("Found {} signature match(es).", found), verbose);
+        FileScan::Signatures(held)
+    } else {
+        let mut strings = extract_strings(&mut reader, options, limit);
+        add_context(path, &mut strings, options);
+        FileScan::Strings(strings)
+    };
+    if let Some(budget) = budget.filter(|&budget| reader.bytes_read > budget) {
+        exceeded(budget);
+    }
+    Some((scan, reader.bytes_read))
+}
 
-    info(&format!("Input file: {:?}", input_path), verbose);
-    info(&format!("Minimum string length: {}", min_length), verbose);
+/// Writes the result of `scan_file` (or adds its strings to the histogram) and returns the
+/// number of strings found.
+fn write_scan<W: Write>(writer: &mut W, scan: FileScan, prefix: Option<&Path>, options: &Options, histogram: &mut LengthHistogram) -> usize {
+    match scan {
+        // Only one input is allowed in this mode, so the table covers the whole run.
+        FileScan::Regions(regions) => {
+            write_regions(writer, &regions, options);
+            0
+        }
+        FileScan::Signatures(held) => {
+            if let Some(signature) = &options.signature {
+                held.for_each(signature, |offset, bytes| write_signature_match(writer, offset, bytes, prefix, options));
+            }
+            0
+        }
+        FileScan::Strings(strings) => {
+            if options.length_histogram {
+                strings.iter().for_each(|s| histogram.record(s.length));
+            } else {
+                write_strings(writer, &strings, prefix, options);
+            }
+            strings.len()
+        }
+    }
+}
+
+/// Scans `files` on `options.threads` worker threads and returns the total bytes read and
+/// strings found. Workers take file indices from a shared queue; each result is written as soon
+/// as every earlier file has been written, so the output is in the same (sorted) order as with
+/// one thread. Results that finish early are held in memory until their turn.
+fn scan_parallel<W: Write>(files: &[PathBuf], options: &Options, writer: &mut W, multi_file: bool, histogram: &mut LengthHistogram) -> (u64, usize) {
+    let (job_sender, job_receiver) = mpsc::channel();
+    for index in 0..files.len() {
+        let _ = job_sender.send(index);
+    }
+    // Once the queue is drained, workers see the channel closed and stop.
+    drop(job_sender);
+    let jobs = Mutex::new(job_receiver);
+    let (result_sender, results) = mpsc::channel();
 
-    let mut input_file = File::open(&input_path).unwrap_or_else(|e| {
-        fatal_error(&format!("Failed to open input file {:?}: {}", input_path, e));
+    let mut total_bytes: u64 = 0;
+    let mut total_strings: usize = 0;
+    thread::scope(|scope| {
+        for _ in 0..options.threads.min(files.len()) {
+            let result_sender = result_sender.clone();
+            let jobs = &jobs;
+            scope.spawn(move || loop {
+                // The lock is held only while taking the next index, not during the scan.
+                let Ok(Ok(index)) = jobs.lock().map(|queue| queue.recv()) else {
+                    break;
+                };
+                let scan = scan_file(&files[index], options, None, None, None);
+                if result_sender.send((index, scan)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_sender);
+
+        let mut pending: Vec<Option<Option<(FileScan, u64)>>> = (0..files.len()).map(|_| None).collect();
+        let mut next = 0;
+        for (index, scan) in results {
+            pending[index] = Some(scan);
+            while let Some(scan) = pending.get_mut(next).and_then(Option::take) {
+                if let Some((scan, bytes_read)) = scan {
+                    total_bytes += bytes_read;
+                    total_strings += write_scan(writer, scan, multi_file.then_some(files[next].as_path()), options, histogram);
+                }
+                next += 1;
+            }
+        }
     });
-    let mut reader = BufReader::new(&mut input_file);
+    (total_bytes, total_strings)
+}
+
+/// The main entry point for the application.
+/// Parses arguments, extracts strings from the input file(s), and writes them to the output.
+fn main() {
+    let options = parse_args();
+    let verbose = options.verbose;
 
-    let strings = extract_strings(&mut reader, min_length, verbose);
+    info(&format!("Input path: {:?}", options.input_path), verbose);
+    info(&format!("Minimum string length: {}", options.min_length), verbose);
+    info(&format!("Printable byte range: 0x{:02X}-0x{:02X}", options.min_byte, options.max_byte), verbose);
+    info(&format!("Encoding: {:?}, output encoding: {:?}", options.encoding, options.output_encoding), verbose);
+    if let Some(head) = options.head_bytes {
+        info(&format!("Reading only the first {} bytes of each input.", head), verbose);
+    }
 
-    let mut writer: Box<dyn Write> = if let Some(path) = output_path {
+    let files = if options.recursive {
+        if !options.input_path.is_dir() {
+            fatal_error(&format!("--recursive requires a directory, but {:?} is not one.", options.input_path));
+        }
+        let mut files = Vec::new();
+        collect_files(&options.input_path, &mut files, verbose);
+        info(&format!("Found {} files to scan.", files.len()), verbose);
+        files
+    } else {
+        vec![options.input_path.clone()]
+    };
+    let multi_file = files.len() > 1;
+
+    let mut writer: Box<dyn Write> = if options.benchmark {
+        info("Benchmark mode: extracted strings are not written.", verbose);
+        Box::new(io::sink())
+    } else if options.quiet {
+        info("Quiet mode: checking for a matching string without writing output.", verbose);
+        Box::new(io::sink())
+    } else if let Some(path) = &options.output_path {
         info(&format!("Output file: {:?}", path), verbose);
-        Box::new(File::create(&path).unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to create output file {:?}: {}", path, e));
-        }))
+        if options.tee {
+            info("Also writing output to stdout (--tee).", verbose);
+            Box::new(TeeWriter { file: Some(create_output(path)), stdout: Some(io::stdout()) })
+        } else {
+            Box::new(create_output(path))
+        }
     } else {
         info("Outputting to stdout.", verbose);
         Box::new(io::stdout())
     };
 
-    write_strings(&mut writer, &strings, verbose);
+    if options.format == OutputFormat::Csv && !options.region_analysis {
+        write_csv_header(&mut writer, multi_file, &options);
+    }
+
+    let mut histogram = LengthHistogram::default();
+    let start = Instant::now();
+    let mut total_bytes: u64 = 0;
+    let mut total_strings: usize = 0;
+    let mut remaining = options.max_strings;
+    if options.threads > 1 {
+        info(&format!("Scanning with {} threads.", options.threads), verbose);
+        let (bytes, strings) = scan_parallel(&files, &options, &mut writer, multi_file, &mut histogram);
+        total_bytes = bytes;
+        total_strings = strings;
+    } else {
+        for path in &files {
+            if remaining == Some(0) {
+                info("String limit reached. Skipping remaining files.", verbose);
+                break;
+            }
+            if let Some(offsets) = &options.target_offsets {
+                info(&format!("Extracting strings at {} offset(s) of {:?}.", offsets.len(), path), verbose);
+                let mut strings = extract_at_offsets(path, offsets, &options, remaining);
+                add_context(path, &mut strings, &options);
+                total_strings += strings.len();
+                write_strings(&mut writer, &strings, None, &options);
+                continue;
+            }
+            let budget = options.max_input_bytes.map(|limit| limit - total_bytes);
+            let prefix = multi_file.then_some(path.as_path());
+            let Some((scan, bytes_read)) = scan_file(path, &options, budget, remaining, Some((&mut writer, prefix))) else {
+                continue;
+            };
+            total_bytes += bytes_read;
+            let found = write_scan(&mut writer, scan, prefix, &options, &mut histogram);
+            total_strings += found;
+            remaining = remaining.map(|n| n - found);
+        }
+    }
+
+    if options.length_histogram {
+        histogram.write(&mut writer, &options);
+    }
+
+    if options.benchmark {
+        report_benchmark(total_bytes, total_strings, start.elapsed().as_secs_f64());
+    }
 
+    finish_output(writer, options.output_path.as_deref());
     info("Binary string extraction complete.", verbose);
+    if options.quiet && total_strings == 0 {
+        info("No string matched the filter.", verbose);
+        process::exit(1);
+    }
     process::exit(0);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Parses `args` as if given on the command line, without a defaults file.
+    fn options(args: &[&str]) -> Options {
+        options_from_args(["binary_string_extractor"].iter().chain(args).map(|arg| arg.to_string()).collect())
+    }
+
+    #[test]
+    fn run_spanning_read_boundary_is_one_string() {
+        let mut input = vec![0u8; 4090];
+        input.extend_from_slice(b"BOUNDARY_SPANNING_STRING_MARKER");
+        input.extend_from_slice(&[0; 16]);
+        let strings = extract_strings(&mut Cursor::new(input), &options(&["-i", "-"]), None);
+        assert_eq!(strings.len(), 1);
+        assert_eq!(strings[0].value, "BOUNDARY_SPANNING_STRING_MARKER");
+        assert_eq!(strings[0].offset, 4090);
+        assert_eq!(strings[0].length, 31);
+    }
+}
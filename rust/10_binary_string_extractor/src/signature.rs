@@ -0,0 +1,124 @@
+// signature.rs
+//
+// Byte signature scanning used by `--signature`.
+//
+// A signature is a sequence of hex bytes in which `??` matches any byte, such as
+// `4D 5A ?? ?? 50 45`. The input is read in fixed-size chunks and the signature is tried at every
+// offset. The last `length - 1` bytes of each chunk are carried over to the next one, so matches
+// that span a chunk boundary are found while memory stays bounded by the chunk size plus the
+// signature length. Overlapping matches are all reported, each one as soon as it is found.
+// Standard library only, in keeping with the rest of the tool.
+
+use std::io::{self, Read};
+
+/// Bytes read from the input at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A parsed `--signature`: one entry per byte, `None` for a `??` wildcard.
+pub struct Signature {
+    bytes: Vec<Option<u8>>,
+}
+
+/// Signature matches kept until they can be written, for files scanned by `--threads` workers.
+/// Only the offsets and the bytes under `??` wildcards are stored; the other bytes of a match are
+/// those of the signature.
+#[derive(Default)]
+pub struct HeldMatches {
+    offsets: Vec<u64>,
+    wildcard_bytes: Vec<u8>,
+}
+
+impl HeldMatches {
+    /// Stores the match of `signature` at `offset`, where `window` holds the matched bytes.
+    pub fn push(&mut self, signature: &Signature, offset: u64, window: &[u8]) {
+        self.offsets.push(offset);
+        let wildcards = signature.bytes.iter().zip(window).filter(|(expected, _)| expected.is_none());
+        self.wildcard_bytes.extend(wildcards.map(|(_, &byte)| byte));
+    }
+
+    /// Calls `on_match` with the offset and bytes of each held match, in the order found.
+    pub fn for_each(&self, signature: &Signature, mut on_match: impl FnMut(u64, &[u8])) {
+        let mut bytes = vec![0; signature.len()];
+        let mut wildcard_bytes = self.wildcard_bytes.iter();
+        for &offset in &self.offsets {
+            for (byte, expected) in bytes.iter_mut().zip(&signature.bytes) {
+                *byte = expected.or_else(|| wildcard_bytes.next().copied()).unwrap_or_default();
+            }
+            on_match(offset, &bytes);
+        }
+    }
+}
+
+impl Signature {
+    /// Parses pairs of hex digits or `??`, optionally separated by whitespace (`4D 5A ?? 90`
+    /// and `4D5A??90` are the same signature). At least one byte must not be a wildcard.
+    pub fn parse(text: &str) -> Result<Signature, String> {
+        let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.is_empty() {
+            return Err("the signature is empty".to_string());
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Err("expected pairs of hex digits or '??', but the digit count is odd".to_string());
+        }
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| {
+                let pair: String = pair.iter().collect();
+                if pair == "??" {
+                    Ok(None)
+                } else if pair.chars().all(|c| c.is_ascii_hexdigit()) {
+                    Ok(u8::from_str_radix(&pair, 16).ok())
+                } else {
+                    Err(format!("'{}' is not a hex byte or '??'", pair))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if bytes.iter().all(Option::is_none) {
+            return Err("the signature needs at least one byte that is not '??'".to_string());
+        }
+        Ok(Signature { bytes })
+    }
+
+    /// Number of bytes in the signature, wildcards included.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns true if `window`, which is exactly as long as the signature, matches it.
+    fn matches(&self, window: &[u8]) -> bool {
+        self.bytes.iter().zip(window).all(|(expected, &byte)| expected.is_none_or(|expected| expected == byte))
+    }
+
+    /// Reads `reader` to the end and calls `on_match` with the offset and bytes of every match as
+    /// it is found. Returns the number of matches.
+    pub fn scan<R: Read>(&self, reader: &mut R, mut on_match: impl FnMut(u64, &[u8])) -> io::Result<u64> {
+        let length = self.len();
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut buffer: Vec<u8> = Vec::with_capacity(CHUNK_SIZE + length);
+        let mut base: u64 = 0; // Input offset of buffer[0]
+        let mut matches: u64 = 0;
+        loop {
+            let bytes_read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            buffer.extend_from_slice(&chunk[..bytes_read]);
+            if buffer.len() < length {
+                continue;
+            }
+            for (index, window) in buffer.windows(length).enumerate() {
+                if self.matches(window) {
+                    on_match(base + index as u64, window);
+                    matches += 1;
+                }
+            }
+            // A match starting in the last `length - 1` bytes needs bytes from the next chunk.
+            let consumed = buffer.len() - (length - 1);
+            buffer.drain(..consumed);
+            base += consumed as u64;
+        }
+        Ok(matches)
+    }
+}
@@ -0,0 +1,107 @@
+// highlight.rs
+//
+// Match highlighting used by `--color`.
+//
+// The spans to color come from the regex engine as byte ranges: the whole match and each capture
+// group. Every character is painted in the color of the innermost span covering it, so a group
+// stands out from the match around it, nested groups from their parents, and overlapping matches
+// of several patterns merge instead of producing nested escape sequences. Zero-width spans cover
+// no characters and paint nothing. The color is reset before every line break and at the end of
+// the text, so an escape sequence never carries over into the next output line.
+// Standard library only, in keeping with the rest of the tool.
+
+use crate::regex::{Regex, Visited};
+
+/// Resets all text attributes.
+const RESET: &str = "\x1b[0m";
+/// Color of a whole match: bold red, as grep uses.
+const MATCH_COLOR: &str = "\x1b[1;31m";
+/// Colors of capture groups 1, 2, ..., reused in turn for further groups.
+const GROUP_COLORS: &[&str] = &["\x1b[1;32m", "\x1b[1;33m", "\x1b[1;34m", "\x1b[1;35m", "\x1b[1;36m"];
+
+/// A byte range of the text to color, and the capture group it belongs to (0 for the whole match).
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub group: usize,
+}
+
+impl Span {
+    fn color(&self) -> &'static str {
+        match self.group {
+            0 => MATCH_COLOR,
+            group => GROUP_COLORS[(group - 1) % GROUP_COLORS.len()],
+        }
+    }
+}
+
+/// Converts the group offsets returned by `Regex::spans` into spans, skipping groups that did
+/// not participate in the match.
+pub fn group_spans(groups: &[Option<(usize, usize)>]) -> impl Iterator<Item = Span> + '_ {
+    groups.iter().enumerate().filter_map(|(group, span)| span.map(|(start, end)| Span { start, end, group }))
+}
+
+/// Returns the spans of every match of `regex` in `text`, scanning left to right. The next
+/// match is searched from the end of the previous one, or one character further after an empty
+/// match, so the scan always advances. `text` must be no longer than `Regex::max_text_len`.
+pub fn match_spans(regex: &Regex, text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut visited = Visited::default();
+    let mut from = 0;
+    while let Some(groups) = regex.spans(text, from, text.len(), &mut visited) {
+        let Some((start, end)) = groups[0] else {
+            break;
+        };
+        spans.extend(group_spans(&groups));
+        from = if end > start {
+            end
+        } else {
+            match text[end..].chars().next() {
+                Some(c) => end + c.len_utf8(),
+                None => break,
+            }
+        };
+    }
+    spans
+}
+
+/// Returns `text` with each span wrapped in its color. Spans must start and end on character
+/// boundaries, as regex offsets do; parts beyond the end of `text` are ignored.
+pub fn paint(text: &str, spans: &[Span]) -> String {
+    if spans.iter().all(|span| span.start >= span.end) {
+        return text.to_string();
+    }
+    // For each byte, the index of the innermost span covering it: the shortest, or on a tie the
+    // later group, which is nested inside the earlier one.
+    let mut owner: Vec<Option<usize>> = vec![None; text.len()];
+    let rank = |span: &Span| (span.end - span.start, usize::MAX - span.group);
+    for (index, span) in spans.iter().enumerate() {
+        let end = span.end.min(text.len());
+        for slot in owner.iter_mut().take(end).skip(span.start) {
+            if slot.is_none_or(|other| rank(span) < rank(&spans[other])) {
+                *slot = Some(index);
+            }
+        }
+    }
+
+    let mut painted = String::with_capacity(text.len() + 16 * spans.len());
+    let mut current: Option<&str> = None;
+    for (index, c) in text.char_indices() {
+        let wanted = if c == '\n' { None } else { owner[index].map(|span| spans[span].color()) };
+        if wanted != current {
+            if current.is_some() {
+                painted.push_str(RESET);
+            }
+            if let Some(color) = wanted {
+                painted.push_str(color);
+            }
+            current = wanted;
+        }
+        painted.push(c);
+    }
+    if current.is_some() {
+        painted.push_str(RESET);
+    }
+    painted
+}
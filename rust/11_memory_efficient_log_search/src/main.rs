@@ -15,27 +15,160 @@
 // - CLI-Only Interface: Focuses purely on the search logic.
 // - One Tool = One Problem: Dedicated to memory-efficient log pattern searching.
 
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
 use std::env;
-use std::fs::File;
-use std::io::{self, BufReader, BufRead, Write};
-use std::path::PathBuf;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufRead, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+mod highlight;
+mod patterns;
+mod regex;
+use highlight::{group_spans, match_spans, paint, Span};
+use patterns::PatternSet;
+use regex::{Regex, Visited};
 
 // Constants for output formatting
 const INFO_PREFIX: &str = "[INFO] ";
+const WARNING_PREFIX: &str = "[WARNING] ";
 const ERROR_PREFIX: &str = "[ERROR] ";
 
+// Tool identity reported by --version
+const TOOL_NAME: &str = "Memory-Efficient Log Search";
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Default and maximum --window-lines for --multiline
+const DEFAULT_WINDOW_LINES: usize = 10;
+const MAX_WINDOW_LINES: usize = 1000;
+
 // --- Shared Abstractions ---
 // Consistent CLI Argument Parsing: Uses `std::env::args` for CLI flags.
-// Standardized Error Handling & Exit Codes: Exits with 0 on success, non-zero on error.
-// Unified Logging/Output Format: Uses INFO, ERROR prefixes.
+// Standardized Error Handling & Exit Codes: Exits with 0 on success, 1 on errors, and 4 on I/O
+//   errors (see EXIT_IO_ERROR).
+// Unified Logging/Output Format: Uses INFO, WARNING, ERROR prefixes.
 
 /// Prints an error message to stderr and exits the program with a non-zero status code.
 fn fatal_error(message: &str) -> ! {
     eprintln!("{}{}", ERROR_PREFIX, message);
+    discard_partial_output();
     process::exit(1);
 }
 
+/// Exit status for I/O failures, such as an input that cannot be read or an output that cannot be
+/// written, so that scripts can tell them apart from exit status 1.
+const EXIT_IO_ERROR: i32 = 4;
+
+/// Prints an I/O error message to stderr and exits with `EXIT_IO_ERROR`.
+fn io_error(message: &str) -> ! {
+    eprintln!("{}{}", ERROR_PREFIX, message);
+    discard_partial_output();
+    process::exit(EXIT_IO_ERROR);
+}
+
+/// Path of the in-progress `-o` file, if any. `fatal_error` and `io_error` remove it so that a
+/// failed run never leaves a truncated file behind.
+static PARTIAL_OUTPUT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Removes the in-progress output file registered by `create_output`, if any.
+fn discard_partial_output() {
+    // try_lock: never block (or deadlock) on the way out of the process.
+    if let Ok(mut partial) = PARTIAL_OUTPUT.try_lock() {
+        if let Some(path) = partial.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Creates a buffered writer for the `-o` file. Output goes to `<FILE>.partial` and is only
+/// renamed to `<FILE>` by `finish_output` once everything has been written and flushed.
+fn create_output(path: &Path) -> BufWriter<File> {
+    let mut partial_name = path.file_name().unwrap_or_default().to_os_string();
+    partial_name.push(".partial");
+    let partial_path = path.with_file_name(partial_name);
+    let file = File::create(&partial_path).unwrap_or_else(|e| {
+        io_error(&format!("Failed to create output file {:?}: {}", partial_path, e));
+    });
+    *PARTIAL_OUTPUT.lock().unwrap_or_else(|e| e.into_inner()) = Some(partial_path);
+    BufWriter::new(file)
+}
+
+/// Set when a `--tee` destination fails. The run still finishes on the other destination,
+/// but `finish_output` then exits non-zero.
+static TEE_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Writer for `--tee`: copies all output to the `-o` file and to stdout. A destination that
+/// fails is dropped with a warning, so the other one still receives the complete output.
+struct TeeWriter {
+    file: Option<BufWriter<File>>,
+    stdout: Option<io::Stdout>,
+}
+
+impl TeeWriter {
+    /// Applies `op` to each remaining destination, dropping any that fail. Errors only once
+    /// both destinations are gone.
+    fn each(&mut self, mut op: impl FnMut(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            if let Err(e) = op(file) {
+                warn(&format!("--tee: writing to the output file failed: {}", e));
+                self.file = None;
+                discard_partial_output(); // Never leave a truncated file behind.
+                TEE_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        if let Some(stdout) = &mut self.stdout {
+            if let Err(e) = op(stdout) {
+                warn(&format!("--tee: writing to stdout failed: {}", e));
+                self.stdout = None;
+                TEE_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        if self.file.is_none() && self.stdout.is_none() {
+            return Err(io::Error::other("both --tee destinations failed"));
+        }
+        Ok(())
+    }
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.each(|writer| writer.write_all(buf)).map(|()| buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.each(|writer| writer.flush())
+    }
+}
+
+/// Flushes the output and, for `-o` files, moves the completed file into place.
+/// Exits non-zero if a `--tee` destination failed along the way.
+fn finish_output(mut writer: Box<dyn Write>, output_path: Option<&Path>) {
+    writer.flush().unwrap_or_else(|e| {
+        io_error(&format!("Failed to write to output: {}", e));
+    });
+    drop(writer); // Close the file before renaming it.
+
+    let partial = PARTIAL_OUTPUT.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let (Some(partial_path), Some(path)) = (partial, output_path) {
+        if let Err(e) = fs::rename(&partial_path, path) {
+            let _ = fs::remove_file(&partial_path);
+            io_error(&format!("Failed to move completed output into place at {:?}: {}", path, e));
+        }
+    }
+    if TEE_FAILED.load(Ordering::Relaxed) {
+        io_error("--tee: one output destination failed, so its output is incomplete (see warning above).");
+    }
+}
+
+/// Prints a warning message to stderr.
+fn warn(message: &str) {
+    eprintln!("{}{}", WARNING_PREFIX, message);
+}
+
 /// Prints an informational message to stderr if verbose mode is enabled.
 fn info(message: &str, verbose: bool) {
     if verbose {
@@ -43,17 +176,253 @@ fn info(message: &str, verbose: bool) {
     }
 }
 
-/// Parses command-line arguments.
-/// Returns (input_file_path, pattern, output_file_path, before_context, after_context, case_sensitive, verbose)
-fn parse_args() -> (PathBuf, String, Option<PathBuf>, usize, usize, bool, bool) {
-    let args: Vec<String> = env::args().collect();
+/// How a trailing carriage return (from CRLF line endings) is treated in output.
+/// Matching always ignores it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CrlfMode {
+    Keep,
+    Strip,
+}
+
+/// Output format selected with --format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    /// Plain lines, with optional file banners (default).
+    Text,
+    /// One JSON object per output line, for SIEM ingestion.
+    Ndjson,
+}
+
+/// When to highlight matches, selected with --color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorMode {
+    /// Only when writing text to a terminal, and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    /// Plain output (default).
+    Never,
+}
+
+/// A `--hex` search pattern: the lowercase hex digits and the bytes they encode.
+struct HexPattern {
+    digits: String,
+    bytes: Vec<u8>,
+}
+
+impl HexPattern {
+    /// Parses hex such as `deadbeef`, `0xDEADBEEF` or `de:ad:be:ef`. Spaces, ':' and '-'
+    /// between bytes are ignored.
+    fn parse(pattern: &str) -> Result<HexPattern, String> {
+        let trimmed = pattern.trim();
+        let body = trimmed.strip_prefix("0x").or_else(|| trimmed.strip_prefix("0X")).unwrap_or(trimmed);
+        let digits: String = body
+            .chars()
+            .filter(|c| !matches!(c, ' ' | ':' | '-'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        if let Some(bad) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!("'{}' is not a hex digit", bad));
+        }
+        if digits.is_empty() || !digits.len().is_multiple_of(2) {
+            return Err("expected a non-empty, even number of hex digits".to_string());
+        }
+        let bytes = (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok(HexPattern { digits, bytes })
+    }
+
+    /// Returns true if `line` contains the pattern as hex text in any case, or as raw bytes.
+    /// Hex text is matched at any digit position, so it may also match across byte boundaries
+    /// (e.g. `adbe` inside `DEADBEEF`).
+    fn matches(&self, line: &str) -> bool {
+        let line = line.as_bytes();
+        line.windows(self.digits.len()).any(|w| w.eq_ignore_ascii_case(self.digits.as_bytes()))
+            || line.windows(self.bytes.len()).any(|w| w == self.bytes.as_slice())
+    }
+}
+
+/// Command-line options controlling the search.
+struct Options {
+    input_paths: Vec<PathBuf>,
+    patterns: Vec<String>,
+    matcher: PatternSet,
+    output_path: Option<PathBuf>,
+    tee: bool,
+    before_context: usize,
+    after_context: usize,
+    case_sensitive: bool,
+    excludes: Vec<String>,
+    crlf: CrlfMode,
+    show_filename: bool,
+    format: OutputFormat,
+    /// True if matches are highlighted with ANSI colors (--color).
+    color: bool,
+    /// With --color or --only-matching, the literal search patterns compiled as regexes, to find
+    /// their match spans.
+    literal_regexes: Vec<Regex>,
+    hex: Option<HexPattern>,
+    extract: Option<Regex>,
+    multiline: Option<Regex>,
+    window_lines: usize,
+    /// The longest line, in bytes, that the regexes in use can search (see `Regex::max_text_len`).
+    /// They search only the start of a longer line. `usize::MAX` when no regex is used.
+    regex_line_limit: usize,
+    isolated: Option<usize>,
+    count_by_pattern: bool,
+    only_matching: bool,
+    unique_count: bool,
+    max_line_bytes: Option<usize>,
+    max_input_bytes: Option<u64>,
+    /// First line of each file to search (1-based).
+    start_line: usize,
+    /// Last line of each file to search; reading stops after it.
+    end_line: Option<usize>,
+    verbose: bool,
+}
+
+/// Parses a byte count such as `4096`, `64K`, `16M` or `2G` (binary multiples). Zero is rejected.
+fn parse_byte_size(value: &str, flag: &str) -> u64 {
+    let (digits, multiplier) = match value.to_ascii_uppercase().chars().last() {
+        Some('K') => (&value[..value.len() - 1], 1 << 10),
+        Some('M') => (&value[..value.len() - 1], 1 << 20),
+        Some('G') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| {
+            fatal_error(&format!(
+                "Invalid value for {}: '{}'. Must be a positive byte count, optionally with a K, M or G suffix.",
+                flag, value
+            ));
+        })
+}
+
+/// Name of the per-user defaults file, read from `$XDG_CONFIG_HOME`, or from `~/.config` when
+/// that is not set.
+const DEFAULTS_FILE_NAME: &str = "memory_efficient_log_search.conf";
+
+/// Short flags and the long options they stand for, so that a command-line flag overrides its
+/// default however it is spelled.
+const SHORT_FLAGS: &[(&str, &str)] = &[("-i", "input"), ("-p", "pattern"), ("-o", "output"), ("-b", "before-context"), ("-a", "after-context"), ("-c", "case-sensitive"), ("-e", "exclude"), ("-H", "with-filename"), ("-v", "verbose")];
+
+/// Groups of long options that set the same thing or rule each other out. An option from a group
+/// given on the command line drops the defaults for every option in the group.
+const OPTION_GROUPS: &[&[&str]] = &[&["line-start", "anchored"], &["pattern", "pattern-file", "pattern-from-file"], &["extract", "only-matching", "count-by-pattern"]];
+
+/// Returns the path of the defaults file, or `None` if no config directory is known.
+fn defaults_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME").filter(|home| !home.is_empty())?).join(".config"),
+    };
+    Some(dir.join(DEFAULTS_FILE_NAME))
+}
+
+/// Inserts the options of the defaults file between the program name and the command-line
+/// arguments. Each `option = value` line stands for `--option value`; `true` stands for the bare
+/// flag and `false` leaves it out. A flag given on the command line drops every default for the
+/// same option and for the rest of its `OPTION_GROUPS` entry, so the command line wins over the
+/// file and the file over the built-in defaults.
+/// A missing file is not an error, and `--no-defaults` skips the file altogether.
+fn with_defaults(mut args: Vec<String>) -> Vec<String> {
+    if args.iter().any(|arg| arg == "--no-defaults") {
+        args.retain(|arg| arg != "--no-defaults");
+        return args;
+    }
+    let Some(path) = defaults_path() else {
+        return args;
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return args,
+        Err(e) => io_error(&format!("Failed to read defaults file {:?}: {}", path, e)),
+    };
+    let given: Vec<String> = args
+        .iter()
+        .skip(1)
+        .filter_map(|arg| match arg.strip_prefix("--") {
+            Some(long) => Some(long.to_string()),
+            None => SHORT_FLAGS.iter().find(|(short, _)| short == arg).map(|(_, long)| long.to_string()),
+        })
+        .collect();
+    let overridden: Vec<&str> = OPTION_GROUPS
+        .iter()
+        .filter(|group| group.iter().any(|option| given.iter().any(|flag| flag == option)))
+        .flat_map(|group| group.iter().copied())
+        .collect();
+    let mut defaults = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            fatal_error(&format!(
+                "Malformed line {} in defaults file {:?}: expected 'option = value'.",
+                line_index + 1,
+                path
+            ));
+        };
+        let key = key.trim();
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        if given.iter().any(|flag| flag == key) || overridden.contains(&key) || value == "false" {
+            continue;
+        }
+        defaults.push(format!("--{}", key));
+        if value != "true" {
+            defaults.push(value.to_string());
+        }
+    }
+    args.splice(1..1, defaults);
+    args
+}
+
+/// Parses command-line arguments, after those of the defaults file, into an `Options` value.
+fn parse_args() -> Options {
+    options_from_args(with_defaults(env::args().collect()))
+}
 
-    let mut input_file_path: Option<PathBuf> = None;
-    let mut pattern: Option<String> = None;
+/// Parses an argument list, starting with the program name, into an `Options` value.
+fn options_from_args(args: Vec<String>) -> Options {
+
+    let mut input_file_paths: Vec<PathBuf> = Vec::new();
+    let mut patterns: Vec<String> = Vec::new();
+    let mut pattern_file: Option<PathBuf> = None;
+    let mut pattern_from_file: Option<PathBuf> = None;
     let mut output_file_path: Option<PathBuf> = None;
+    let mut tee = false;
     let mut before_context: usize = 0;
     let mut after_context: usize = 0;
     let mut case_sensitive = false;
+    let mut excludes: Vec<String> = Vec::new();
+    let mut crlf = CrlfMode::Strip;
+    let mut filename_override: Option<bool> = None;
+    let mut extract_pattern: Option<String> = None;
+    let mut format = OutputFormat::Text;
+    let mut color_mode = ColorMode::Never;
+    let mut hex = false;
+    let mut multiline = false;
+    let mut window_lines: Option<usize> = None;
+    let mut isolated: Option<usize> = None;
+    let mut count_by_pattern = false;
+    let mut only_matching = false;
+    let mut line_start = false;
+    let mut unique_count = false;
+    let mut max_line_bytes: Option<usize> = None;
+    let mut max_input_bytes: Option<u64> = None;
+    let mut start_line: usize = 1;
+    let mut end_line: Option<usize> = None;
     let mut verbose = false;
 
     // Skip the first argument which is the program name
@@ -63,7 +432,7 @@ fn parse_args() -> (PathBuf, String, Option<PathBuf>, usize, usize, bool, bool)
             "-i" | "--input" => {
                 i += 1;
                 if i < args.len() {
-                    input_file_path = Some(PathBuf::from(&args[i]));
+                    input_file_paths.push(PathBuf::from(&args[i]));
                 } else {
                     fatal_error("Missing value for --input");
                 }
@@ -71,11 +440,27 @@ fn parse_args() -> (PathBuf, String, Option<PathBuf>, usize, usize, bool, bool)
             "-p" | "--pattern" => {
                 i += 1;
                 if i < args.len() {
-                    pattern = Some(args[i].clone());
+                    patterns.push(args[i].clone());
                 } else {
                     fatal_error("Missing value for --pattern");
                 }
             }
+            "--pattern-file" => {
+                i += 1;
+                if i < args.len() {
+                    pattern_file = Some(PathBuf::from(&args[i]));
+                } else {
+                    fatal_error("Missing value for --pattern-file");
+                }
+            }
+            "--pattern-from-file" => {
+                i += 1;
+                if i < args.len() {
+                    pattern_from_file = Some(PathBuf::from(&args[i]));
+                } else {
+                    fatal_error("Missing value for --pattern-from-file");
+                }
+            }
             "-o" | "--output" => {
                 i += 1;
                 if i < args.len() {
@@ -107,6 +492,144 @@ fn parse_args() -> (PathBuf, String, Option<PathBuf>, usize, usize, bool, bool)
             "-c" | "--case-sensitive" => {
                 case_sensitive = true;
             }
+            "-e" | "--exclude" => {
+                i += 1;
+                if i < args.len() {
+                    excludes.push(args[i].clone());
+                } else {
+                    fatal_error("Missing value for --exclude");
+                }
+            }
+            "--crlf" => {
+                i += 1;
+                if i < args.len() {
+                    crlf = match args[i].as_str() {
+                        "keep" => CrlfMode::Keep,
+                        "strip" => CrlfMode::Strip,
+                        other => fatal_error(&format!("Invalid value for --crlf: '{}'. Choose 'keep' or 'strip'.", other)),
+                    };
+                } else {
+                    fatal_error("Missing value for --crlf");
+                }
+            }
+            "-H" | "--with-filename" => {
+                filename_override = Some(true);
+            }
+            "--no-filename" => {
+                filename_override = Some(false);
+            }
+            "--format" => {
+                i += 1;
+                if i < args.len() {
+                    format = match args[i].as_str() {
+                        "text" => OutputFormat::Text,
+                        "ndjson" => OutputFormat::Ndjson,
+                        other => fatal_error(&format!("Invalid value for --format: '{}'. Choose 'text' or 'ndjson'.", other)),
+                    };
+                } else {
+                    fatal_error("Missing value for --format");
+                }
+            }
+            "--color" => {
+                i += 1;
+                if i < args.len() {
+                    color_mode = match args[i].as_str() {
+                        "auto" => ColorMode::Auto,
+                        "always" => ColorMode::Always,
+                        "never" => ColorMode::Never,
+                        other => fatal_error(&format!("Invalid value for --color: '{}'. Choose 'auto', 'always' or 'never'.", other)),
+                    };
+                } else {
+                    fatal_error("Missing value for --color");
+                }
+            }
+            "--hex" => {
+                hex = true;
+            }
+            "--line-start" | "--anchored" => {
+                line_start = true;
+            }
+            "--extract" => {
+                i += 1;
+                if i < args.len() {
+                    extract_pattern = Some(args[i].clone());
+                } else {
+                    fatal_error("Missing value for --extract");
+                }
+            }
+            "--multiline" => {
+                multiline = true;
+            }
+            "--window-lines" => {
+                i += 1;
+                if i < args.len() {
+                    let lines = args[i].parse::<usize>().ok().filter(|n| (2..=MAX_WINDOW_LINES).contains(n));
+                    window_lines = Some(lines.unwrap_or_else(|| {
+                        fatal_error(&format!("Invalid value for --window-lines. Must be an integer from 2 to {}.", MAX_WINDOW_LINES));
+                    }));
+                } else {
+                    fatal_error("Missing value for --window-lines");
+                }
+            }
+            "--isolated" => {
+                i += 1;
+                if i < args.len() {
+                    isolated = Some(args[i].parse::<usize>().ok().filter(|&n| n > 0).unwrap_or_else(|| {
+                        fatal_error("Invalid value for --isolated. Must be a positive integer.");
+                    }));
+                } else {
+                    fatal_error("Missing value for --isolated");
+                }
+            }
+            "--tee" => {
+                tee = true;
+            }
+            "--count-by-pattern" => {
+                count_by_pattern = true;
+            }
+            "--only-matching" => {
+                only_matching = true;
+            }
+            "--unique-count" => {
+                unique_count = true;
+            }
+            "--max-line-bytes" => {
+                i += 1;
+                if i < args.len() {
+                    let limit = parse_byte_size(&args[i], "--max-line-bytes");
+                    max_line_bytes = Some(usize::try_from(limit).unwrap_or(usize::MAX));
+                } else {
+                    fatal_error("Missing value for --max-line-bytes");
+                }
+            }
+            "--max-input-bytes" => {
+                i += 1;
+                if i < args.len() {
+                    max_input_bytes = Some(parse_byte_size(&args[i], "--max-input-bytes"));
+                } else {
+                    fatal_error("Missing value for --max-input-bytes");
+                }
+            }
+            "--start-line" => {
+                i += 1;
+                if i < args.len() {
+                    start_line = args[i].parse::<usize>().ok().filter(|&n| n > 0).unwrap_or_else(|| {
+                        fatal_error("Invalid value for --start-line. Must be a positive line number.");
+                    });
+                } else {
+                    fatal_error("Missing value for --start-line");
+                }
+            }
+            "--end-line" => {
+                i += 1;
+                if i < args.len() {
+                    end_line = Some(args[i].parse::<usize>().ok().filter(|&n| n > 0).unwrap_or_else(|| {
+                        fatal_error("Invalid value for --end-line. Must be a positive line number.");
+                    }));
+                } else {
+                    fatal_error("Missing value for --end-line");
+                }
+            }
             "-v" | "--verbose" => {
                 verbose = true;
             }
@@ -114,6 +637,10 @@ fn parse_args() -> (PathBuf, String, Option<PathBuf>, usize, usize, bool, bool)
                 print_help();
                 process::exit(0);
             }
+            "--version" => {
+                print_version();
+                process::exit(0);
+            }
             _ => {
                 fatal_error(&format!("Unknown argument: {}", args[i]));
             }
@@ -121,16 +648,217 @@ fn parse_args() -> (PathBuf, String, Option<PathBuf>, usize, usize, bool, bool)
         i += 1;
     }
 
-    let input_path = input_file_path.unwrap_or_else(|| {
+    if input_file_paths.is_empty() {
         print_help();
         fatal_error("Input file path is required.");
+    }
+    if let Some(path) = &pattern_file {
+        let loaded = read_pattern_file(path);
+        if loaded.is_empty() {
+            fatal_error(&format!("Pattern file {:?} contains no patterns.", path));
+        }
+        patterns.extend(loaded);
+    }
+    if let Some(path) = &pattern_from_file {
+        if hex || multiline {
+            fatal_error("--pattern-from-file reads a literal pattern and cannot be combined with --hex or --multiline.");
+        }
+        patterns.push(read_pattern_from_file(path));
+    }
+    // With --extract the regex selects lines on its own, so -p becomes an optional pre-filter.
+    if patterns.is_empty() {
+        if extract_pattern.is_none() {
+            print_help();
+            fatal_error("Search pattern is required.");
+        }
+        patterns.push(String::new());
+    }
+
+    let hex = hex.then(|| {
+        if patterns.len() > 1 {
+            fatal_error("--hex takes a single hex pattern given with -p and cannot be combined with more patterns.");
+        }
+        if patterns[0].is_empty() {
+            fatal_error("--hex requires a hex pattern given with -p.");
+        }
+        HexPattern::parse(&patterns[0]).unwrap_or_else(|e| {
+            fatal_error(&format!("Invalid --hex pattern '{}': {}", patterns[0], e));
+        })
     });
-    let search_pattern = pattern.unwrap_or_else(|| {
-        print_help();
-        fatal_error("Search pattern is required.");
+    if line_start && (hex.is_some() || multiline) {
+        fatal_error("--line-start cannot be combined with --hex or --multiline. Start a --multiline regex with '^' instead.");
+    }
+    let matcher = PatternSet::new(&patterns, !case_sensitive, line_start);
+
+    let extract = extract_pattern.map(|source| {
+        let regex = Regex::new(&source, !case_sensitive).unwrap_or_else(|e| {
+            fatal_error(&format!("Invalid --extract regex '{}': {}", source, e));
+        });
+        if regex.group_count() == 0 {
+            fatal_error(&format!("--extract regex '{}' must contain a capture group.", source));
+        }
+        if before_context > 0 || after_context > 0 {
+            fatal_error("--extract cannot be combined with --before-context or --after-context.");
+        }
+        regex
+    });
+
+    if window_lines.is_some() && !multiline {
+        fatal_error("--window-lines requires --multiline.");
+    }
+    let multiline = multiline.then(|| {
+        if patterns.len() != 1 || extract.is_some() || hex.is_some() || count_by_pattern {
+            fatal_error("--multiline takes a single regex given with -p and cannot be combined with --pattern-file, --extract, --hex or --count-by-pattern.");
+        }
+        if before_context > 0 || after_context > 0 {
+            fatal_error("--multiline cannot be combined with --before-context or --after-context.");
+        }
+        let regex = Regex::new(&patterns[0], !case_sensitive).unwrap_or_else(|e| {
+            fatal_error(&format!("Invalid --multiline regex '{}': {}", patterns[0], e));
+        });
+        if regex.has_nested_unbounded_repeat() {
+            fatal_error(&format!(
+                "--multiline regex '{}' nests unbounded repetitions (e.g. '(a+)*'), which is rejected. Bound the inner or outer repetition with {{n,m}}.",
+                patterns[0]
+            ));
+        }
+        regex
+    });
+
+    if count_by_pattern && (extract.is_some() || hex.is_some() || before_context > 0 || after_context > 0) {
+        fatal_error("--count-by-pattern cannot be combined with --extract, --hex, --before-context or --after-context.");
+    }
+
+    if isolated.is_some()
+        && (extract.is_some() || multiline.is_some() || count_by_pattern || before_context > 0 || after_context > 0)
+    {
+        fatal_error("--isolated cannot be combined with --extract, --multiline, --count-by-pattern, --before-context or --after-context.");
+    }
+
+    if let Some(end) = end_line.filter(|&end| end < start_line) {
+        fatal_error(&format!("--end-line ({}) must not be before --start-line ({}).", end, start_line));
+    }
+
+    if only_matching {
+        if extract.is_some() {
+            fatal_error("--only-matching cannot be combined with --extract, which already prints only the captured text.");
+        }
+        if patterns.iter().all(String::is_empty) {
+            fatal_error("--only-matching requires a non-empty pattern given with -p, --pattern-file or --pattern-from-file.");
+        }
+        if hex.is_some() || multiline.is_some() || isolated.is_some() || count_by_pattern || before_context > 0 || after_context > 0 {
+            fatal_error("--only-matching cannot be combined with --hex, --multiline, --isolated, --count-by-pattern, --before-context or --after-context.");
+        }
+    }
+    if unique_count && !only_matching && extract.is_none() {
+        fatal_error("--unique-count counts the values printed by --only-matching or --extract; give one of them.");
+    }
+
+    if tee && output_file_path.is_none() {
+        fatal_error("--tee requires an output file given with -o.");
+    }
+
+    if color_mode == ColorMode::Always && format == OutputFormat::Ndjson {
+        fatal_error("--color always cannot be combined with --format ndjson.");
+    }
+    let color = match color_mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            format == OutputFormat::Text
+                && output_file_path.is_none()
+                && env::var_os("NO_COLOR").is_none()
+                && io::stdout().is_terminal()
+        }
+    };
+    // Literal patterns are highlighted and extracted through the regex engine, which reports match
+    // spans in the original line for case-insensitive searches too. Regex searches use their own spans.
+    let literal_regexes = if (color || only_matching) && extract.is_none() && multiline.is_none() && hex.is_none() {
+        patterns
+            .iter()
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| if line_start { format!("^{}", regex::escape(pattern)) } else { regex::escape(pattern) })
+            .filter_map(|pattern| Regex::new(&pattern, !case_sensitive).ok())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Each line of a --multiline window must fit, with its '\n', in the joined text the regex searches.
+    let window_lines = window_lines.unwrap_or(DEFAULT_WINDOW_LINES);
+    let regex_line_limit = match &multiline {
+        Some(regex) => ((regex.max_text_len() + 1) / window_lines).saturating_sub(1),
+        None => extract.iter().chain(&literal_regexes).map(Regex::max_text_len).min().unwrap_or(usize::MAX),
+    };
+
+    // Banners are shown automatically for multi-file searches unless overridden.
+    let show_filename = filename_override.unwrap_or(input_file_paths.len() > 1);
+
+    Options {
+        input_paths: input_file_paths,
+        patterns,
+        matcher,
+        output_path: output_file_path,
+        tee,
+        before_context,
+        after_context,
+        case_sensitive,
+        excludes,
+        crlf,
+        show_filename,
+        format,
+        color,
+        literal_regexes,
+        hex,
+        extract,
+        multiline,
+        window_lines,
+        regex_line_limit,
+        isolated,
+        count_by_pattern,
+        only_matching,
+        unique_count,
+        max_line_bytes,
+        max_input_bytes,
+        start_line,
+        end_line,
+        verbose,
+    }
+}
+
+/// Reads a `--pattern-file`: one literal pattern per line. Blank lines and lines starting with '#'
+/// are skipped, and a trailing '\r' is ignored; other whitespace is part of the pattern.
+fn read_pattern_file(path: &Path) -> Vec<String> {
+    let content = fs::read_to_string(path).unwrap_or_else(|e| {
+        io_error(&format!("Failed to read pattern file {:?}: {}", path, e));
     });
+    content
+        .lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Reads a `--pattern-from-file`: the whole file is one literal pattern, minus a single trailing
+/// '\n' or "\r\n", so patterns with quotes or control characters need no shell escaping.
+fn read_pattern_from_file(path: &Path) -> String {
+    let content = fs::read_to_string(path).unwrap_or_else(|e| {
+        io_error(&format!("Failed to read pattern file {:?}: {}", path, e));
+    });
+    let pattern = content.strip_suffix('\n').map_or(content.as_str(), |rest| rest.strip_suffix('\r').unwrap_or(rest));
+    if pattern.is_empty() {
+        fatal_error(&format!("Pattern file {:?} is empty.", path));
+    }
+    if pattern.contains('\n') {
+        warn(&format!("The pattern in {:?} spans several lines, so it can never match a single log line.", path));
+    }
+    pattern.to_string()
+}
 
-    (input_path, search_pattern, output_file_path, before_context, after_context, case_sensitive, verbose)
+/// Prints the tool name and version.
+fn print_version() {
+    println!("{} {}", TOOL_NAME, TOOL_VERSION);
 }
 
 /// Prints the help message for the tool.
@@ -138,134 +866,1085 @@ fn print_help() {
     println!(
         "Memory-Efficient Log Search
 
-Usage: memory_efficient_log_search -i <LOG_FILE> -p <PATTERN> [-o <OUTPUT_FILE>] [-b <LINES>] [-a <LINES>] [-c | --case-sensitive] [-v | --verbose] [--help]
+Usage: memory_efficient_log_search -i <LOG_FILE> (-p <PATTERN>... | --pattern-file <FILE> | --pattern-from-file <FILE> | --extract <REGEX>) [-o <OUTPUT_FILE> [--tee]] [-b <LINES>] [-a <LINES>] [-c | --case-sensitive] [-e <PATTERN>]... [--crlf keep|strip] [-H | --with-filename | --no-filename] [--format text|ndjson] [--color auto|always|never] [--line-start] [--hex] [--multiline [--window-lines <N>]] [--isolated <N>] [--count-by-pattern] [--only-matching] [--unique-count] [--max-line-bytes <N>] [--max-input-bytes <N>] [--start-line <N>] [--end-line <N>] [-v | --verbose] [--no-defaults] [--help] [--version]
 
 Arguments:
-  -i, --input <FILE>        Path to the input log file to search.
-  -p, --pattern <PATTERN>   The search pattern (string or regex).
+  -i, --input <FILE>        Path to an input log file to search. May be repeated to search several files.
+  -p, --pattern <PATTERN>   The literal text to search for. May be repeated; a line matches if it contains any.
+                            For a regex, use --extract, or -p with --multiline.
+  --pattern-file <FILE>     Read literal patterns from FILE, one per line ('#' comments and blank lines skipped).
+                            A line matches if it contains any of them (or -p). All patterns are matched in a
+                            single pass per line, so large IOC lists stay fast.
+  --pattern-from-file <FILE> Read one literal pattern from the whole of FILE, minus a single trailing newline, for
+                            patterns with quotes or control characters that are awkward to pass as -p.
   -o, --output <FILE>       (Optional) Path to save the matching lines. If not provided, output is printed to stdout.
+  --tee                     (Optional) With -o, also print the output to stdout. If one destination fails, the
+                            other still receives everything, and the tool exits non-zero.
   -b, --before-context <LINES> (Optional) Number of lines to show before a match (default: 0).
   -a, --after-context <LINES>  (Optional) Number of lines to show after a match (default: 0).
   -c, --case-sensitive      (Optional) Perform case-sensitive matching.
+  -e, --exclude <PATTERN>   (Optional) Drop matching lines that also contain PATTERN. May be repeated (OR'd).
+  --crlf <keep|strip>       (Optional) Keep or strip the trailing '\\r' of CRLF lines in output (default: strip).
+                            Matching always ignores it.
+  -H, --with-filename       (Optional) Always print a '==> FILE <==' banner before each file's results.
+  --no-filename             (Optional) Never print file banners. By default banners appear only for multiple files.
+  --format <text|ndjson>    (Optional) 'text' (default) prints lines. 'ndjson' prints one JSON object per output
+                            line with file, line, text and byte_offset fields; context lines add \"context\":true.
+  --color <WHEN>            (Optional) Highlight matches in text output: 'never' (default), 'always', or 'auto' for
+                            a terminal without -o or NO_COLOR. Regex matches (--extract, --multiline) color each
+                            capture group differently. Lines found with --hex are not highlighted.
+  --line-start, --anchored  (Optional) Match patterns only at the start of a line, e.g. '-p ERROR' for lines
+                            beginning with the log level. Honors -c, -e and context options. Not with --hex or
+                            --multiline (use '^' in the regex instead).
+  --hex                     (Optional) Treat -p as hex bytes (e.g. 'deadbeef', '0xDE AD BE EF'). Lines match if they
+                            contain the bytes as hex text (always case-insensitive) or as raw bytes.
+  --extract <REGEX>         (Optional) Print only capture group 1 of each line matching REGEX (e.g. '(\\d+\\.\\d+\\.\\d+\\.\\d+)').
+                            Lines where the group does not participate are skipped. -p, if given, pre-filters lines.
+                            A regex searches at most 512M / (its compiled size) bytes of a line; see the README.
+  --multiline               (Optional) Treat -p as a regex matched across line boundaries: each line is joined with
+                            the lines after it (up to --window-lines) and the lines spanned by a match starting on
+                            it are printed as one region ('\\n' or '\\s' match a line break; '.' matches any
+                            character, including line breaks). Regexes that nest unbounded repetitions, such as
+                            '(a+)*', are rejected. With --format ndjson, each region is one object with an end_line field.
+  --window-lines <N>        (Optional) With --multiline, the most lines a match may span, from 2 to {} (default: {}).
+  --isolated <N>            (Optional) Print a matching line only if no other line within N lines before or after
+                            it matches, to find one-off events. Only one pending match is held while the next N
+                            lines are read.
+  --count-by-pattern        (Optional) Instead of lines, print one 'PATTERN: COUNT' line per pattern, in the order
+                            given, with the number of lines it matched across all inputs. A line matching several
+                            patterns counts for each. With --format ndjson, prints {{\"pattern\":...,\"count\":N}} objects.
+  --only-matching           (Optional) Print only the parts of matching lines that match a pattern, one per output
+                            line, as 'grep -o' does. Overlapping matches are resolved left to right, longest first.
+  --unique-count            (Optional) With --only-matching or --extract, instead of each value print one
+                            'COUNT VALUE' line per distinct value, most frequent first, like 'sort | uniq -c | sort -rn'.
+                            With --format ndjson, prints {{\"value\":...,\"count\":N}} objects. Memory grows with the
+                            number and length of distinct values.
+  --max-line-bytes <N>      (Optional) Buffer at most N bytes of any one line; the rest of a longer line is skipped
+                            with a warning. Accepts K, M and G suffixes (e.g. 64K). Default: unlimited.
+  --max-input-bytes <N>     (Optional) Fail with an error once more than N bytes have been read across all inputs.
+                            Accepts K, M and G suffixes (e.g. 2G). Default: unlimited.
+  --start-line <N>          (Optional) Search each file from line N on. Earlier lines are skipped without being
+                            searched or buffered; line numbers and byte offsets still count from the file start.
+  --end-line <N>            (Optional) Search each file up to and including line N, then stop reading it.
+                            Context lines, --multiline windows and --isolated distances stay within the range.
   -v, --verbose             (Optional) Enable verbose output.
-  --help                    Display this help message."
+  --no-defaults             Ignore the defaults file described below.
+  --help                    Display this help message.
+  --version                 Display the tool version.
+
+Defaults file: default options may be set in $XDG_CONFIG_HOME/memory_efficient_log_search.conf
+(or ~/.config/memory_efficient_log_search.conf), one 'option = value' per line (e.g. 'verbose = true').
+Options given on the command line override them, and a missing file is ignored.
+
+Exit status: 0 on success (with or without matches), 1 on invalid arguments or other errors
+(e.g. --max-input-bytes exceeded), 4 if an input cannot be read or the output cannot be written.",
+        MAX_WINDOW_LINES, DEFAULT_WINDOW_LINES
     );
 }
 
+/// Splits a trailing carriage return off a line read from a CRLF file.
+/// Returns the line without it and whether one was present.
+fn split_crlf(line: &str) -> (&str, bool) {
+    match line.strip_suffix('\r') {
+        Some(stripped) => (stripped, true),
+        None => (line, false),
+    }
+}
+
+/// A streaming line reader that reuses a single `String` buffer across lines,
+/// avoiding the per-line allocation of `BufRead::lines`.
+/// Unlike `lines`, it only strips the '\n', leaving any CRLF '\r' for the caller to handle.
+/// With `max_line_bytes`, at most that many bytes of a line are buffered and the rest of the
+/// line is skipped, so input without newlines cannot grow the buffer without bound.
+struct LineReader<R: BufRead> {
+    reader: R,
+    buffer: String,
+    line_number: usize,
+    offset: u64, // Byte offset of the next line
+    max_line_bytes: Option<usize>,
+    end_line: Option<usize>, // Number of the last line to return
+}
+
+/// A line returned by `LineReader`, with its 1-based number and the byte offsets at which it
+/// starts and at which the next line starts. `truncated` is set when the line was cut at
+/// `max_line_bytes`.
+struct Line<'a> {
+    number: usize,
+    offset: u64,
+    end: u64,
+    text: &'a str,
+    truncated: bool,
+}
+
+impl<R: BufRead> LineReader<R> {
+    fn new(reader: R, max_line_bytes: Option<usize>, end_line: Option<usize>) -> Self {
+        LineReader { reader, buffer: String::new(), line_number: 0, offset: 0, max_line_bytes, end_line }
+    }
+
+    /// Consumes lines without buffering or decoding them, so that the next line returned is line
+    /// `number`. Stops early at the end of input.
+    fn skip_to(&mut self, number: usize) {
+        let mut discard = Vec::new();
+        while self.line_number + 1 < number {
+            discard.clear();
+            let skipped = read_line_capped(&mut self.reader, &mut discard, 0).unwrap_or_else(|e| {
+                io_error(&format!("Failed to read line from file: {}", e));
+            });
+            if skipped == 0 {
+                return; // End of file
+            }
+            self.offset += skipped as u64;
+            self.line_number += 1;
+        }
+    }
+
+    /// Returns the next line without its trailing '\n', or None at end of input or after
+    /// `end_line`. A last line without a '\n' is returned the same way; its `end` is then the
+    /// end of the input, so numbers and offsets match those of the same file with a final newline.
+    fn next_line(&mut self) -> Option<Line<'_>> {
+        if self.end_line.is_some_and(|end| self.line_number >= end) {
+            return None; // Past --end-line; the rest of the input is never read
+        }
+        // Read raw bytes into the reused buffer's allocation, then validate them as UTF-8.
+        let mut bytes = std::mem::take(&mut self.buffer).into_bytes();
+        bytes.clear();
+        let read = match self.max_line_bytes {
+            None => self.reader.read_until(b'\n', &mut bytes),
+            Some(limit) => read_line_capped(&mut self.reader, &mut bytes, limit),
+        };
+        let bytes_read = read.unwrap_or_else(|e| {
+            io_error(&format!("Failed to read line from file: {}", e));
+        });
+        if bytes_read == 0 {
+            return None; // End of file
+        }
+        let truncated = bytes.len() < bytes_read;
+        self.buffer = match String::from_utf8(bytes) {
+            Ok(line) => line,
+            // A cut can split a multi-byte character; drop the incomplete tail.
+            Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+                let valid = e.utf8_error().valid_up_to();
+                let mut bytes = e.into_bytes();
+                bytes.truncate(valid);
+                String::from_utf8(bytes).unwrap_or_default()
+            }
+            Err(_) => io_error("Failed to read line from file: stream did not contain valid UTF-8"),
+        };
+        let line_offset = self.offset;
+        self.offset += bytes_read as u64;
+        self.line_number += 1;
+        Some(Line {
+            number: self.line_number,
+            offset: line_offset,
+            end: self.offset,
+            text: self.buffer.strip_suffix('\n').unwrap_or(&self.buffer),
+            truncated,
+        })
+    }
+}
+
+/// Like `read_until(b'\n', ...)`, but appends at most `limit` bytes of the line to `bytes` and
+/// consumes the rest of the line without buffering it. The '\n' does not count towards the limit:
+/// it is appended if the whole line fit, so `bytes` is shorter than the returned total number of
+/// bytes consumed exactly when the line was cut.
+fn read_line_capped<R: BufRead>(reader: &mut R, bytes: &mut Vec<u8>, limit: usize) -> io::Result<usize> {
+    let mut consumed = 0;
+    let mut cut = false;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            return Ok(consumed);
+        }
+        let (chunk, done) = match available.iter().position(|&b| b == b'\n') {
+            Some(end) => (&available[..=end], true),
+            None => (available, false),
+        };
+        let content = if done { &chunk[..chunk.len() - 1] } else { chunk };
+        let room = limit.saturating_sub(bytes.len());
+        cut |= content.len() > room;
+        bytes.extend_from_slice(&content[..content.len().min(room)]);
+        if done && !cut {
+            bytes.push(b'\n');
+        }
+        let length = chunk.len();
+        reader.consume(length);
+        consumed += length;
+        if done {
+            return Ok(consumed);
+        }
+    }
+}
+
+/// Formats a string as a JSON string literal, escaping as it writes so NDJSON output needs no
+/// intermediate copy of each line.
+struct JsonString<'a>(&'a str);
+
+impl fmt::Display for JsonString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("\"")?;
+        let mut plain_start = 0;
+        for (i, c) in self.0.char_indices() {
+            let escaped = match c {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                c if (c as u32) < 0x20 => "",
+                _ => continue,
+            };
+            // Runs of characters that need no escaping are written in one piece.
+            f.write_str(&self.0[plain_start..i])?;
+            if escaped.is_empty() {
+                write!(f, "\\u{:04x}", c as u32)?;
+            } else {
+                f.write_str(escaped)?;
+            }
+            plain_start = i + c.len_utf8();
+        }
+        f.write_str(&self.0[plain_start..])?;
+        f.write_str("\"")
+    }
+}
+
+/// One line of search output: a match (or extracted capture) or a context line. A `--multiline`
+/// region is one record spanning `line_number` to `end_line`.
+struct Record<'a> {
+    line_number: usize,
+    end_line: Option<usize>,
+    byte_offset: u64,
+    text: &'a str,
+    context: bool,
+}
+
+/// Writes one output record in the selected format. Text output is preceded by the pending file
+/// banner if this is the file's first output; NDJSON carries the file name in every object instead.
+fn emit(writer: &mut dyn Write, options: &Options, file: &str, banner: &mut Option<String>, record: Record) {
+    if options.format == OutputFormat::Ndjson {
+        let context = if record.context { ",\"context\":true" } else { "" };
+        let end_line = record.end_line.map(|line| format!(",\"end_line\":{}", line)).unwrap_or_default();
+        writeln!(
+            writer,
+            "{{\"file\":{},\"line\":{}{},\"text\":{},\"byte_offset\":{}{}}}",
+            JsonString(file),
+            record.line_number,
+            end_line,
+            JsonString(record.text),
+            record.byte_offset,
+            context
+        )
+        .unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to output: {}", e));
+        });
+        return;
+    }
+    write_line(writer, record.text, banner);
+}
+
+/// Writes one output line, preceded by the pending file banner if this is the file's first output.
+fn write_line(writer: &mut dyn Write, line: &str, banner: &mut Option<String>) {
+    if let Some(name) = banner.take() {
+        writeln!(writer, "==> {} <==", name).unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to output: {}", e));
+        });
+    }
+    writeln!(writer, "{}", line).unwrap_or_else(|e| {
+        io_error(&format!("Failed to write to output: {}", e));
+    });
+}
+
+/// With `--color`, returns `line` with every occurrence of the search patterns highlighted.
+/// Returns `None` when there is nothing to highlight.
+fn highlight_matches(options: &Options, line: &str) -> Option<String> {
+    if !options.color || options.literal_regexes.is_empty() {
+        return None;
+    }
+    let searchable = regex_searchable(line, options.regex_line_limit);
+    let spans: Vec<Span> = options.literal_regexes.iter().flat_map(|regex| match_spans(regex, searchable)).collect();
+    Some(paint(line, &spans))
+}
+
+/// Returns the byte ranges printed by `--only-matching`: the occurrences of the search patterns in
+/// `line`, left to right and without overlaps. Of overlapping occurrences, the one that starts
+/// first wins, and of those the longest, as with `grep -o`. Empty occurrences are skipped.
+fn literal_matches(options: &Options, line: &str) -> Vec<(usize, usize)> {
+    let mut matches: Vec<(usize, usize)> = options
+        .literal_regexes
+        .iter()
+        .flat_map(|regex| match_spans(regex, regex_searchable(line, options.regex_line_limit)))
+        .filter(|span| span.group == 0 && span.end > span.start)
+        .map(|span| (span.start, span.end))
+        .collect();
+    matches.sort_unstable_by_key(|&(start, end)| (start, Reverse(end)));
+    let mut next = 0;
+    matches.retain(|&(start, end)| {
+        let keep = start >= next;
+        if keep {
+            next = end;
+        }
+        keep
+    });
+    matches
+}
+
+/// Returns the part of `text` that the regexes in use search: all of it, or the whole characters
+/// that fit in `limit` bytes (see `Options::regex_line_limit`).
+fn regex_searchable(text: &str, limit: usize) -> &str {
+    let mut end = limit.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Adds one occurrence of `value` to the `--unique-count` tally, copying it only the first time.
+fn tally(counts: &mut HashMap<String, usize>, value: &str) {
+    match counts.get_mut(value) {
+        Some(count) => *count += 1,
+        None => {
+            counts.insert(value.to_string(), 1);
+        }
+    }
+}
+
+/// Writes the `--unique-count` report: one line per distinct value, most frequent first and
+/// ties in byte order, formatted like `uniq -c` (or as NDJSON objects).
+fn write_unique_counts(writer: &mut dyn Write, options: &Options, counts: &HashMap<String, usize>) {
+    let mut sorted: Vec<(&String, &usize)> = counts.iter().collect();
+    sorted.sort_unstable_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    for (value, count) in sorted {
+        let line = match options.format {
+            OutputFormat::Text => format!("{:>7} {}", count, value),
+            OutputFormat::Ndjson => format!("{{\"value\":{},\"count\":{}}}", JsonString(value), count),
+        };
+        writeln!(writer, "{}", line).unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to output: {}", e));
+        });
+    }
+}
+
+/// Writes the `--count-by-pattern` report: one line (or NDJSON object) per pattern, in the order given.
+fn write_pattern_counts(writer: &mut dyn Write, options: &Options, counts: &[usize]) {
+    for (pattern, count) in options.patterns.iter().zip(counts) {
+        let line = match options.format {
+            OutputFormat::Text => format!("{}: {}", pattern, count),
+            OutputFormat::Ndjson => format!("{{\"pattern\":{},\"count\":{}}}", JsonString(pattern), count),
+        };
+        writeln!(writer, "{}", line).unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to output: {}", e));
+        });
+    }
+}
+
+/// A line held in the `--multiline` window: its number and byte offset, its text as output (per
+/// --crlf), and the length of the part that is matched, which excludes any CRLF '\r' and
+/// anything past `Options::regex_line_limit`.
+struct WindowLine {
+    number: usize,
+    offset: u64,
+    text: String,
+    content_len: usize,
+}
+
+/// Matches the `--multiline` regex against the window's lines joined with '\n'. Only matches that
+/// start on the first line count, so each region is found once. If one does (and contains no
+/// exclude pattern), the lines it spans are emitted as one record and removed from the window;
+/// otherwise just the first line is removed. Returns true if a region was emitted.
+fn scan_window(
+    window: &mut VecDeque<WindowLine>,
+    visited: &mut Visited,
+    options: &Options,
+    excludes: &[String],
+    writer: &mut dyn Write,
+    file: &str,
+    banner: &mut Option<String>,
+) -> bool {
+    let Some(regex) = &options.multiline else {
+        return false;
+    };
+    let mut joined = String::new();
+    // Offset in `joined` of the end of each line, where its '\n' separator starts.
+    let mut line_ends = Vec::with_capacity(window.len());
+    for (index, line) in window.iter().enumerate() {
+        if index > 0 {
+            joined.push('\n');
+        }
+        joined.push_str(&line.text[..line.content_len]);
+        line_ends.push(joined.len());
+    }
+    let Some(groups) = regex.spans(&joined, 0, line_ends[0], visited) else {
+        window.pop_front();
+        return false;
+    };
+    let Some((start, end)) = groups[0] else {
+        window.pop_front();
+        return false;
+    };
+    // The region runs to the line holding the match's last character (a '\n' ends its line).
+    let last_char = if end > start { end - 1 } else { start };
+    let last = line_ends.iter().position(|&line_end| last_char <= line_end).unwrap_or(window.len() - 1);
+    let region = &joined[..line_ends[last]];
+    let lowered;
+    let region = if options.case_sensitive {
+        region
+    } else {
+        lowered = region.to_lowercase();
+        &lowered
+    };
+    if excludes.iter().any(|e| region.contains(e.as_str())) {
+        window.pop_front();
+        return false;
+    }
+
+    let lines: Vec<WindowLine> = window.drain(..=last).collect();
+    let mut text = lines.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join("\n");
+    if options.color {
+        // Offsets in `joined` leave out any '\r' kept by --crlf keep, so shift each span past
+        // the '\r's of the lines before it. A span that runs past the region is cut off.
+        let mut shifts = Vec::with_capacity(lines.len());
+        let mut shift = 0;
+        for line in &lines {
+            shifts.push(shift);
+            shift += line.text.len() - line.content_len;
+        }
+        let to_text = |offset: usize| {
+            let line = line_ends.iter().position(|&line_end| offset <= line_end).unwrap_or(last);
+            offset + shifts[line.min(last)]
+        };
+        let spans: Vec<Span> = group_spans(&groups)
+            .map(|span| Span { start: to_text(span.start), end: to_text(span.end), ..span })
+            .collect();
+        text = paint(&text, &spans);
+    }
+    let record = Record {
+        line_number: lines[0].number,
+        end_line: Some(lines[last].number),
+        byte_offset: lines[0].offset,
+        text: &text,
+        context: false,
+    };
+    emit(writer, options, file, banner, record);
+    true
+}
+
+/// Stops the run because reading `input_path` went past `--max-input-bytes`, with `remaining`
+/// bytes of the budget left when the file was started.
+fn input_limit_exceeded(input_path: &Path, options: &Options, remaining: u64) -> ! {
+    fatal_error(&format!(
+        "Input limit exceeded: reading {:?} goes past --max-input-bytes ({} bytes across all inputs, {} remaining).",
+        input_path,
+        options.max_input_bytes.unwrap_or_default(),
+        remaining
+    ));
+}
+
 /// Searches a log file for a pattern with memory efficiency.
+/// `input_budget` is what remains of `--max-input-bytes`, if set. Returns the number of bytes read.
 fn search_log_file(
-    input_path: &PathBuf,
-    pattern: &str,
-    mut writer: Box<dyn Write>,
-    before_context: usize,
-    after_context: usize,
-    case_sensitive: bool,
-    verbose: bool,
-) {
-    info(&format!("Searching log file: {:?}", input_path), verbose);
-    info(&format!("Pattern: {:?}", pattern), verbose);
-
+    input_path: &Path,
+    options: &Options,
+    input_budget: Option<u64>,
+    writer: &mut dyn Write,
+    pattern_counts: &mut [usize],
+    value_counts: &mut HashMap<String, usize>,
+) -> u64 {
     let file = File::open(input_path).unwrap_or_else(|e| {
-        fatal_error(&format!("Failed to open input file {:?}: {}", input_path, e));
+        io_error(&format!("Failed to open input file {:?}: {}", input_path, e));
     });
-    let reader = BufReader::new(file);
+    // Fail up front when the file's size is known; the read limit below also covers pipes and growing files.
+    // With --end-line, the part of the file that is read is not known in advance.
+    if let Some(budget) = input_budget.filter(|_| options.end_line.is_none()) {
+        if file.metadata().is_ok_and(|m| m.is_file() && m.len() > budget) {
+            input_limit_exceeded(input_path, options, budget);
+        }
+    }
+    // Reading one byte past the budget is enough to tell that it was exceeded.
+    let reader = BufReader::new(file.take(input_budget.map_or(u64::MAX, |budget| budget.saturating_add(1))));
+    search_reader(reader, input_path, options, input_budget, writer, pattern_counts, value_counts)
+}
+
+/// Searches the lines of `reader`, read from `input_path`, and writes the results.
+/// When `show_filename` is set, a `==> FILE <==` banner precedes the file's first output line.
+/// With `--count-by-pattern`, nothing is written; instead each pattern's entry in `pattern_counts`
+/// is incremented for every line it matches. Returns the number of bytes read.
+fn search_reader<R: BufRead>(
+    reader: R,
+    input_path: &Path,
+    options: &Options,
+    input_budget: Option<u64>,
+    writer: &mut dyn Write,
+    pattern_counts: &mut [usize],
+    value_counts: &mut HashMap<String, usize>,
+) -> u64 {
+    let file_name = input_path.display().to_string();
+    let mut banner = options.show_filename.then(|| file_name.clone());
+    let before_context = options.before_context;
+    let after_context = options.after_context;
+    let case_sensitive = options.case_sensitive;
+    let verbose = options.verbose;
+    let exceeded = |remaining: u64| -> ! { input_limit_exceeded(input_path, options, remaining) };
+
+    info(&format!("Searching log file: {:?}", input_path), verbose);
+    info(&format!("Patterns: {}", options.patterns.len()), verbose);
 
-    let mut before_buffer: Vec<String> = Vec::with_capacity(before_context);
+    // Before-context lines with their line number and byte offset, as a ring buffer: the oldest
+    // line is dropped in O(1), so large -b values cost no more per line than small ones.
+    let mut before_buffer: VecDeque<(usize, u64, String)> = VecDeque::with_capacity(before_context);
+    // Allocations of before-context lines already written, reused when the buffer refills.
+    let mut spare_lines: Vec<String> = Vec::with_capacity(before_context);
     let mut after_counter = 0;
     // after_buffer is not strictly needed for this implementation, as we write directly
     // after matching and managing the counter.
 
     let mut found_match_in_chunk = false; // To track if any match was found for info message
 
-    for (line_num, read_line) in reader.lines().enumerate() {
-        let line = read_line.unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to read line from file: {}", e));
-        });
+    // Exclude patterns are normalized once up front, honoring case sensitivity.
+    let excludes: Vec<String> = options
+        .excludes
+        .iter()
+        .map(|e| if case_sensitive { e.clone() } else { e.to_lowercase() })
+        .collect();
+
+    // Patterns were lowercased when the matcher was built; each line is lowercased once below,
+    // reusing `lowered_line`.
+    let mut lowered_line = String::new();
+    // With --multiline, the current line and up to `window_lines - 1` lines after it.
+    let mut window: VecDeque<WindowLine> = VecDeque::with_capacity(options.window_lines);
+    let mut found_patterns = vec![false; pattern_counts.len()];
+    // With --isolated, the line number of the last match, and the last match if no earlier match
+    // was within range, held until the following lines show whether it is isolated.
+    let mut last_match: Option<usize> = None;
+    let mut pending: Option<(usize, u64, String)> = None;
 
-        let mut line_to_match = line.clone();
-        let mut search_pattern_str = pattern.to_string(); // Renamed to avoid shadowing
+    let mut lines = LineReader::new(reader, options.max_line_bytes, options.end_line);
+    if options.start_line > 1 {
+        info(&format!("Skipping to line {}.", options.start_line), verbose);
+        lines.skip_to(options.start_line);
+        if let Some(budget) = input_budget.filter(|&budget| lines.offset > budget) {
+            exceeded(budget);
+        }
+        if lines.line_number + 1 < options.start_line {
+            warn(&format!(
+                "{:?} has only {} line(s), so nothing from --start-line {} on was searched.",
+                input_path, lines.line_number, options.start_line
+            ));
+        }
+    }
+    let mut truncated_lines = 0;
+    let mut regex_cut_lines = 0;
+    // Reused by every regex search of the file.
+    let mut visited = Visited::default();
+    while let Some(Line { number: line_number, offset: byte_offset, end, text: raw_line, truncated }) = lines.next_line() {
+        if let Some(budget) = input_budget.filter(|&budget| end > budget) {
+            exceeded(budget);
+        }
+        if truncated {
+            // Warn once per file; the total is reported when the file is done.
+            if truncated_lines == 0 {
+                warn(&format!(
+                    "Line {} of {:?} exceeds --max-line-bytes ({} bytes); only its first {} bytes are searched.",
+                    line_number,
+                    input_path,
+                    options.max_line_bytes.unwrap_or_default(),
+                    raw_line.len()
+                ));
+            }
+            truncated_lines += 1;
+        }
+        // Match against the line without its CRLF '\r'; emit it according to --crlf.
+        let (content, had_cr) = split_crlf(raw_line);
+        let line = if had_cr && options.crlf == CrlfMode::Keep { raw_line } else { content };
+        if content.len() > options.regex_line_limit {
+            // Warn once per file, like --max-line-bytes.
+            if regex_cut_lines == 0 {
+                warn(&format!(
+                    "Line {} of {:?} is too long for the regex; only its first {} bytes are searched by it.",
+                    line_number,
+                    input_path,
+                    regex_searchable(content, options.regex_line_limit).len()
+                ));
+            }
+            regex_cut_lines += 1;
+        }
 
-        if !case_sensitive {
-            line_to_match = line_to_match.to_lowercase();
-            search_pattern_str = search_pattern_str.to_lowercase();
+        if options.multiline.is_some() {
+            let content_len = regex_searchable(content, options.regex_line_limit).len();
+            window.push_back(WindowLine { number: line_number, offset: byte_offset, text: line.to_string(), content_len });
+            if window.len() == options.window_lines && scan_window(&mut window, &mut visited, options, &excludes, writer, &file_name, &mut banner) {
+                found_match_in_chunk = true;
+            }
+            continue;
         }
 
-        let is_match = line_to_match.contains(&search_pattern_str);
-        // Simplified regex behavior: `contains` is sufficient for this demo given the constraints.
-        // A true regex implementation would require a regex crate, violating standard library only.
+        // With --line-start, only the start of the line can match, so only that much is lowercased
+        // unless -e exclusions need the rest.
+        let match_len = match options.matcher.prefix_len() {
+            Some(len) if excludes.is_empty() => len.min(content.len()),
+            _ => content.len(),
+        };
+        let line_to_match: &str = if case_sensitive {
+            content
+        } else if content.as_bytes()[..match_len].is_ascii() {
+            // ASCII fast path: lowercase into a reused buffer instead of allocating. An ASCII byte
+            // always ends a character, so `match_len` is a character boundary here.
+            lowered_line.clear();
+            lowered_line.push_str(&content[..match_len]);
+            lowered_line.make_ascii_lowercase();
+            &lowered_line
+        } else {
+            lowered_line = content.to_lowercase();
+            &lowered_line
+        };
+
+        let excluded = |line_to_match: &str| excludes.iter().any(|e| line_to_match.contains(e.as_str()));
+        if options.count_by_pattern {
+            found_patterns.fill(false);
+            options.matcher.find_all(line_to_match, &mut found_patterns);
+            if found_patterns.contains(&true) && !excluded(line_to_match) {
+                found_match_in_chunk = true;
+                for (count, _) in pattern_counts.iter_mut().zip(&found_patterns).filter(|(_, &found)| found) {
+                    *count += 1;
+                }
+            }
+            continue;
+        }
+
+        // Excluded lines are treated as non-matches, so they never anchor a context window.
+        let pattern_matches = match &options.hex {
+            Some(hex) => hex.matches(content),
+            None => options.matcher.is_match(line_to_match),
+        };
+        let is_match = pattern_matches && !excluded(line_to_match);
+
+        if let Some(distance) = options.isolated {
+            if is_match {
+                // A match within range of the pending one means neither is isolated.
+                let isolated_before = last_match.is_none_or(|last| line_number - last > distance);
+                pending = isolated_before.then(|| (line_number, byte_offset, line.to_string()));
+                last_match = Some(line_number);
+            } else if let Some((number, offset, text)) = pending.take_if(|(number, ..)| line_number - *number >= distance) {
+                found_match_in_chunk = true;
+                let painted = highlight_matches(options, &text);
+                let record = Record { line_number: number, end_line: None, byte_offset: offset, text: painted.as_deref().unwrap_or(&text), context: false };
+                emit(writer, options, &file_name, &mut banner, record);
+            }
+            continue;
+        }
 
         if is_match {
+            if options.only_matching {
+                for (start, end) in literal_matches(options, content) {
+                    found_match_in_chunk = true;
+                    let matched = &content[start..end];
+                    if options.unique_count {
+                        tally(value_counts, matched);
+                        continue;
+                    }
+                    let painted = options.color.then(|| paint(matched, &[Span { start: 0, end: matched.len(), group: 0 }]));
+                    let record = Record { line_number, end_line: None, byte_offset: byte_offset + start as u64, text: painted.as_deref().unwrap_or(matched), context: false };
+                    emit(writer, options, &file_name, &mut banner, record);
+                }
+                continue;
+            }
+            if let Some(regex) = &options.extract {
+                // Extraction mode prints only the captured text; context options are rejected up front.
+                let searchable = regex_searchable(content, options.regex_line_limit);
+                let groups = regex.spans(searchable, 0, searchable.len(), &mut visited);
+                if let Some((start, end)) = groups.as_ref().and_then(|groups| groups[1]) {
+                    found_match_in_chunk = true;
+                    let captured = &content[start..end];
+                    if options.unique_count {
+                        tally(value_counts, captured);
+                        continue;
+                    }
+                    let painted;
+                    let text = match groups.filter(|_| options.color) {
+                        Some(groups) => {
+                            // Nested groups keep their own colors within the printed capture.
+                            let spans: Vec<Span> = group_spans(&groups)
+                                .filter(|span| span.start < end && span.end > start)
+                                .map(|span| Span { start: span.start.max(start) - start, end: span.end.min(end) - start, ..span })
+                                .collect();
+                            painted = paint(captured, &spans);
+                            &painted
+                        }
+                        None => captured,
+                    };
+                    let record = Record { line_number, end_line: None, byte_offset: byte_offset + start as u64, text, context: false };
+                    emit(writer, options, &file_name, &mut banner, record);
+                }
+                continue;
+            }
             found_match_in_chunk = true;
 
             // Write before context
-            for prev_line in &before_buffer {
-                writeln!(writer, "{}", prev_line).unwrap_or_else(|e| {
-                    fatal_error(&format!("Failed to write to output: {}", e));
-                });
+            for (prev_number, prev_offset, prev_line) in &before_buffer {
+                let record = Record { line_number: *prev_number, end_line: None, byte_offset: *prev_offset, text: prev_line, context: true };
+                emit(writer, options, &file_name, &mut banner, record);
             }
-            before_buffer.clear(); // Clear buffer after writing
+            spare_lines.extend(before_buffer.drain(..).map(|(_, _, text)| text)); // Keep the allocations
 
             // Write the matched line
-            writeln!(writer, "{}", line).unwrap_or_else(|e| {
-                fatal_error(&format!("Failed to write to output: {}", e));
-            });
+            let painted = highlight_matches(options, line);
+            let record = Record { line_number, end_line: None, byte_offset, text: painted.as_deref().unwrap_or(line), context: false };
+            emit(writer, options, &file_name, &mut banner, record);
             after_counter = after_context; // Start after context counter
             // No after_buffer to clear here.
         } else if after_counter > 0 {
             // If we are currently writing after-context lines
-            writeln!(writer, "{}", line).unwrap_or_else(|e| {
-                fatal_error(&format!("Failed to write to output: {}", e));
-            });
+            let record = Record { line_number, end_line: None, byte_offset, text: line, context: true };
+            emit(writer, options, &file_name, &mut banner, record);
             after_counter -= 1;
         } else {
             // No match and no after-context pending, manage before-context buffer
             if before_context > 0 {
-                if before_buffer.len() == before_context {
-                    before_buffer.remove(0); // Remove oldest line
-                }
-                before_buffer.push(line.clone());
+                // Reuse the oldest line's allocation once the buffer is full.
+                let mut text = if before_buffer.len() == before_context {
+                    before_buffer.pop_front().map(|(_, _, text)| text).unwrap_or_default()
+                } else {
+                    spare_lines.pop().unwrap_or_default()
+                };
+                text.clear();
+                text.push_str(line);
+                before_buffer.push_back((line_number, byte_offset, text)); // Only context lines are copied
             }
         }
     }
 
+    // A pending --isolated match has no match after it before the end of the file.
+    if let Some((number, offset, text)) = pending {
+        found_match_in_chunk = true;
+        let painted = highlight_matches(options, &text);
+        let record = Record { line_number: number, end_line: None, byte_offset: offset, text: painted.as_deref().unwrap_or(&text), context: false };
+        emit(writer, options, &file_name, &mut banner, record);
+    }
+
+    // Lines near the end of the file start windows shorter than --window-lines.
+    while !window.is_empty() {
+        if scan_window(&mut window, &mut visited, options, &excludes, writer, &file_name, &mut banner) {
+            found_match_in_chunk = true;
+        }
+    }
+
+    if truncated_lines > 1 {
+        warn(&format!("{} line(s) of {:?} were truncated by --max-line-bytes.", truncated_lines, input_path));
+    }
+    if regex_cut_lines > 1 {
+        warn(&format!("{} line(s) of {:?} were too long for the regex and only partly searched by it.", regex_cut_lines, input_path));
+    }
     if found_match_in_chunk {
         info("Search complete. Matches found.", verbose);
     } else {
         info("Search complete. No matches found.", verbose);
     }
+    lines.offset
 }
 
 /// The main entry point for the application.
 /// Parses arguments, searches the log file for patterns, and outputs the results.
 fn main() {
-    let (input_path, pattern, output_path, before_context, after_context, case_sensitive, verbose) = parse_args();
+    let options = parse_args();
+    let verbose = options.verbose;
 
-    info(&format!("Input file: {:?}", input_path), verbose);
-    info(&format!("Search pattern: {:?}", pattern), verbose);
-    info(&format!("Before context: {}", before_context), verbose);
-    info(&format!("After context: {}", after_context), verbose);
-    info(&format!("Case sensitive: {}", case_sensitive), verbose);
+    info(&format!("Input files: {:?}", options.input_paths), verbose);
+    info(&format!("Search patterns: {}", options.patterns.len()), verbose);
+    info(&format!("Before context: {}", options.before_context), verbose);
+    info(&format!("After context: {}", options.after_context), verbose);
+    info(&format!("Case sensitive: {}", options.case_sensitive), verbose);
+    info(&format!("Exclude patterns: {:?}", options.excludes), verbose);
+    info(&format!("CRLF handling: {:?}", options.crlf), verbose);
 
-    let mut writer: Box<dyn Write> = if let Some(path) = output_path {
+    let mut writer: Box<dyn Write> = if let Some(path) = &options.output_path {
         info(&format!("Output file: {:?}", path), verbose);
-        Box::new(File::create(&path).unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to create output file {:?}: {}", path, e));
-        }))
+        if options.tee {
+            info("Also writing output to stdout (--tee).", verbose);
+            Box::new(TeeWriter { file: Some(create_output(path)), stdout: Some(io::stdout()) })
+        } else {
+            Box::new(create_output(path))
+        }
     } else {
         info("Outputting to stdout.", verbose);
         Box::new(io::stdout())
     };
 
-    search_log_file(
-        &input_path,
-        &pattern,
-        writer,
-        before_context,
-        after_context,
-        case_sensitive,
-        verbose,
-    );
+    let mut total_read: u64 = 0;
+    let mut pattern_counts = vec![0; options.patterns.len()];
+    let mut value_counts = HashMap::new();
+    for input_path in &options.input_paths {
+        let budget = options.max_input_bytes.map(|limit| limit - total_read);
+        total_read += search_log_file(input_path, &options, budget, writer.as_mut(), &mut pattern_counts, &mut value_counts);
+    }
+    if options.count_by_pattern {
+        write_pattern_counts(writer.as_mut(), &options, &pattern_counts);
+    }
+    if options.unique_count {
+        info(&format!("{} distinct value(s) found.", value_counts.len()), verbose);
+        write_unique_counts(writer.as_mut(), &options, &value_counts);
+    }
 
+    finish_output(writer, options.output_path.as_deref());
     info("Log search complete.", verbose);
     process::exit(0);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::io::Cursor;
+
+    thread_local! {
+        /// Allocations made so far by the current thread. Tests run on several threads, so
+        /// each test sees only its own.
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// The system allocator, counting each allocation and reallocation in `ALLOCATIONS`.
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// The lines of `eof_context.txt`.
+    const EOF_CONTEXT: &str = "INFO: service started\nINFO: health check ok\nINFO: health check ok\nALERT: disk usage at 95%\nFINAL: shutdown requested\n";
+
+    /// Searches `input` with `args` given on the command line, without a defaults file, and
+    /// returns the output lines. Only the '\n' is removed, so a kept '\r' stays visible.
+    fn search(input: &str, args: &[&str]) -> Vec<String> {
+        let args = ["memory_efficient_log_search", "-i", "test.log"].iter().chain(args);
+        let options = options_from_args(args.map(|arg| arg.to_string()).collect());
+        let mut output = Vec::new();
+        let mut pattern_counts = vec![0; options.patterns.len()];
+        search_reader(Cursor::new(input), Path::new("test.log"), &options, None, &mut output, &mut pattern_counts, &mut HashMap::new());
+        String::from_utf8(output).unwrap().split_terminator('\n').map(str::to_string).collect()
+    }
+
+    /// `count` lines reading `line N`, or `ALERT line N` for each N in `alerts`, numbered from 1.
+    fn numbered_lines(count: usize, alerts: &[usize]) -> Vec<String> {
+        (1..=count)
+            .map(|n| if alerts.contains(&n) { format!("ALERT line {}", n) } else { format!("line {}", n) })
+            .collect()
+    }
+
+    #[test]
+    fn before_context_at_start_middle_and_back_to_back() {
+        // The lines of before_context.txt.
+        let lines = numbered_lines(30, &[1, 12, 13, 30]);
+        let input = lines.join("\n") + "\n";
+        let output = search(&input, &["-p", "ALERT", "-b", "5"]);
+        let expected = [&lines[0..1], &lines[6..13], &lines[24..30]].concat();
+        assert_eq!(output.len(), 14);
+        assert_eq!(output, expected);
+        assert_eq!(search(&input, &["-p", "ALERT", "-b", "100"]), lines);
+    }
+
+    #[test]
+    fn large_before_context_keeps_exact_window() {
+        let lines = numbered_lines(5000, &[1, 2500, 2600, 2650, 5000]);
+        let input = lines.join("\n") + "\n";
+        let output = search(&input, &["-p", "ALERT", "-b", "1000"]);
+        // Line 1 has no earlier lines. Line 2500 gets lines 1500-2499, and the matches at 2600
+        // and 2650, closer together than -b, extend that run without repeating a line.
+        let expected = [&lines[0..1], &lines[1499..2650], &lines[3999..5000]].concat();
+        assert_eq!(output.len(), 1 + 1151 + 1001);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn regex_searches_only_the_start_of_a_very_long_line() {
+        let options = options_from_args(["memory_efficient_log_search", "-i", "test.log", "--extract", "(a{3000}b)"].map(String::from).to_vec());
+        let limit = options.extract.as_ref().unwrap().max_text_len();
+        assert_eq!(options.regex_line_limit, limit);
+        // A match within the limit is found, and one past it is not.
+        let near = "a".repeat(3000) + "b" + &"x".repeat(limit);
+        let far = "x".repeat(limit - 3000) + &"a".repeat(3000) + "b";
+        let output = search(&(near + "\n" + &far + "\n"), &["--extract", "(a{3000}b)", "--format", "ndjson"]);
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0], format!(r#"{{"file":"test.log","line":1,"text":"{}b","byte_offset":0}}"#, "a".repeat(3000)));
+    }
+
+    /// Allocations made by searching `benchmark_log.txt`, repeated `copies` times, with `args`.
+    /// Parsing the options and building the input are not counted, and the output is discarded.
+    fn search_allocations(copies: usize, args: &[&str]) -> usize {
+        let input = include_str!("../benchmark_log.txt").repeat(copies);
+        let args = ["memory_efficient_log_search", "-i", "bench.log"].iter().chain(args);
+        let options = options_from_args(args.map(|arg| arg.to_string()).collect());
+        let mut pattern_counts = vec![0; options.patterns.len()];
+        let mut value_counts = HashMap::new();
+        let before = ALLOCATIONS.with(Cell::get);
+        search_reader(Cursor::new(input), Path::new("bench.log"), &options, None, &mut io::sink(), &mut pattern_counts, &mut value_counts);
+        ALLOCATIONS.with(Cell::get) - before
+    }
+
+    #[test]
+    fn search_allocations_do_not_grow_with_input() {
+        for args in [&["-p", "nginx", "-c", "--format", "ndjson"][..], &["-p", "ERROR", "-c", "-b", "2"]] {
+            let small = search_allocations(10, args);
+            let large = search_allocations(10_000, args);
+            eprintln!("{:?}: {} allocations for 10 copies, {} for 10,000", args, small, large);
+            assert_eq!(small, large, "{:?}", args);
+            assert!(large < 50, "{:?}", args);
+        }
+    }
+
+    /// Searches `input` the way the tool did before lines were read into a reused buffer: each
+    /// line is copied, and lowercased for a case-insensitive search, before it is matched, and
+    /// before-context lines are dropped with `Vec::remove(0)`.
+    fn copying_search(input: &str, pattern: &str, context: (usize, usize), case_sensitive: bool, excludes: &[&str], keep_cr: bool) -> Vec<String> {
+        let (before_context, after_context) = context;
+        let excludes: Vec<String> = excludes.iter().map(|e| if case_sensitive { e.to_string() } else { e.to_lowercase() }).collect();
+        let mut reader = Cursor::new(input);
+        let mut output = Vec::new();
+        let mut before_buffer: Vec<String> = Vec::new();
+        let mut after_counter = 0;
+        let mut raw_line = String::new();
+        loop {
+            raw_line.clear();
+            if reader.read_line(&mut raw_line).unwrap() == 0 {
+                break;
+            }
+            let without_lf = raw_line.strip_suffix('\n').unwrap_or(&raw_line);
+            let (content, had_cr) = split_crlf(without_lf);
+            let line = if had_cr && keep_cr { without_lf.to_string() } else { content.to_string() };
+            let mut line_to_match = content.to_string();
+            let mut search_pattern = pattern.to_string();
+            if !case_sensitive {
+                line_to_match = line_to_match.to_lowercase();
+                search_pattern = search_pattern.to_lowercase();
+            }
+            let is_match = line_to_match.contains(&search_pattern) && !excludes.iter().any(|e| line_to_match.contains(e.as_str()));
+            if is_match {
+                output.append(&mut before_buffer);
+                output.push(line);
+                after_counter = after_context;
+            } else if after_counter > 0 {
+                output.push(line);
+                after_counter -= 1;
+            } else if before_context > 0 {
+                if before_buffer.len() == before_context {
+                    before_buffer.remove(0);
+                }
+                before_buffer.push(line);
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn benchmark_log_output_matches_copying_search() {
+        let log = include_str!("../benchmark_log.txt");
+        // Every other line ends in "\r\n", and the last line has no newline.
+        let mixed: String = log.split_inclusive('\n').enumerate().map(|(i, line)| if i % 2 == 0 { line.replace('\n', "\r\n") } else { line.to_string() }).collect();
+        let mixed = mixed.strip_suffix('\n').unwrap();
+        for input in [log, mixed] {
+            for pattern in ["nginx", "ERROR", "get", "\"", "\\", "web01", "absent"] {
+                for context in [(0, 0), (2, 0), (0, 3), (1, 1), (5, 5)] {
+                    for case_sensitive in [false, true] {
+                        for excludes in [&[][..], &["FAVICON"]] {
+                            for keep_cr in [false, true] {
+                                let (b, a) = (context.0.to_string(), context.1.to_string());
+                                let mut args = vec!["-p", pattern, "-b", &b, "-a", &a, "--crlf", if keep_cr { "keep" } else { "strip" }];
+                                if case_sensitive {
+                                    args.push("-c");
+                                }
+                                for exclude in excludes {
+                                    args.extend(["-e", exclude]);
+                                }
+                                let expected = copying_search(input, pattern, context, case_sensitive, excludes, keep_cr);
+                                assert_eq!(search(input, &args), expected, "{:?}", args);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn after_context_stops_at_end_of_input() {
+        let output = search(EOF_CONTEXT, &["-p", "ALERT", "-a", "5"]);
+        assert_eq!(output, ["ALERT: disk usage at 95%", "FINAL: shutdown requested"]);
+    }
+
+    #[test]
+    fn match_on_last_line_has_no_after_context() {
+        let output = search(EOF_CONTEXT, &["-p", "FINAL", "-a", "5", "-b", "1"]);
+        assert_eq!(output, ["ALERT: disk usage at 95%", "FINAL: shutdown requested"]);
+        let output = search(EOF_CONTEXT, &["-p", "FINAL", "-a", "5"]);
+        assert_eq!(output, ["FINAL: shutdown requested"]);
+    }
+
+    /// The lines of `crlf_log.txt`.
+    const CRLF_LOG: &str = "INFO: Service started\r\nWARN: Disk usage high\r\nERROR: Login failed for user admin\r\nINFO: Retrying connection\r\nERROR: Login failed for user root\r\n";
+
+    #[test]
+    fn crlf_strip_removes_carriage_returns() {
+        let output = search(CRLF_LOG, &["-p", "admin", "-a", "1"]);
+        assert_eq!(output, ["ERROR: Login failed for user admin", "INFO: Retrying connection"]);
+        let output = search(CRLF_LOG, &["-p", "admin", "-a", "1", "--crlf", "strip"]);
+        assert_eq!(output, ["ERROR: Login failed for user admin", "INFO: Retrying connection"]);
+    }
+
+    #[test]
+    fn crlf_keep_preserves_carriage_returns() {
+        let output = search(CRLF_LOG, &["-p", "admin", "-a", "1", "--crlf", "keep"]);
+        assert_eq!(output, ["ERROR: Login failed for user admin\r", "INFO: Retrying connection\r"]);
+    }
+
+    #[test]
+    fn end_anchored_match_ignores_carriage_return() {
+        for mode in ["strip", "keep"] {
+            let output = search(CRLF_LOG, &["--extract", r"(\w+)$", "--crlf", mode]);
+            assert_eq!(output, ["started", "high", "admin", "connection", "root"], "--crlf {}", mode);
+        }
+        let output = search(CRLF_LOG, &["--extract", r"(\w+) admin$", "--format", "ndjson"]);
+        assert_eq!(output, [r#"{"file":"test.log","line":3,"text":"user","byte_offset":70}"#]);
+    }
+
+    #[test]
+    fn missing_final_newline_changes_nothing() {
+        let unterminated = EOF_CONTEXT.strip_suffix('\n').unwrap();
+        let modes = [
+            &["-p", "ALERT", "-a", "5"][..],
+            &["-p", "FINAL", "-a", "5", "-b", "1"],
+            &["-p", "e", "-a", "2"],
+            &["--isolated", "1", "-p", "FINAL"],
+            &["--extract", r"(s\w+d)"],
+            &["--multiline", "-p", r"ALERT.*\nFINAL"],
+        ];
+        for args in modes {
+            let args = [args, &["--format", "ndjson"]].concat();
+            assert_eq!(search(EOF_CONTEXT, &args), search(unterminated, &args), "{:?}", args);
+        }
+        let output = search(unterminated, &["-p", "ALERT", "-a", "5", "--format", "ndjson"]);
+        assert_eq!(
+            output,
+            [
+                r#"{"file":"test.log","line":4,"text":"ALERT: disk usage at 95%","byte_offset":66}"#,
+                r#"{"file":"test.log","line":5,"text":"FINAL: shutdown requested","byte_offset":91,"context":true}"#,
+            ]
+        );
+    }
+}
+
@@ -0,0 +1,579 @@
+// regex.rs
+//
+// Minimal regular expression engine used by `--extract`, `--multiline` and `--color`.
+//
+// Supported syntax: literals, `.`, bracket classes (`[a-z_]`, `[^0-9]`), the shorthand classes
+// `\d \w \s` and their negations `\D \W \S`, escapes (`\.`, `\t`, ...), anchors `^` and `$`,
+// capturing groups `(...)`, non-capturing groups `(?:...)`, alternation `|`, and the quantifiers
+// `* + ? {n} {n,} {n,m}` (append `?` for the lazy form).
+//
+// Matching is a backtracking VM with a visited set over (instruction, position) pairs, so a
+// search is bounded by O(program length x line length) even for patterns like `(a*)*`. The
+// visited set holds one bit per pair and is capped at 64 MiB, so the text one pattern may search
+// is limited (see `max_text_len`); callers cut longer lines. The set is kept in a reusable
+// `Visited` buffer, and each search clears only the part it touched.
+// `--multiline` still rejects such nested unbounded repetitions (see `has_nested_unbounded_repeat`),
+// since it runs each pattern over many lines joined together rather than over one line.
+// Standard library only, in keeping with the rest of the tool.
+
+/// Upper bound on compiled program size, guarding against huge counted repetitions.
+const MAX_PROGRAM_LEN: usize = 10_000;
+
+/// Upper bound on the visited set of one search, in bits (64 MiB).
+const MAX_VISITED_BITS: usize = 64 << 23;
+
+/// A set of character ranges, optionally negated.
+#[derive(Debug, Clone)]
+struct CharClass {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl CharClass {
+    fn matches(&self, c: char, case_insensitive: bool) -> bool {
+        let in_ranges = |c: char| self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+        let found = in_ranges(c)
+            || (case_insensitive && (c.to_lowercase().any(in_ranges) || c.to_uppercase().any(in_ranges)));
+        found != self.negated
+    }
+}
+
+const DIGIT_RANGES: &[(char, char)] = &[('0', '9')];
+const WORD_RANGES: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE_RANGES: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+/// Parsed pattern syntax tree.
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Literal(char),
+    Any,
+    Class(CharClass),
+    Start,
+    End,
+    /// A group; capturing groups carry their 1-based index.
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize>, greedy: bool },
+}
+
+/// A single VM instruction.
+#[derive(Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(CharClass),
+    Start,
+    End,
+    /// Records the current position in a capture slot.
+    Save(usize),
+    /// Tries the first target, backtracking to the second.
+    Split(usize, usize),
+    Jmp(usize),
+    Match,
+}
+
+/// Recursive-descent parser over the pattern's characters.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    group_count: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.eat('|') {
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 { branches.pop().unwrap_or(Node::Empty) } else { Node::Alternate(branches) })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut items = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            items.push(self.parse_quantifier(atom)?);
+        }
+        Ok(match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap_or(Node::Empty),
+            _ => Node::Concat(items),
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        let c = self.next().ok_or("unexpected end of pattern")?;
+        match c {
+            '(' => {
+                let index = if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                    None
+                } else {
+                    self.group_count += 1;
+                    Some(self.group_count)
+                };
+                let inner = self.parse_alternation()?;
+                if !self.eat(')') {
+                    return Err("missing closing ')'".to_string());
+                }
+                Ok(Node::Group(Box::new(inner), index))
+            }
+            '[' => self.parse_class(),
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '\\' => self.parse_escape(),
+            '*' | '+' | '?' => Err(format!("nothing to repeat before '{}'", c)),
+            _ => Ok(Node::Literal(c)),
+        }
+    }
+
+    /// Parses the escape following a backslash, outside a bracket class.
+    fn parse_escape(&mut self) -> Result<Node, String> {
+        let c = self.next().ok_or("trailing backslash")?;
+        let shorthand = |ranges: &[(char, char)], negated| Node::Class(CharClass { ranges: ranges.to_vec(), negated });
+        Ok(match c {
+            'd' => shorthand(DIGIT_RANGES, false),
+            'D' => shorthand(DIGIT_RANGES, true),
+            'w' => shorthand(WORD_RANGES, false),
+            'W' => shorthand(WORD_RANGES, true),
+            's' => shorthand(SPACE_RANGES, false),
+            'S' => shorthand(SPACE_RANGES, true),
+            _ => Node::Literal(escaped_char(c)?),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.next().ok_or("missing closing ']'")?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = if c == '\\' {
+                let escaped = self.next().ok_or("trailing backslash")?;
+                let shorthand = match escaped {
+                    'd' => DIGIT_RANGES,
+                    'w' => WORD_RANGES,
+                    's' => SPACE_RANGES,
+                    _ => &[],
+                };
+                if !shorthand.is_empty() {
+                    ranges.extend_from_slice(shorthand);
+                    continue;
+                }
+                escaped_char(escaped)?
+            } else {
+                c
+            };
+            // A '-' forms a range unless it is the last character before ']'.
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&n| n != ']') {
+                self.pos += 1;
+                let hi = match self.next() {
+                    Some('\\') => escaped_char(self.next().ok_or("trailing backslash")?)?,
+                    Some(hi) => hi,
+                    None => return Err("missing closing ']'".to_string()),
+                };
+                if hi < lo {
+                    return Err(format!("invalid class range '{}-{}'", lo, hi));
+                }
+                ranges.push((lo, hi));
+            } else {
+                ranges.push((lo, lo));
+            }
+        }
+        Ok(Node::Class(CharClass { ranges, negated }))
+    }
+
+    /// Parses an optional quantifier following `atom`.
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            // parse_counted leaves the cursor on the closing '}'. If the braces are not a valid
+            // quantifier, the '{' is parsed as a literal instead.
+            Some('{') => match self.parse_counted() {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        self.pos += 1;
+        if let Some(max) = max {
+            if max < min {
+                return Err(format!("invalid repetition {{{},{}}}", min, max));
+            }
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat { node: Box::new(atom), min, max, greedy })
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}` starting at '{'. On success the cursor is left on the
+    /// closing '}'; otherwise it is not moved.
+    fn parse_counted(&mut self) -> Option<(usize, Option<usize>)> {
+        let close = self.chars[self.pos..].iter().position(|&c| c == '}')? + self.pos;
+        let body: String = self.chars[self.pos + 1..close].iter().collect();
+        let (min, max) = match body.split_once(',') {
+            None => {
+                let n = body.parse().ok()?;
+                (n, Some(n))
+            }
+            Some((lo, "")) => (lo.parse().ok()?, None),
+            Some((lo, hi)) => (lo.parse().ok()?, Some(hi.parse().ok()?)),
+        };
+        self.pos = close;
+        Some((min, max))
+    }
+}
+
+/// Resolves an escaped literal character (`\n`, `\.`, ...).
+fn escaped_char(c: char) -> Result<char, String> {
+    match c {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        _ if c.is_ascii_alphanumeric() => Err(format!("unsupported escape '\\{}'", c)),
+        _ => Ok(c),
+    }
+}
+
+/// Escapes `literal` so that it compiles to a regex matching exactly that text.
+pub fn escape(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Appends the instructions for `node` to `prog`.
+fn compile(node: &Node, prog: &mut Vec<Inst>) -> Result<(), String> {
+    if prog.len() > MAX_PROGRAM_LEN {
+        return Err("pattern is too large".to_string());
+    }
+    match node {
+        Node::Empty => {}
+        Node::Literal(c) => prog.push(Inst::Char(*c)),
+        Node::Any => prog.push(Inst::Any),
+        Node::Class(class) => prog.push(Inst::Class(class.clone())),
+        Node::Start => prog.push(Inst::Start),
+        Node::End => prog.push(Inst::End),
+        Node::Group(inner, None) => compile(inner, prog)?,
+        Node::Group(inner, Some(index)) => {
+            prog.push(Inst::Save(2 * index));
+            compile(inner, prog)?;
+            prog.push(Inst::Save(2 * index + 1));
+        }
+        Node::Concat(items) => {
+            for item in items {
+                compile(item, prog)?;
+            }
+        }
+        Node::Alternate(branches) => {
+            let mut jumps = Vec::new();
+            for (i, branch) in branches.iter().enumerate() {
+                if i + 1 < branches.len() {
+                    let split = prog.len();
+                    prog.push(Inst::Split(split + 1, 0));
+                    compile(branch, prog)?;
+                    jumps.push(prog.len());
+                    prog.push(Inst::Jmp(0));
+                    prog[split] = Inst::Split(split + 1, prog.len());
+                } else {
+                    compile(branch, prog)?;
+                }
+            }
+            let end = prog.len();
+            for jump in jumps {
+                prog[jump] = Inst::Jmp(end);
+            }
+        }
+        Node::Repeat { node, min, max, greedy } => {
+            for _ in 0..*min {
+                compile(node, prog)?;
+            }
+            let split_to = |split: usize, end: usize| {
+                if *greedy { Inst::Split(split + 1, end) } else { Inst::Split(end, split + 1) }
+            };
+            match max {
+                None => {
+                    let split = prog.len();
+                    prog.push(Inst::Split(0, 0));
+                    compile(node, prog)?;
+                    prog.push(Inst::Jmp(split));
+                    prog[split] = split_to(split, prog.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(prog.len());
+                        prog.push(Inst::Split(0, 0));
+                        compile(node, prog)?;
+                    }
+                    let end = prog.len();
+                    for split in splits {
+                        prog[split] = split_to(split, end);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if `node` contains an unbounded repetition (`*`, `+`, `{n,}`) inside another.
+fn nested_unbounded(node: &Node, inside_unbounded: bool) -> bool {
+    match node {
+        Node::Group(inner, _) => nested_unbounded(inner, inside_unbounded),
+        Node::Concat(items) | Node::Alternate(items) => items.iter().any(|item| nested_unbounded(item, inside_unbounded)),
+        Node::Repeat { node, max, .. } => {
+            (max.is_none() && inside_unbounded) || nested_unbounded(node, inside_unbounded || max.is_none())
+        }
+        _ => false,
+    }
+}
+
+/// A compiled regular expression.
+#[derive(Debug)]
+pub struct Regex {
+    prog: Vec<Inst>,
+    group_count: usize,
+    case_insensitive: bool,
+    nested_unbounded: bool,
+}
+
+/// Memory for the visited set of `Regex::spans`, reused across searches so that the many
+/// searches of one line, or of every line in a file, allocate it only once. It is all zeros
+/// between searches.
+#[derive(Debug, Default)]
+pub struct Visited {
+    bits: Vec<u64>,
+}
+
+/// Pending work on the backtracking stack.
+enum Job {
+    Explore(usize, usize),
+    RestoreSlot(usize, Option<usize>),
+}
+
+impl Regex {
+    /// Compiles `pattern`, returning a description of the problem if it is invalid.
+    pub fn new(pattern: &str, case_insensitive: bool) -> Result<Regex, String> {
+        let mut parser = Parser { chars: pattern.chars().collect(), pos: 0, group_count: 0 };
+        let node = parser.parse_alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err("unmatched ')'".to_string());
+        }
+
+        // Group 0 spans the whole match.
+        let mut prog = vec![Inst::Save(0)];
+        compile(&node, &mut prog)?;
+        prog.push(Inst::Save(1));
+        prog.push(Inst::Match);
+        if prog.len() > MAX_PROGRAM_LEN {
+            return Err("pattern is too large".to_string());
+        }
+        Ok(Regex { prog, group_count: parser.group_count, case_insensitive, nested_unbounded: nested_unbounded(&node, false) })
+    }
+
+    /// True if the pattern nests unbounded repetitions, as in `(a+)*` or `(?:x.*)+`.
+    pub fn has_nested_unbounded_repeat(&self) -> bool {
+        self.nested_unbounded
+    }
+
+    /// Length in bytes of the longest text this pattern can search, given the cap on the
+    /// visited set: one bit per instruction for each position in the text and the one after it.
+    pub fn max_text_len(&self) -> usize {
+        MAX_VISITED_BITS / self.prog.len() - 1
+    }
+
+    /// Number of capturing groups in the pattern (excluding the implicit whole-match group 0).
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    /// Finds the leftmost match in `text` that starts between bytes `min_start` and `max_start`
+    /// and returns the start and end byte offsets of each group, indexed from 0 (the whole
+    /// match). Groups that did not participate in the match are `None`. Anchors still refer to
+    /// the whole of `text`, so `^` cannot match after `min_start` 0. `min_start` must be a
+    /// character boundary, and `text` no longer than `max_text_len`.
+    pub fn spans(&self, text: &str, min_start: usize, max_start: usize, visited: &mut Visited) -> Option<Vec<Option<(usize, usize)>>> {
+        let slots = self.search(text, min_start, max_start, visited)?;
+        Some(
+            slots
+                .chunks(2)
+                .map(|pair| match (pair[0], pair[1]) {
+                    (Some(begin), Some(end)) => Some((begin, end)),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Tries each start position from `min_start` up to `max_start` in turn and returns the
+    /// capture slots of the first match.
+    fn search(&self, text: &str, min_start: usize, max_start: usize, visited: &mut Visited) -> Option<Vec<Option<usize>>> {
+        assert!(text.len() <= self.max_text_len(), "text of {} bytes is too long for the regex", text.len());
+        // States are numbered position-major from `min_start`, since no earlier position is
+        // visited, so the states a search touches are a prefix of the set.
+        let states = self.prog.len() * (text.len() + 1 - min_start.min(text.len()));
+        if visited.bits.len() < states.div_ceil(64) {
+            visited.bits.resize(states.div_ceil(64), 0);
+        }
+        let mut slots = vec![None; 2 * (self.group_count + 1)];
+        let mut touched = 0;
+
+        let rest = text.get(min_start..).unwrap_or_default();
+        let starts = rest.char_indices().map(|(i, _)| min_start + i).chain(std::iter::once(text.len()));
+        let mut found = false;
+        for start in starts.take_while(|&start| start <= max_start) {
+            if self.run(text, min_start, start, &mut visited.bits, &mut touched, &mut slots) {
+                found = true;
+                break;
+            }
+        }
+        visited.bits[..touched.div_ceil(64)].fill(0);
+        found.then_some(slots)
+    }
+
+    /// Runs the VM from `start`. A failed (instruction, position) state fails again regardless
+    /// of how it was reached, so `visited` is shared across start positions. `touched` is raised
+    /// past the highest state marked in `visited`.
+    fn run(&self, text: &str, min_start: usize, start: usize, visited: &mut [u64], touched: &mut usize, slots: &mut [Option<usize>]) -> bool {
+        let chars_equal = |a: char, b: char| a == b || (self.case_insensitive && a.to_lowercase().eq(b.to_lowercase()));
+        let mut stack = vec![Job::Explore(0, start)];
+
+        while let Some(job) = stack.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::RestoreSlot(slot, old) => {
+                    slots[slot] = old;
+                    continue;
+                }
+                Job::Explore(pc, pos) => (pc, pos),
+            };
+            loop {
+                let state = (pos - min_start) * self.prog.len() + pc;
+                if visited[state / 64] & (1 << (state % 64)) != 0 {
+                    break;
+                }
+                visited[state / 64] |= 1 << (state % 64);
+                *touched = (*touched).max(state + 1);
+
+                let next_char = text[pos..].chars().next();
+                match &self.prog[pc] {
+                    Inst::Char(expected) => match next_char {
+                        Some(c) if chars_equal(c, *expected) => {
+                            pc += 1;
+                            pos += c.len_utf8();
+                        }
+                        _ => break,
+                    },
+                    Inst::Any => match next_char {
+                        Some(c) => {
+                            pc += 1;
+                            pos += c.len_utf8();
+                        }
+                        None => break,
+                    },
+                    Inst::Class(class) => match next_char {
+                        Some(c) if class.matches(c, self.case_insensitive) => {
+                            pc += 1;
+                            pos += c.len_utf8();
+                        }
+                        _ => break,
+                    },
+                    Inst::Start if pos == 0 => pc += 1,
+                    Inst::End if pos == text.len() => pc += 1,
+                    Inst::Start | Inst::End => break,
+                    Inst::Save(slot) => {
+                        stack.push(Job::RestoreSlot(*slot, slots[*slot]));
+                        slots[*slot] = Some(pos);
+                        pc += 1;
+                    }
+                    Inst::Split(first, second) => {
+                        stack.push(Job::Explore(*second, pos));
+                        pc = *first;
+                    }
+                    Inst::Jmp(target) => pc = *target,
+                    Inst::Match => return true,
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every match of `regex` in `text` as (start, end) pairs, sharing one `Visited`.
+    fn all_matches(regex: &Regex, text: &str, visited: &mut Visited) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        let mut from = 0;
+        while let Some(groups) = regex.spans(text, from, text.len(), visited) {
+            let (start, end) = groups[0].unwrap();
+            matches.push((start, end));
+            from = end.max(start + 1);
+            if from > text.len() {
+                break;
+            }
+        }
+        matches
+    }
+
+    #[test]
+    fn reused_visited_gives_the_same_matches() {
+        let regex = Regex::new(r"(\w+)=(\d+)|x+y", false).unwrap();
+        let texts = ["a=1 bb=22 xxxy ccc=", "xxxxxxxxxxxxx", "", "k=9", "a=1 bb=22 xxxy ccc="];
+        let mut shared = Visited::default();
+        for text in texts {
+            let fresh = all_matches(&regex, text, &mut Visited::default());
+            assert_eq!(all_matches(&regex, text, &mut shared), fresh, "{:?}", text);
+            assert!(shared.bits.iter().all(|&word| word == 0));
+        }
+        assert_eq!(all_matches(&regex, texts[0], &mut shared), [(0, 3), (4, 9), (10, 14)]);
+    }
+
+    #[test]
+    fn max_text_len_caps_the_visited_set() {
+        let regex = Regex::new("(a{3000}b)", false).unwrap();
+        let limit = regex.max_text_len();
+        assert!((regex.prog.len() * (limit + 1)).div_ceil(64) * 8 <= 64 << 20);
+        // Each start before the run of a's fails at once, so the search stays quick.
+        let text = "x".repeat(limit - 3001) + &"a".repeat(3000) + "b";
+        let groups = regex.spans(&text, 0, text.len(), &mut Visited::default()).unwrap();
+        assert_eq!(groups[1], Some((limit - 3001, limit)));
+    }
+}
@@ -0,0 +1,9 @@
+// Sample Rust source for --source scanning. Lines 3-5 are indented with tabs.
+fn limits() {
+	let header_len: u8 = 250u8 + 10;
+	let flags = 300 as u8;
+		let skipped = 2 * 3 + 4; // Mixed precedence is not evaluated
+    let label = "100 + 200"; // Neither are strings
+    let total = 4_000_000_000_u32 + 1_000_000_000;
+    let fine = 1000 + 24;
+}
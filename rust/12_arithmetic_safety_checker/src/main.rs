@@ -16,20 +16,30 @@
 // - One Tool = One Problem: Dedicated to checking arithmetic safety.
 
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufReader, BufRead, Write};
 use std::path::PathBuf;
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::str::FromStr; // Required for parsing integer types from string
 
+mod range;
+mod source;
+
 // Constants for output formatting
 const INFO_PREFIX: &str = "[INFO] ";
+const WARNING_PREFIX: &str = "[WARNING] ";
 const ERROR_PREFIX: &str = "[ERROR] ";
 
+// Tool identity reported by --version
+const TOOL_NAME: &str = "Arithmetic Safety Checker";
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 // --- Shared Abstractions ---
 // Consistent CLI Argument Parsing: Uses `std::env::args` for CLI flags.
-// Standardized Error Handling & Exit Codes: Exits with 0 on success, non-zero on error.
-// Unified Logging/Output Format: Uses INFO, ERROR prefixes.
+// Standardized Error Handling & Exit Codes: Exits with 0 on success, 1 on issues or errors, and
+//   4 on I/O errors (see EXIT_IO_ERROR).
+// Unified Logging/Output Format: Uses INFO, WARNING, ERROR prefixes.
 
 /// Prints an error message to stderr and exits the program with a non-zero status code.
 fn fatal_error(message: &str) -> ! {
@@ -37,6 +47,21 @@ fn fatal_error(message: &str) -> ! {
     process::exit(1);
 }
 
+/// Exit status for I/O failures, such as an input that cannot be read or an output that cannot be
+/// written, so that scripts can tell them apart from exit status 1.
+const EXIT_IO_ERROR: i32 = 4;
+
+/// Prints an I/O error message to stderr and exits with `EXIT_IO_ERROR`.
+fn io_error(message: &str) -> ! {
+    eprintln!("{}{}", ERROR_PREFIX, message);
+    process::exit(EXIT_IO_ERROR);
+}
+
+/// Prints a warning message to stderr.
+fn warn(message: &str) {
+    eprintln!("{}{}", WARNING_PREFIX, message);
+}
+
 /// Prints an informational message to stderr if verbose mode is enabled.
 fn info(message: &str, verbose: bool) {
     if verbose {
@@ -44,8 +69,56 @@ fn info(message: &str, verbose: bool) {
     }
 }
 
+/// Set when a `--tee` destination fails. The run still finishes on the other destination,
+/// but then exits non-zero.
+static TEE_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Writer for `--tee`: copies all output to the `-o` file and to stdout. A destination that
+/// fails is dropped with a warning, so the other one still receives the complete output.
+struct TeeWriter {
+    file: Option<File>,
+    file_path: PathBuf,
+    stdout: Option<io::Stdout>,
+}
+
+impl TeeWriter {
+    /// Applies `op` to each remaining destination, dropping any that fail. Errors only once
+    /// both destinations are gone.
+    fn each(&mut self, mut op: impl FnMut(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            if let Err(e) = op(file) {
+                warn(&format!("--tee: writing to the output file failed: {}", e));
+                self.file = None;
+                let _ = fs::remove_file(&self.file_path); // Never leave a truncated report behind.
+                TEE_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        if let Some(stdout) = &mut self.stdout {
+            if let Err(e) = op(stdout) {
+                warn(&format!("--tee: writing to stdout failed: {}", e));
+                self.stdout = None;
+                TEE_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        if self.file.is_none() && self.stdout.is_none() {
+            return Err(io::Error::other("both --tee destinations failed"));
+        }
+        Ok(())
+    }
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.each(|writer| writer.write_all(buf)).map(|()| buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.each(|writer| writer.flush())
+    }
+}
+
 /// Represents the type of integer being simulated for arithmetic checks.
-#[derive(Debug)] // Add this line to derive the Debug trait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum IntegerType {
     U8,
     I8,
@@ -59,6 +132,24 @@ enum IntegerType {
     I128,
 }
 
+impl IntegerType {
+    /// The Rust type name, e.g. `u32`.
+    fn name(&self) -> &'static str {
+        match self {
+            IntegerType::U8 => "u8",
+            IntegerType::I8 => "i8",
+            IntegerType::U16 => "u16",
+            IntegerType::I16 => "i16",
+            IntegerType::U32 => "u32",
+            IntegerType::I32 => "i32",
+            IntegerType::U64 => "u64",
+            IntegerType::I64 => "i64",
+            IntegerType::U128 => "u128",
+            IntegerType::I128 => "i128",
+        }
+    }
+}
+
 impl FromStr for IntegerType {
     type Err = &'static str;
 
@@ -79,14 +170,140 @@ impl FromStr for IntegerType {
     }
 }
 
-/// Parses command-line arguments.
-/// Returns (input_file_path, output_file_path, integer_type, verbose)
-fn parse_args() -> (PathBuf, Option<PathBuf>, IntegerType, bool) {
-    let args: Vec<String> = env::args().collect();
+/// Layout of the report selected with --format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    /// `LINE: message` lines (default).
+    Text,
+    /// One JSON object per checked expression, written as soon as it is computed.
+    Jsonl,
+    /// Aligned columns, written once every line has been checked.
+    Table,
+    /// Compiler-style `FILE:LINE:COLUMN: LEVEL: message` diagnostics, one per finding.
+    Gcc,
+}
+
+/// Command-line options controlling the analysis.
+struct Options {
+    input_path: PathBuf,
+    output_path: Option<PathBuf>,
+    tee: bool,
+    integer_type: IntegerType,
+    stats: bool,
+    only_issues: bool,
+    parse_only: bool,
+    compare: Option<(IntegerType, IntegerType)>,
+    types: Vec<IntegerType>,
+    iterations: Option<u64>,
+    source: bool,
+    tab_width: usize,
+    format: OutputFormat,
+    message_width: usize,
+    verbose: bool,
+}
+
+/// Name of the per-user defaults file, read from `$XDG_CONFIG_HOME`, or from `~/.config` when
+/// that is not set.
+const DEFAULTS_FILE_NAME: &str = "arithmetic_safety_checker.conf";
+
+/// Short flags and the long options they stand for, so that a command-line flag overrides its
+/// default however it is spelled.
+const SHORT_FLAGS: &[(&str, &str)] = &[("-i", "input"), ("-o", "output"), ("-t", "type"), ("-v", "verbose")];
+
+/// Groups of long options that set the same thing or rule each other out. An option from a group
+/// given on the command line drops the defaults for every option in the group.
+const OPTION_GROUPS: &[&[&str]] = &[&["type", "types"], &["parse-only", "stats", "compare", "types"]];
+
+/// Returns the path of the defaults file, or `None` if no config directory is known.
+fn defaults_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME").filter(|home| !home.is_empty())?).join(".config"),
+    };
+    Some(dir.join(DEFAULTS_FILE_NAME))
+}
+
+/// Inserts the options of the defaults file between the program name and the command-line
+/// arguments. Each `option = value` line stands for `--option value`; `true` stands for the bare
+/// flag and `false` leaves it out. A flag given on the command line drops every default for the
+/// same option and for the rest of its `OPTION_GROUPS` entry, so the command line wins over the
+/// file and the file over the built-in defaults.
+/// A missing file is not an error, and `--no-defaults` skips the file altogether.
+fn with_defaults(mut args: Vec<String>) -> Vec<String> {
+    if args.iter().any(|arg| arg == "--no-defaults") {
+        args.retain(|arg| arg != "--no-defaults");
+        return args;
+    }
+    let Some(path) = defaults_path() else {
+        return args;
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return args,
+        Err(e) => io_error(&format!("Failed to read defaults file {:?}: {}", path, e)),
+    };
+    let given: Vec<String> = args
+        .iter()
+        .skip(1)
+        .filter_map(|arg| match arg.strip_prefix("--") {
+            Some(long) => Some(long.to_string()),
+            None => SHORT_FLAGS.iter().find(|(short, _)| short == arg).map(|(_, long)| long.to_string()),
+        })
+        .collect();
+    let overridden: Vec<&str> = OPTION_GROUPS
+        .iter()
+        .filter(|group| group.iter().any(|option| given.iter().any(|flag| flag == option)))
+        .flat_map(|group| group.iter().copied())
+        .collect();
+    let mut defaults = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            fatal_error(&format!(
+                "Malformed line {} in defaults file {:?}: expected 'option = value'.",
+                line_index + 1,
+                path
+            ));
+        };
+        let key = key.trim();
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        if given.iter().any(|flag| flag == key) || overridden.contains(&key) || value == "false" {
+            continue;
+        }
+        defaults.push(format!("--{}", key));
+        if value != "true" {
+            defaults.push(value.to_string());
+        }
+    }
+    args.splice(1..1, defaults);
+    args
+}
+
+/// Parses command-line arguments into an `Options` value.
+fn parse_args() -> Options {
+    let args = with_defaults(env::args().collect());
 
     let mut input_file_path: Option<PathBuf> = None;
     let mut output_file_path: Option<PathBuf> = None;
+    let mut tee = false;
     let mut integer_type = IntegerType::I32; // Default to i32
+    let mut stats = false;
+    let mut only_issues = false;
+    let mut parse_only = false;
+    let mut compare: Option<(IntegerType, IntegerType)> = None;
+    let mut types: Vec<IntegerType> = Vec::new();
+    let mut iterations: Option<u64> = None;
+    let mut source = false;
+    let mut tab_width: Option<usize> = None;
+    let mut format = OutputFormat::Text;
+    let mut message_width: Option<usize> = None;
     let mut verbose = false;
 
     // Skip the first argument which is the program name
@@ -119,6 +336,95 @@ fn parse_args() -> (PathBuf, Option<PathBuf>, IntegerType, bool) {
                     fatal_error("Missing value for --type");
                 }
             }
+            "--stats" => {
+                stats = true;
+            }
+            "--only-issues" => {
+                only_issues = true;
+            }
+            "--parse-only" => {
+                parse_only = true;
+            }
+            "--compare" => {
+                i += 1;
+                if i < args.len() {
+                    let (first, second) = args[i].split_once(',').unwrap_or_else(|| {
+                        fatal_error("Invalid value for --compare. Expected two types, e.g. 'u32,u64'.");
+                    });
+                    let parse_type = |s: &str| s.trim().parse::<IntegerType>().unwrap_or_else(|e| fatal_error(e));
+                    compare = Some((parse_type(first), parse_type(second)));
+                } else {
+                    fatal_error("Missing value for --compare");
+                }
+            }
+            "--types" => {
+                i += 1;
+                if i < args.len() {
+                    types.clear();
+                    for name in args[i].split(',') {
+                        let integer_type = name.trim().parse::<IntegerType>().unwrap_or_else(|e| fatal_error(e));
+                        if types.contains(&integer_type) {
+                            fatal_error(&format!("Invalid value for --types: '{}' is listed twice.", integer_type.name()));
+                        }
+                        types.push(integer_type);
+                    }
+                } else {
+                    fatal_error("Missing value for --types");
+                }
+            }
+            "--iterations" => {
+                i += 1;
+                if i < args.len() {
+                    iterations = match args[i].parse::<u64>() {
+                        Ok(n) if n > 0 => Some(n),
+                        _ => fatal_error("Invalid value for --iterations. Must be a positive integer."),
+                    };
+                } else {
+                    fatal_error("Missing value for --iterations");
+                }
+            }
+            "--tee" => {
+                tee = true;
+            }
+            "--format" => {
+                i += 1;
+                if i < args.len() {
+                    format = match args[i].as_str() {
+                        "text" => OutputFormat::Text,
+                        "jsonl" => OutputFormat::Jsonl,
+                        "table" => OutputFormat::Table,
+                        "gcc" => OutputFormat::Gcc,
+                        other => fatal_error(&format!("Invalid value for --format: '{}'. Choose 'text', 'jsonl', 'table' or 'gcc'.", other)),
+                    };
+                } else {
+                    fatal_error("Missing value for --format");
+                }
+            }
+            "--message-width" => {
+                i += 1;
+                if i < args.len() {
+                    message_width = match args[i].parse::<usize>() {
+                        Ok(n) if n > 0 => Some(n),
+                        _ => fatal_error("Invalid value for --message-width. Must be a positive integer."),
+                    };
+                } else {
+                    fatal_error("Missing value for --message-width");
+                }
+            }
+            "--source" => {
+                source = true;
+            }
+            "--tab-width" => {
+                i += 1;
+                if i < args.len() {
+                    tab_width = match args[i].parse::<usize>() {
+                        Ok(n) if n > 0 => Some(n),
+                        _ => fatal_error("Invalid value for --tab-width. Must be a positive integer."),
+                    };
+                } else {
+                    fatal_error("Missing value for --tab-width");
+                }
+            }
             "-v" | "--verbose" => {
                 verbose = true;
             }
@@ -126,6 +432,10 @@ fn parse_args() -> (PathBuf, Option<PathBuf>, IntegerType, bool) {
                 print_help();
                 process::exit(0);
             }
+            "--version" => {
+                print_version();
+                process::exit(0);
+            }
             _ => {
                 fatal_error(&format!("Unknown argument: {}", args[i]));
             }
@@ -138,7 +448,57 @@ fn parse_args() -> (PathBuf, Option<PathBuf>, IntegerType, bool) {
         fatal_error("Input file path is required.");
     });
 
-    (input_path, output_file_path, integer_type, verbose)
+    if tee && output_file_path.is_none() {
+        fatal_error("--tee requires an output file given with -o.");
+    }
+
+    if parse_only && stats {
+        fatal_error("--stats cannot be combined with --parse-only, which performs no arithmetic checks.");
+    }
+    if compare.is_some() && (parse_only || stats) {
+        fatal_error("--compare cannot be combined with --parse-only or --stats.");
+    }
+    if !types.is_empty() && (compare.is_some() || parse_only || stats || source || format != OutputFormat::Text) {
+        fatal_error("--types cannot be combined with --compare, --parse-only, --stats, --source or --format jsonl|table|gcc.");
+    }
+    if iterations.is_some() && parse_only {
+        fatal_error("--iterations cannot be combined with --parse-only, which performs no arithmetic checks.");
+    }
+    if source && (parse_only || compare.is_some()) {
+        fatal_error("--source cannot be combined with --parse-only or --compare.");
+    }
+    if format != OutputFormat::Text && compare.is_some() {
+        fatal_error("--format jsonl|table|gcc cannot be combined with --compare.");
+    }
+    if message_width.is_some() && format != OutputFormat::Table {
+        fatal_error("--message-width only applies to --format table.");
+    }
+    if tab_width.is_some() && !source && format != OutputFormat::Gcc {
+        fatal_error("--tab-width only applies to --source and --format gcc diagnostics.");
+    }
+
+    Options {
+        input_path,
+        output_path: output_file_path,
+        tee,
+        integer_type,
+        stats,
+        only_issues,
+        parse_only,
+        compare,
+        types,
+        iterations,
+        source,
+        tab_width: tab_width.unwrap_or(4),
+        format,
+        message_width: message_width.unwrap_or(60),
+        verbose,
+    }
+}
+
+/// Prints the tool name and version.
+fn print_version() {
+    println!("{} {}", TOOL_NAME, TOOL_VERSION);
 }
 
 /// Prints the help message for the tool.
@@ -146,115 +506,731 @@ fn print_help() {
     println!(
         "Arithmetic Safety Checker
 
-Usage: arithmetic_safety_checker -i <CODE_SNIPPET_FILE> [-o <OUTPUT_FILE>] [-t <TYPE>] [-v | --verbose] [--help]
+Usage: arithmetic_safety_checker -i <CODE_SNIPPET_FILE> [-o <OUTPUT_FILE> [--tee]] [-t <TYPE>] [--stats] [--only-issues] [--parse-only] [--compare <TYPE>,<TYPE>] [--types <TYPE>,...] [--iterations <N>] [--source [--tab-width <N>]] [--format text|jsonl|table|gcc [--message-width <N>]] [-v | --verbose] [--no-defaults] [--help] [--version]
 
 Arguments:
   -i, --input <FILE>        Path to a file containing code snippets or arithmetic expressions to check.
+                            Supports binary operations (e.g. '250 + 10') and casts (e.g. '300 as u8').
+                            Prefix a line with a type (e.g. 'u8: 200 + 100') to override --type for that line.
+                            Declare a variable's range with 'NAME: LOW..HIGH' (or 'LOW..=HIGH'); expressions using it
+                            (e.g. 'len + 50', 'len as u8') are checked for their worst case over the whole range.
   -o, --output <FILE>       (Optional) Path to save the analysis report. If not provided, output is printed to stdout.
-  -t, --type <TYPE>         (Optional) Integer type to simulate (e.g., u8, i16, i32, u64). Defaults to i32.
+  --tee                     (Optional) With -o, also print the report to stdout. If one destination fails, the
+                            other still receives everything, and the tool exits non-zero.
+  -t, --type <TYPE>         (Optional) Integer type to simulate (e.g., u8, i16, i32, u64). Defaults to i32. Overridden per line by a 'TYPE:' prefix.
+  --stats                   (Optional) Print per-operator overflow/underflow/div-by-zero counts to stderr.
+  --only-issues             (Optional) Suppress 'OK:' results and report only 'WARNING:' and 'ERROR:' lines;
+                            malformed lines are reported as 'ERROR:' instead of 'WARN:'. Exit code is unaffected.
+  --parse-only              (Optional) Only validate syntax (type prefixes, operators, cast targets, integer literals)
+                            without computing results. Exits non-zero only on parse errors.
+  --compare <A>,<B>         (Optional) Check each expression as both types (e.g. 'u32,u64') and report only the lines
+                            whose outcome differs, with each type's result. Exits non-zero if any line differs.
+  --types <T>,<T>,...       (Optional) Check each expression under every listed type (e.g. 'u8,u16,u32') and report
+                            whether it is safe in ALL, SOME or NONE of them, naming the safe and failed types and
+                            each failure. Exits non-zero if any expression fails under any type.
+  --iterations <N>          (Optional) Model an accumulator: apply 'OPERATOR VALUE' to the first operand N times
+                            (e.g. '100 + 50' adds 50 to 100 N times) and report the iteration at which it first
+                            overflows. Casts are checked once. Any N up to 18446744073709551615 is checked at once.
+  --source                  (Optional) Treat the input as Rust source code. Constant integer expressions (e.g.
+                            '250u8 + 10', '300 as u8') are checked and reported as 'LINE:COLUMN: result'. A literal's
+                            type suffix selects its type. Cannot be combined with --parse-only or --compare.
+  --tab-width <N>           (Optional) With --source or --format gcc, expand tabs to N columns when computing columns
+                            (default: 4), so columns match what an editor shows.
+  --format <FORMAT>         (Optional) 'text' (default), 'jsonl', 'table' or 'gcc'. 'jsonl' streams one JSON object per
+                            checked expression with 'line', 'expression', 'outcome', 'result' and 'message' (plus
+                            'column' with --source). 'table' prints aligned LINE, EXPRESSION, STATUS, RESULT and
+                            MESSAGE columns once all lines are checked. 'gcc' prints each finding as
+                            'FILE:LINE:COLUMN: warning|error: message' for editors and CI annotations, and omits
+                            'OK:' results. Cannot be combined with --compare.
+  --message-width <N>       (Optional) With --format table, wrap the MESSAGE column at N characters (default: 60).
   -v, --verbose             (Optional) Enable verbose output.
-  --help                    Display this help message."
+  --no-defaults             Ignore the defaults file described below.
+  --help                    Display this help message.
+  --version                 Display the tool version.
+
+Defaults file: default options may be set in $XDG_CONFIG_HOME/arithmetic_safety_checker.conf
+(or ~/.config/arithmetic_safety_checker.conf), one 'option = value' per line (e.g. 'verbose = true').
+Options given on the command line override them, and a missing file is ignored.
+
+Exit status: 0 if no issues are found, 1 if any are (see each mode) or on invalid arguments,
+4 if the input cannot be read or the report cannot be written."
     );
 }
 
+/// Classifies the outcome of a single expression check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FindingKind {
+    Ok,
+    Overflow,
+    Underflow,
+    DivisionByZero,
+    DivisionOverflow,
+    Malformed,
+    InvalidOperand,
+    UnsupportedOperator,
+    LossyCast,
+    InvalidCastTarget,
+    InvalidTypePrefix,
+}
+
+impl FindingKind {
+    /// Returns true if this finding should be treated as an issue (non-zero exit).
+    fn is_issue(&self) -> bool {
+        !matches!(self, FindingKind::Ok | FindingKind::Malformed)
+    }
+
+    /// Snake-case name used as the `outcome` in `--format jsonl`.
+    fn name(&self) -> &'static str {
+        match self {
+            FindingKind::Ok => "ok",
+            FindingKind::Overflow => "overflow",
+            FindingKind::Underflow => "underflow",
+            FindingKind::DivisionByZero => "division_by_zero",
+            FindingKind::DivisionOverflow => "division_overflow",
+            FindingKind::Malformed => "malformed",
+            FindingKind::InvalidOperand => "invalid_operand",
+            FindingKind::UnsupportedOperator => "unsupported_operator",
+            FindingKind::LossyCast => "lossy_cast",
+            FindingKind::InvalidCastTarget => "invalid_cast_target",
+            FindingKind::InvalidTypePrefix => "invalid_type_prefix",
+        }
+    }
+}
+
+/// The structured result of checking one expression.
+/// Carries the operator so that findings can be aggregated per operation type, and the
+/// computed value when there is one (including the truncated value of a lossy cast).
+#[derive(Debug)]
+struct CheckResult {
+    kind: FindingKind,
+    operator: Option<String>,
+    message: String,
+    value: Option<String>,
+}
+
+impl CheckResult {
+    fn new(kind: FindingKind, operator: Option<&str>, message: String) -> Self {
+        CheckResult {
+            kind,
+            operator: operator.map(|op| op.to_string()),
+            message,
+            value: None,
+        }
+    }
+
+    /// Attaches the computed value.
+    fn with_value(mut self, value: impl ToString) -> Self {
+        self.value = Some(value.to_string());
+        self
+    }
+}
+
+/// Checks a cast expression of the form "VALUE as TYPE".
+/// The value is parsed as the simulated source type and then cast to the target type,
+/// reporting the truncated result if the value does not fit.
+fn check_cast(
+    value_str: &str,
+    target_str: &str,
+    int_type: &IntegerType,
+    expression: &str,
+    verbose: bool,
+) -> CheckResult {
+    let target_type = match target_str.parse::<IntegerType>() {
+        Ok(t) => t,
+        Err(_) => {
+            return CheckResult::new(
+                FindingKind::InvalidCastTarget,
+                Some("as"),
+                format!("ERROR: Invalid cast target '{}' in expression: {}", target_str, expression),
+            )
+        }
+    };
+
+    // Casts `$value` to `$target`, returning the resulting value and whether it was lossy.
+    macro_rules! cast_to {
+        ($value:expr, $target:ty) => {{
+            let truncated = $value as $target;
+            (truncated.to_string(), <$target>::try_from($value).is_err())
+        }};
+    }
+
+    macro_rules! cast_from {
+        ($type:ty) => {{
+            let value = match value_str.parse::<$type>() {
+                Ok(val) => val,
+                Err(_) => return CheckResult::new(FindingKind::InvalidOperand, Some("as"), format!("ERROR: Invalid operand '{}' for type {:?} in expression: {}", value_str, int_type, expression)),
+            };
+            info(&format!("Checking cast: {} as {} from {}", value, target_str, stringify!($type)), verbose);
+            match target_type {
+                IntegerType::U8 => cast_to!(value, u8),
+                IntegerType::I8 => cast_to!(value, i8),
+                IntegerType::U16 => cast_to!(value, u16),
+                IntegerType::I16 => cast_to!(value, i16),
+                IntegerType::U32 => cast_to!(value, u32),
+                IntegerType::I32 => cast_to!(value, i32),
+                IntegerType::U64 => cast_to!(value, u64),
+                IntegerType::I64 => cast_to!(value, i64),
+                IntegerType::U128 => cast_to!(value, u128),
+                IntegerType::I128 => cast_to!(value, i128),
+            }
+        }};
+    }
+
+    let (result, lossy) = match int_type {
+        IntegerType::U8 => cast_from!(u8),
+        IntegerType::I8 => cast_from!(i8),
+        IntegerType::U16 => cast_from!(u16),
+        IntegerType::I16 => cast_from!(i16),
+        IntegerType::U32 => cast_from!(u32),
+        IntegerType::I32 => cast_from!(i32),
+        IntegerType::U64 => cast_from!(u64),
+        IntegerType::I64 => cast_from!(i64),
+        IntegerType::U128 => cast_from!(u128),
+        IntegerType::I128 => cast_from!(i128),
+    };
+
+    if lossy {
+        CheckResult::new(
+            FindingKind::LossyCast,
+            Some("as"),
+            format!("WARNING: Lossy cast detected: {} as {} = {}, LOSSY", value_str, target_str, result),
+        )
+        .with_value(result)
+    } else {
+        CheckResult::new(
+            FindingKind::Ok,
+            Some("as"),
+            format!("OK: {} as {} = {}", value_str, target_str, result),
+        )
+        .with_value(result)
+    }
+}
+
+/// Splits an optional per-expression type prefix (e.g. "u8: 200 + 100") from the expression.
+/// Returns the prefixed type (if any) and the remaining expression, or the unparsable prefix on error.
+fn split_type_prefix(expression: &str) -> Result<(Option<IntegerType>, &str), &str> {
+    match expression.split_once(':') {
+        Some((prefix, rest)) => match prefix.trim().parse::<IntegerType>() {
+            Ok(int_type) => Ok((Some(int_type), rest.trim())),
+            Err(_) => Err(prefix.trim()),
+        },
+        None => Ok((None, expression)),
+    }
+}
+
+/// Binary operators supported by the checker, in statistics-table order.
+const SUPPORTED_OPERATORS: [&str; 4] = ["+", "-", "*", "/"];
+
+/// Validates the syntax of an expression for `--parse-only` without computing anything.
+/// Operands only need to be integer literals; whether they fit the simulated type is left to the full check.
+fn check_syntax(expression: &str, variables: &range::Variables) -> CheckResult {
+    let body = match split_type_prefix(expression) {
+        Ok((_, rest)) => rest,
+        Err(prefix) => {
+            return CheckResult::new(
+                FindingKind::InvalidTypePrefix,
+                None,
+                format!("ERROR: Invalid type prefix '{}' in expression: {}", prefix, expression),
+            )
+        }
+    };
+
+    let parts: Vec<&str> = body.split_whitespace().collect();
+    if parts.len() != 3 {
+        return CheckResult::new(
+            FindingKind::Malformed,
+            None,
+            format!("ERROR: Malformed expression (expected 'VALUE OPERATOR VALUE' or 'VALUE as TYPE'): {}", body),
+        );
+    }
+    let operator = parts[1];
+
+    let is_integer_literal = |s: &str| s.parse::<i128>().is_ok() || s.parse::<u128>().is_ok() || variables.contains_key(s);
+    let operands: &[&str] = if operator == "as" {
+        if parts[2].parse::<IntegerType>().is_err() {
+            return CheckResult::new(
+                FindingKind::InvalidCastTarget,
+                Some(operator),
+                format!("ERROR: Invalid cast target '{}' in expression: {}", parts[2], body),
+            );
+        }
+        &parts[..1]
+    } else if SUPPORTED_OPERATORS.contains(&operator) {
+        &[parts[0], parts[2]]
+    } else {
+        return CheckResult::new(
+            FindingKind::UnsupportedOperator,
+            Some(operator),
+            format!("ERROR: Unsupported operator '{}' in expression: {}", operator, body),
+        );
+    };
+
+    match operands.iter().find(|op| !is_integer_literal(op)) {
+        Some(op) => CheckResult::new(
+            FindingKind::InvalidOperand,
+            Some(operator),
+            format!("ERROR: Invalid operand '{}' in expression: {}", op, body),
+        ),
+        None => CheckResult::new(FindingKind::Ok, Some(operator), format!("OK: Parsed {}", body)),
+    }
+}
+
 /// Performs a conceptual check for arithmetic overflow/underflow.
-/// This is a simplified demonstration, assuming expressions are "VALUE OPERATOR VALUE".
+/// This is a simplified demonstration, assuming expressions are "VALUE OPERATOR VALUE"
+/// or casts of the form "VALUE as TYPE", optionally prefixed with "TYPE:".
+/// With `iterations`, the operation is applied repeatedly to an accumulator (see `--iterations`),
+/// in time independent of the iteration count.
+/// Expressions using a declared variable are checked by range analysis instead (see `range.rs`).
 fn check_arithmetic_safety(
     expression: &str,
-    int_type: &IntegerType,
+    default_type: &IntegerType,
+    variables: &range::Variables,
+    iterations: Option<u64>,
     verbose: bool,
-) -> String {
+) -> CheckResult {
+    // An inline "TYPE:" prefix overrides the CLI --type for this expression only.
+    let (int_type, expression) = match split_type_prefix(expression) {
+        Ok((Some(int_type), rest)) => (int_type, rest),
+        Ok((None, rest)) => (*default_type, rest),
+        Err(prefix) => {
+            return CheckResult::new(
+                FindingKind::InvalidTypePrefix,
+                None,
+                format!("ERROR: Invalid type prefix '{}' in expression: {}", prefix, expression),
+            )
+        }
+    };
+    let int_type = &int_type;
+
     let parts: Vec<&str> = expression.split_whitespace().collect();
     if parts.len() != 3 {
-        return format!("WARN: Skipping malformed expression: {}", expression);
+        return CheckResult::new(
+            FindingKind::Malformed,
+            None,
+            format!("WARN: Skipping malformed expression: {}", expression),
+        );
+    }
+
+    if range::uses_variables(&parts, variables) {
+        info(&format!("Checking expression by range analysis: {} as {}", expression, int_type.name()), verbose);
+        return range::check_range(&parts, *int_type, variables, expression);
     }
 
     let op1_str = parts[0];
     let operator = parts[1];
     let op2_str = parts[2];
 
+    if operator == "as" {
+        return check_cast(op1_str, op2_str, int_type, expression, verbose);
+    }
+
     macro_rules! check_op {
-        ($type:ty, $min_val:expr, $max_val:expr) => {{
+        ($type:ty) => {{
             let op1 = match op1_str.parse::<$type>() {
                 Ok(val) => val,
-                Err(_) => return format!("ERROR: Invalid operand '{}' for type {:?} in expression: {}", op1_str, int_type, expression),
+                Err(_) => return CheckResult::new(FindingKind::InvalidOperand, Some(operator), format!("ERROR: Invalid operand '{}' for type {:?} in expression: {}", op1_str, int_type, expression)),
             };
             let op2 = match op2_str.parse::<$type>() {
                 Ok(val) => val,
-                Err(_) => return format!("ERROR: Invalid operand '{}' for type {:?} in expression: {}", op2_str, int_type, expression),
+                Err(_) => return CheckResult::new(FindingKind::InvalidOperand, Some(operator), format!("ERROR: Invalid operand '{}' for type {:?} in expression: {}", op2_str, int_type, expression)),
             };
 
             info(&format!("Checking expression: {} {} {} as {}", op1, operator, op2, stringify!($type)), verbose);
 
-            match operator {
-                "+" => {
-                    if let Some(res) = op1.checked_add(op2) {
-                        format!("OK: {} {} {} = {}", op1, operator, op2, res)
-                    } else {
-                        format!("WARNING: Overflow detected for {} {} {} as {}", op1, operator, op2, stringify!($type))
-                    }
+            let ok = |res: $type| CheckResult::new(FindingKind::Ok, Some(operator), format!("OK: {} {} {} = {}", op1, operator, op2, res)).with_value(res);
+            // Reports a failed checked operation as underflow (true result below MIN)
+            // or overflow (true result above MAX).
+            let out_of_range = |below_min: bool| {
+                if below_min {
+                    CheckResult::new(FindingKind::Underflow, Some(operator), format!("WARNING: Underflow detected for {} {} {} as {}", op1, operator, op2, stringify!($type)))
+                } else {
+                    CheckResult::new(FindingKind::Overflow, Some(operator), format!("WARNING: Overflow detected for {} {} {} as {}", op1, operator, op2, stringify!($type)))
                 }
-                "-" => {
-                    if let Some(res) = op1.checked_sub(op2) {
-                        format!("OK: {} {} {} = {}", op1, operator, op2, res)
-                    } else {
-                        format!("WARNING: Underflow detected for {} {} {} as {}", op1, operator, op2, stringify!($type))
-                    }
+            };
+            let zero = <$type>::default();
+            if let Some(iterations) = iterations.filter(|_| SUPPORTED_OPERATORS.contains(&operator)) {
+                if operator == "/" && op2 == zero {
+                    return CheckResult::new(FindingKind::DivisionByZero, Some(operator), format!("ERROR: Division by zero detected in expression: {}", expression));
                 }
-                "*" => {
-                    if let Some(res) = op1.checked_mul(op2) {
-                        format!("OK: {} {} {} = {}", op1, operator, op2, res)
-                    } else {
-                        format!("WARNING: Overflow detected for {} {} {} as {}", op1, operator, op2, stringify!($type))
+                // Reports the first failing iteration and the accumulator just before it.
+                let failure = |iteration: u64, acc: $type| {
+                    let (kind, label) = match operator {
+                        "/" => (FindingKind::DivisionOverflow, "Division overflow"),
+                        "+" if op2 < zero => (FindingKind::Underflow, "Underflow"),
+                        "-" if op2 >= zero => (FindingKind::Underflow, "Underflow"),
+                        "*" if (acc < zero) != (op2 < zero) => (FindingKind::Underflow, "Underflow"),
+                        _ => (FindingKind::Overflow, "Overflow"),
+                    };
+                    CheckResult::new(kind, Some(operator), format!("WARNING: {} detected at iteration {} of {} for {} {} {} as {} (accumulator was {})", label, iteration, iterations, op1, operator, op2, stringify!($type), acc))
+                };
+                let mut acc = op1;
+                if matches!(operator, "+" | "-") {
+                    // '+ 0' and '- 0' leave the accumulator unchanged.
+                    if op2 != zero {
+                        // Each iteration moves the accumulator |op2| closer to MAX or MIN, so the number
+                        // of iterations that fit is the distance to that bound divided by |op2|. Any
+                        // result that fits is computed exactly by wrapping arithmetic.
+                        let upward = (operator == "+") == (op2 > zero);
+                        let bound = if upward { <$type>::MAX } else { <$type>::MIN };
+                        let fitting = (op1.abs_diff(bound) / op2.abs_diff(zero)) as u128;
+                        let after = |steps: u128| {
+                            let delta = op2.wrapping_mul(steps as $type);
+                            if operator == "+" { op1.wrapping_add(delta) } else { op1.wrapping_sub(delta) }
+                        };
+                        if u128::from(iterations) > fitting {
+                            return failure(fitting as u64 + 1, after(fitting));
+                        }
+                        acc = after(u128::from(iterations));
+                    }
+                } else {
+                    // Accumulator loop: `acc = acc OP op2`, stopping at the first failing iteration.
+                    // With |op2| >= 2, '*' overflows and '/' reaches 0 within about 128 iterations.
+                    // The remaining cases settle into a fixed point or alternate between two values.
+                    let mut previous = None;
+                    for iteration in 1..=iterations {
+                        let next = match operator {
+                            "*" => acc.checked_mul(op2),
+                            _ => acc.checked_div(op2),
+                        };
+                        match next {
+                            // A fixed point (e.g. '* 1' or '/ 1') can never overflow in later iterations.
+                            Some(next) if next == acc => break,
+                            // A value seen two iterations ago (e.g. from '* -1') alternates with `acc`
+                            // from now on, so the final value depends only on how many iterations remain.
+                            Some(next) if previous == Some(next) => {
+                                if (iterations - iteration) % 2 == 0 {
+                                    acc = next;
+                                }
+                                break;
+                            }
+                            Some(next) => {
+                                previous = Some(acc);
+                                acc = next;
+                            }
+                            None => return failure(iteration, acc),
+                        }
                     }
                 }
+                return CheckResult::new(FindingKind::Ok, Some(operator), format!("OK: {} {} {} repeated {} times = {}", op1, operator, op2, iterations, acc)).with_value(acc);
+            }
+            match operator {
+                // Adding a negative value can only fail by dropping below MIN.
+                "+" => match op1.checked_add(op2) {
+                    Some(res) => ok(res),
+                    None => out_of_range(op2 < zero),
+                },
+                // Subtracting a negative value can only fail by exceeding MAX.
+                "-" => match op1.checked_sub(op2) {
+                    Some(res) => ok(res),
+                    None => out_of_range(op2 >= zero),
+                },
+                // A product with operands of opposite sign can only fail below MIN.
+                "*" => match op1.checked_mul(op2) {
+                    Some(res) => ok(res),
+                    None => out_of_range((op1 < zero) != (op2 < zero)),
+                },
                 "/" => {
-                    if op2 == 0 {
-                        return format!("ERROR: Division by zero detected in expression: {}", expression);
+                    if op2 == zero {
+                        return CheckResult::new(FindingKind::DivisionByZero, Some(operator), format!("ERROR: Division by zero detected in expression: {}", expression));
                     }
-                    if let Some(res) = op1.checked_div(op2) {
-                        format!("OK: {} {} {} = {}", op1, operator, op2, res)
-                    } else {
-                        format!("WARNING: Division overflow/underflow detected for {} {} {} as {}", op1, operator, op2, stringify!($type))
+                    // With a non-zero divisor, checked_div only fails for signed MIN / -1,
+                    // whose true result (MAX + 1) is not representable.
+                    match op1.checked_div(op2) {
+                        Some(res) => ok(res),
+                        None => CheckResult::new(FindingKind::DivisionOverflow, Some(operator), format!("WARNING: Division overflow detected for {} {} {} as {} ({}::MIN / -1 exceeds {}::MAX)", op1, operator, op2, stringify!($type), stringify!($type), stringify!($type))),
                     }
                 }
-                _ => format!("ERROR: Unsupported operator '{}' in expression: {}", operator, expression),
+                _ => CheckResult::new(FindingKind::UnsupportedOperator, Some(operator), format!("ERROR: Unsupported operator '{}' in expression: {}", operator, expression)),
             }
         }};
     }
 
     match int_type {
-        IntegerType::U8 => check_op!(u8, u8::MIN, u8::MAX),
-        IntegerType::I8 => check_op!(i8, i8::MIN, i8::MAX),
-        IntegerType::U16 => check_op!(u16, u16::MIN, u16::MAX),
-        IntegerType::I16 => check_op!(i16, i16::MIN, i16::MAX),
-        IntegerType::U32 => check_op!(u32, u32::MIN, u32::MAX),
-        IntegerType::I32 => check_op!(i32, i32::MIN, i32::MAX),
-        IntegerType::U64 => check_op!(u64, u64::MIN, u64::MAX),
-        IntegerType::I64 => check_op!(i64, i64::MIN, i64::MAX),
-        IntegerType::U128 => check_op!(u128, u128::MIN, u128::MAX),
-        IntegerType::I128 => check_op!(i128, i128::MIN, i128::MAX),
+        IntegerType::U8 => check_op!(u8),
+        IntegerType::I8 => check_op!(i8),
+        IntegerType::U16 => check_op!(u16),
+        IntegerType::I16 => check_op!(i16),
+        IntegerType::U32 => check_op!(u32),
+        IntegerType::I32 => check_op!(i32),
+        IntegerType::U64 => check_op!(u64),
+        IntegerType::I64 => check_op!(i64),
+        IntegerType::U128 => check_op!(u128),
+        IntegerType::I128 => check_op!(i128),
+    }
+}
+
+/// Encodes a string as a JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// One row of `--format table` output, buffered until every line is checked so that the
+/// column widths are known.
+struct TableRow {
+    location: String,
+    expression: String,
+    status: &'static str,
+    result: String,
+    message: String,
+}
+
+/// Writes one check result in the selected format, unless `--only-issues` hides it.
+/// `column` is set for `--source` results. With `--format table`, the result is added to
+/// `table` instead and written later by `write_table`.
+fn write_result(
+    writer: &mut dyn Write,
+    options: &Options,
+    line_number: usize,
+    column: Option<usize>,
+    expression: &str,
+    result: &CheckResult,
+    table: &mut Vec<TableRow>,
+) {
+    // Compiler diagnostics report problems only.
+    if (options.only_issues || options.format == OutputFormat::Gcc) && result.kind == FindingKind::Ok {
+        return;
+    }
+    // --only-issues emits only WARNING: and ERROR: lines, so a skipped malformed line becomes an error.
+    let promoted;
+    let message = match result.message.strip_prefix("WARN: ") {
+        Some(rest) if options.only_issues => {
+            promoted = format!("ERROR: {}", rest);
+            &promoted
+        }
+        _ => &result.message,
+    };
+    let line = match (options.format, column) {
+        (OutputFormat::Table, _) => {
+            table.push(TableRow {
+                location: column.map(|c| format!("{}:{}", line_number, c)).unwrap_or_else(|| line_number.to_string()),
+                expression: expression.to_string(),
+                status: result.kind.name(),
+                result: result.value.clone().unwrap_or_else(|| "-".to_string()),
+                message: message.clone(),
+            });
+            return;
+        }
+        (OutputFormat::Gcc, _) => {
+            let (level, message) = diagnostic_level(message);
+            format!("{}:{}:{}: {}: {}", options.input_path.display(), line_number, column.unwrap_or(1), level, message)
+        }
+        (OutputFormat::Text, Some(column)) => format!("{}:{}: {}", line_number, column, message),
+        (OutputFormat::Text, None) => format!("{}: {}", line_number, message),
+        (OutputFormat::Jsonl, _) => format!(
+            "{{\"line\":{}{},\"expression\":{},\"outcome\":\"{}\",\"result\":{},\"message\":{}}}",
+            line_number,
+            column.map(|c| format!(",\"column\":{}", c)).unwrap_or_default(),
+            json_string(expression),
+            result.kind.name(),
+            result.value.as_deref().map(json_string).unwrap_or_else(|| "null".to_string()),
+            json_string(message)
+        ),
+    };
+    writeln!(writer, "{}", line).unwrap_or_else(|e| {
+        io_error(&format!("Failed to write to output: {}", e));
+    });
+}
+
+/// Splits a result message into its diagnostic level and the rest of the message: `ERROR:`
+/// becomes `error`, and `WARNING:` and `WARN:` (malformed lines) become `warning`.
+fn diagnostic_level(message: &str) -> (&'static str, &str) {
+    if let Some(rest) = message.strip_prefix("ERROR: ") {
+        ("error", rest)
+    } else if let Some(rest) = message.strip_prefix("WARNING: ").or_else(|| message.strip_prefix("WARN: ")) {
+        ("warning", rest)
+    } else {
+        ("note", message.strip_prefix("OK: ").unwrap_or(message))
+    }
+}
+
+/// Returns the column of the first non-blank character of an expression line for `--format gcc`,
+/// with tabs expanded to `--tab-width`. Other formats report no column outside `--source`.
+fn expression_column(line: &str, options: &Options) -> Option<usize> {
+    if options.format != OutputFormat::Gcc {
+        return None;
+    }
+    let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+    Some(source::display_column(line, indent, options.tab_width))
+}
+
+/// Splits `text` into lines of at most `width` characters, breaking at spaces where possible
+/// and splitting words that are longer than `width`.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let current_len = current.chars().count();
+        if current_len > 0 && current_len + 1 + word.len() <= width {
+            current.push(' ');
+            current.extend(&word);
+            continue;
+        }
+        if current_len > 0 {
+            lines.push(std::mem::take(&mut current));
+        }
+        while word.len() > width {
+            lines.push(word.drain(..width).collect());
+        }
+        current.extend(&word);
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Writes the buffered `--format table` rows as aligned columns under a header. Messages longer
+/// than `--message-width` continue on indented lines below their row.
+fn write_table(writer: &mut dyn Write, options: &Options, table: &[TableRow]) {
+    let headers = ["LINE", "EXPRESSION", "STATUS", "RESULT"];
+    let cells = |row: &TableRow| [row.location.clone(), row.expression.clone(), row.status.to_string(), row.result.clone()];
+    let mut widths = headers.map(|header| header.len());
+    for row in table {
+        for (width, cell) in widths.iter_mut().zip(cells(row)) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let indent: usize = widths.iter().map(|width| width + 2).sum();
+
+    let mut write_line = |line: String| {
+        writeln!(writer, "{}", line).unwrap_or_else(|e| {
+            io_error(&format!("Failed to write to output: {}", e));
+        });
+    };
+    let pad = |cells: &[String]| -> String {
+        cells.iter().zip(widths).map(|(cell, width)| format!("{:<width$}  ", cell, width = width)).collect()
+    };
+    write_line(format!("{}MESSAGE", pad(&headers.map(String::from))));
+    write_line(format!("{}{}", pad(&widths.map(|width| "-".repeat(width))), "-".repeat(7)));
+    for row in table {
+        let message = wrap_text(&row.message, options.message_width);
+        write_line(format!("{}{}", pad(&cells(row)), message[0]));
+        for continuation in &message[1..] {
+            write_line(format!("{:indent$}{}", "", continuation, indent = indent));
+        }
+    }
+}
+
+/// Writes the `--types` verdict for one expression: whether it is safe under all, some or none
+/// of the types, which types those are, and the message of each failure. Returns true if any
+/// type failed. A line that is malformed under every type is reported once, as usual.
+fn write_type_sweep(
+    writer: &mut dyn Write,
+    options: &Options,
+    line_number: usize,
+    expression: &str,
+    results: &[(IntegerType, CheckResult)],
+    table: &mut Vec<TableRow>,
+) -> bool {
+    if results.iter().all(|(_, result)| result.kind == FindingKind::Malformed) {
+        write_result(writer, options, line_number, None, expression, &results[0].1, table);
+        return false;
+    }
+    let names = |safe: bool| -> Vec<&str> {
+        results.iter().filter(|(_, r)| r.kind.is_issue() != safe).map(|(t, _)| t.name()).collect()
+    };
+    let (safe, failed) = (names(true), names(false));
+    let verdict = match (safe.is_empty(), failed.is_empty()) {
+        (_, true) => "ALL",
+        (true, _) => "NONE",
+        _ => "SOME",
+    };
+    if options.only_issues && failed.is_empty() {
+        return false;
+    }
+    let mut report = format!("{}: SAFE IN {}: {}", line_number, verdict, expression);
+    if !safe.is_empty() {
+        report.push_str(&format!(" | safe: {}", safe.join(", ")));
+    }
+    if !failed.is_empty() {
+        report.push_str(&format!(" | failed: {}", failed.join(", ")));
+    }
+    for (integer_type, result) in results.iter().filter(|(_, r)| r.kind.is_issue()) {
+        report.push_str(&format!("\n    {}: {}", integer_type.name(), result.message));
+    }
+    writeln!(writer, "{}", report).unwrap_or_else(|e| {
+        io_error(&format!("Failed to write to output: {}", e));
+    });
+    !failed.is_empty()
+}
+
+/// Per-operator counters of overflow, underflow and division-by-zero findings.
+#[derive(Debug, Default)]
+struct OperatorStats {
+    overflow: usize,
+    underflow: usize,
+    div_by_zero: usize,
+}
+
+/// Fixed-order statistics table, one row per supported operator.
+type StatsTable = [(&'static str, OperatorStats); 4];
+
+/// Records a finding in the per-operator statistics table.
+/// Only findings for the supported operators (+, -, *, /) are counted.
+fn record_stats(stats: &mut StatsTable, result: &CheckResult) {
+    let operator = match result.operator.as_deref() {
+        Some(op) => op,
+        None => return,
+    };
+    if let Some((_, entry)) = stats.iter_mut().find(|(op, _)| *op == operator) {
+        match result.kind {
+            FindingKind::Overflow | FindingKind::DivisionOverflow => entry.overflow += 1,
+            FindingKind::Underflow => entry.underflow += 1,
+            FindingKind::DivisionByZero => entry.div_by_zero += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Prints the per-operator statistics table to stderr so it doesn't pollute the main report.
+fn print_stats(stats: &StatsTable) {
+    eprintln!("Operator Statistics:");
+    eprintln!("{:<10}{:>10}{:>11}{:>13}", "Operator", "Overflow", "Underflow", "Div-by-zero");
+    for (op, entry) in stats {
+        eprintln!("{:<10}{:>10}{:>11}{:>13}", op, entry.overflow, entry.underflow, entry.div_by_zero);
     }
 }
 
 /// The main entry point for the application.
 /// Parses arguments, reads expressions from the input file, checks them, and writes the report.
 fn main() {
-    let (input_path, output_path, integer_type, verbose) = parse_args();
+    let options = parse_args();
+    let verbose = options.verbose;
 
-    info(&format!("Input file: {:?}", input_path), verbose);
-    info(&format!("Simulating type: {:?}", integer_type), verbose);
+    info(&format!("Input file: {:?}", options.input_path), verbose);
+    info(&format!("Simulating type: {:?}", options.integer_type), verbose);
 
-    let file = File::open(&input_path).unwrap_or_else(|e| {
-        fatal_error(&format!("Failed to open input file {:?}: {}", input_path, e));
+    let file = File::open(&options.input_path).unwrap_or_else(|e| {
+        io_error(&format!("Failed to open input file {:?}: {}", options.input_path, e));
     });
     let reader = BufReader::new(file);
 
-    let mut writer: Box<dyn Write> = if let Some(path) = output_path {
+    let mut writer: Box<dyn Write> = if let Some(path) = &options.output_path {
         info(&format!("Output file: {:?}", path), verbose);
-        Box::new(File::create(&path).unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to create output file {:?}: {}", path, e));
-        }))
+        let file = File::create(path).unwrap_or_else(|e| {
+            io_error(&format!("Failed to create output file {:?}: {}", path, e));
+        });
+        if options.tee {
+            info("Also writing the report to stdout (--tee).", verbose);
+            Box::new(TeeWriter { file: Some(file), file_path: path.clone(), stdout: Some(io::stdout()) })
+        } else {
+            Box::new(file)
+        }
     } else {
         info("Outputting to stdout.", verbose);
         Box::new(io::stdout())
@@ -262,21 +1238,112 @@ fn main() {
 
     info("Starting arithmetic safety checks...", verbose);
     let mut issues_found = false;
+    let mut stats: StatsTable = SUPPORTED_OPERATORS.map(|op| (op, OperatorStats::default()));
+    // Variables declared so far with 'NAME: LOW..HIGH' lines (never in --source mode).
+    let mut variables = range::Variables::new();
+    // Rows for --format table, written once all lines are checked.
+    let mut table: Vec<TableRow> = Vec::new();
     for (line_num, read_line) in reader.lines().enumerate() {
         let expression = read_line.unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to read line from file: {}", e));
+            io_error(&format!("Failed to read line from file: {}", e));
         });
         if expression.trim().is_empty() || expression.trim().starts_with('#') {
             continue;
         }
 
-        let result = check_arithmetic_safety(&expression, &integer_type, verbose);
-        writeln!(writer, "{}: {}", line_num + 1, result).unwrap_or_else(|e| {
-            fatal_error(&format!("Failed to write to output: {}", e));
-        });
-        if result.starts_with("WARNING:") || result.starts_with("ERROR:") {
+        if options.source {
+            // Rust source mode: check every constant expression on the line, reporting its column.
+            for found in source::find_expressions(&expression) {
+                let result = check_arithmetic_safety(&found.expression, &options.integer_type, &variables, options.iterations, verbose);
+                let column = source::display_column(&expression, found.start, options.tab_width);
+                write_result(writer.as_mut(), &options, line_num + 1, Some(column), &found.expression, &result, &mut table);
+                if result.kind.is_issue() {
+                    issues_found = true;
+                }
+                record_stats(&mut stats, &result);
+            }
+            continue;
+        }
+
+        if let Some(declaration) = range::parse_declaration(&expression) {
+            // Declarations produce no output; they apply to every later line.
+            match declaration {
+                Ok((name, interval)) => {
+                    info(&format!("Line {}: declared {} in {}..={}", line_num + 1, name, interval.low, interval.high), verbose);
+                    variables.insert(name, interval);
+                }
+                Err(message) => {
+                    let result = CheckResult::new(FindingKind::Malformed, None, message);
+                    let column = expression_column(&expression, &options);
+                    write_result(writer.as_mut(), &options, line_num + 1, column, expression.trim(), &result, &mut table);
+                    if options.parse_only {
+                        issues_found = true;
+                    }
+                }
+            }
+            continue;
+        }
+
+        if let Some((first, second)) = options.compare {
+            // Side-by-side mode: only lines whose outcome differs between the two types are reported.
+            let first_result = check_arithmetic_safety(&expression, &first, &variables, options.iterations, verbose);
+            let second_result = check_arithmetic_safety(&expression, &second, &variables, options.iterations, verbose);
+            if first_result.kind != second_result.kind {
+                writeln!(
+                    writer,
+                    "{}: DIFFERS: {}\n    {}: {}\n    {}: {}",
+                    line_num + 1,
+                    expression.trim(),
+                    first.name(),
+                    first_result.message,
+                    second.name(),
+                    second_result.message
+                )
+                .unwrap_or_else(|e| {
+                    io_error(&format!("Failed to write to output: {}", e));
+                });
+                issues_found = true;
+            }
+            continue;
+        }
+
+        if !options.types.is_empty() {
+            // Sweep mode: check the expression under every listed type and report the verdict.
+            let results: Vec<(IntegerType, CheckResult)> = options
+                .types
+                .iter()
+                .map(|integer_type| (*integer_type, check_arithmetic_safety(&expression, integer_type, &variables, options.iterations, verbose)))
+                .collect();
+            if write_type_sweep(writer.as_mut(), &options, line_num + 1, expression.trim(), &results, &mut table) {
+                issues_found = true;
+            }
+            continue;
+        }
+
+        let result = if options.parse_only {
+            check_syntax(&expression, &variables)
+        } else {
+            check_arithmetic_safety(&expression, &options.integer_type, &variables, options.iterations, verbose)
+        };
+        let column = expression_column(&expression, &options);
+        write_result(writer.as_mut(), &options, line_num + 1, column, expression.trim(), &result, &mut table);
+        // Under --parse-only every non-OK result is a parse error, including malformed lines.
+        if result.kind.is_issue() || (options.parse_only && result.kind != FindingKind::Ok) {
             issues_found = true;
         }
+        record_stats(&mut stats, &result);
+    }
+
+    if options.format == OutputFormat::Table {
+        write_table(writer.as_mut(), &options, &table);
+    }
+
+    if options.stats {
+        print_stats(&stats);
+    }
+
+    if TEE_FAILED.load(Ordering::Relaxed) {
+        io_error("--tee: one output destination failed, so its output is incomplete (see warning above).");
     }
 
     if issues_found {
@@ -286,4 +1353,115 @@ fn main() {
         info("Arithmetic safety checks complete. No issues found.", verbose);
         process::exit(0);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks `expression` as `int_type` with no declared variables and no iterations.
+    fn kind(expression: &str, int_type: IntegerType) -> FindingKind {
+        check_arithmetic_safety(expression, &int_type, &range::Variables::new(), None, false).kind
+    }
+
+    /// The `--iterations` message for `op1 operator op2` as i8, found by applying the operation
+    /// one iteration at a time.
+    fn iterate_i8(op1: i8, operator: &str, op2: i8, iterations: u64) -> String {
+        let mut acc = op1;
+        for iteration in 1..=iterations {
+            let next = match operator {
+                "+" => acc.checked_add(op2),
+                "-" => acc.checked_sub(op2),
+                "*" => acc.checked_mul(op2),
+                _ => acc.checked_div(op2),
+            };
+            match next {
+                Some(next) => acc = next,
+                None => {
+                    let label = match operator {
+                        "/" => "Division overflow",
+                        "+" if op2 < 0 => "Underflow",
+                        "-" if op2 >= 0 => "Underflow",
+                        "*" if (acc < 0) != (op2 < 0) => "Underflow",
+                        _ => "Overflow",
+                    };
+                    return format!("WARNING: {} detected at iteration {} of {} for {} {} {} as i8 (accumulator was {})", label, iteration, iterations, op1, operator, op2, acc);
+                }
+            }
+        }
+        format!("OK: {} {} {} repeated {} times = {}", op1, operator, op2, iterations, acc)
+    }
+
+    #[test]
+    fn iterations_match_a_step_by_step_loop() {
+        let variables = range::Variables::new();
+        for operator in ["+", "-", "*", "/"] {
+            for op1 in (-128..=127).step_by(5).chain([-128, -1, 0, 1, 127]) {
+                for op2 in [-128, -100, -7, -2, -1, 0, 1, 2, 3, 64, 127] {
+                    if operator == "/" && op2 == 0 {
+                        continue;
+                    }
+                    for iterations in [1, 2, 3, 4, 5, 77, 128, 255, 256, 257, 300] {
+                        let expression = format!("{} {} {}", op1, operator, op2);
+                        let result = check_arithmetic_safety(&expression, &IntegerType::I8, &variables, Some(iterations), false);
+                        assert_eq!(result.message, iterate_i8(op1, operator, op2, iterations), "{} x{}", expression, iterations);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn huge_iteration_counts_finish_at_once() {
+        let variables = range::Variables::new();
+        let check = |expression: &str, int_type: IntegerType| {
+            check_arithmetic_safety(expression, &int_type, &variables, Some(u64::MAX), false).message
+        };
+        assert_eq!(check("0 + 1", IntegerType::U128), format!("OK: 0 + 1 repeated {} times = {}", u64::MAX, u64::MAX));
+        assert_eq!(
+            check("0 - 1", IntegerType::I64),
+            format!("WARNING: Underflow detected at iteration {} of {} for 0 - 1 as i64 (accumulator was {})", 1u128 << 63 | 1, u64::MAX, i64::MIN)
+        );
+        assert_eq!(check("7 * -1", IntegerType::I32), format!("OK: 7 * -1 repeated {} times = -7", u64::MAX));
+        assert_eq!(check("-7 / -1", IntegerType::I16), format!("OK: -7 / -1 repeated {} times = 7", u64::MAX));
+        assert_eq!(check("5 - 0", IntegerType::U8), format!("OK: 5 - 0 repeated {} times = 5", u64::MAX));
+    }
+
+    #[test]
+    fn signed_edges_as_i8() {
+        let cases = [
+            ("127 + 1", FindingKind::Overflow),
+            ("-128 + -1", FindingKind::Underflow),
+            ("-128 - 1", FindingKind::Underflow),
+            ("127 - -1", FindingKind::Overflow),
+            ("-128 * -1", FindingKind::Overflow),
+            ("-64 * 3", FindingKind::Underflow),
+            ("64 * 2", FindingKind::Overflow),
+            ("-128 / -1", FindingKind::DivisionOverflow),
+            ("-128 / 1", FindingKind::Ok),
+            ("127 / -1", FindingKind::Ok),
+            ("-128 + 0", FindingKind::Ok),
+        ];
+        for (expression, expected) in cases {
+            assert_eq!(kind(expression, IntegerType::I8), expected, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn min_divided_by_minus_one_is_division_overflow() {
+        let cases = [
+            (IntegerType::I8, i8::MIN.to_string()),
+            (IntegerType::I16, i16::MIN.to_string()),
+            (IntegerType::I32, i32::MIN.to_string()),
+            (IntegerType::I64, i64::MIN.to_string()),
+            (IntegerType::I128, i128::MIN.to_string()),
+        ];
+        for (int_type, min) in cases {
+            let expression = format!("{} / -1", min);
+            assert_eq!(kind(&expression, int_type), FindingKind::DivisionOverflow, "{} as {}", expression, int_type.name());
+            let expression = format!("{} - 1", min);
+            assert_eq!(kind(&expression, int_type), FindingKind::Underflow, "{} as {}", expression, int_type.name());
+            let expression = format!("{} / 1", min);
+            assert_eq!(kind(&expression, int_type), FindingKind::Ok, "{} as {}", expression, int_type.name());
+        }
+    }
+}
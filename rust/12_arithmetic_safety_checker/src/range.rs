@@ -0,0 +1,220 @@
+// range.rs
+//
+// Range analysis for expressions over declared variables.
+//
+// A declaration line such as `x: 0..200` gives a variable the range of values it may hold, using
+// Rust range syntax (`0..200` excludes 200, `0..=200` includes it). An expression with a declared
+// variable as an operand, such as `x + y` or `x as u8`, is then checked by worst-case interval
+// arithmetic: every operand is an interval (a literal is a one-value interval), the interval of
+// possible results is computed, and it is compared with the bounds of the simulated type. This
+// finds overflows that are possible for some inputs, not just overflows of concrete values.
+// Bounds are computed in i128, so declared ranges must fit i128 and `u128` is only checked up
+// to `i128::MAX`.
+
+use std::collections::HashMap;
+
+use crate::{CheckResult, FindingKind, IntegerType};
+
+/// An inclusive interval of possible values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub low: i128,
+    pub high: i128,
+}
+
+impl Interval {
+    fn describe(&self) -> String {
+        format!("{}..={}", self.low, self.high)
+    }
+}
+
+/// Declared variables, by name.
+pub type Variables = HashMap<String, Interval>;
+
+/// Returns true if `name` is a variable name: a letter or '_' followed by letters, digits or '_'.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Recognizes a declaration line `NAME: LOW..HIGH` or `NAME: LOW..=HIGH`. Returns `None` if the
+/// line is not a declaration (integer type prefixes such as `u8:` are expressions), or the
+/// declared variable, or an error message for a malformed range.
+pub fn parse_declaration(line: &str) -> Option<Result<(String, Interval), String>> {
+    let (name, range) = line.split_once(':')?;
+    let (name, range) = (name.trim(), range.trim());
+    if !is_identifier(name) || name.parse::<IntegerType>().is_ok() || !range.contains("..") {
+        return None;
+    }
+    let (low, high, inclusive) = match range.split_once("..=") {
+        Some((low, high)) => (low, high, true),
+        None => {
+            let (low, high) = range.split_once("..")?;
+            (low, high, false)
+        }
+    };
+    let parse = |bound: &str| bound.trim().parse::<i128>().ok();
+    let interval = match (parse(low), parse(high)) {
+        (Some(low), Some(high)) if inclusive && low <= high => Interval { low, high },
+        (Some(low), Some(high)) if !inclusive && low < high => Interval { low, high: high - 1 },
+        _ => {
+            return Some(Err(format!(
+                "ERROR: Malformed range declaration (expected 'NAME: LOW..HIGH' or 'NAME: LOW..=HIGH' with LOW below HIGH): {}",
+                line.trim()
+            )))
+        }
+    };
+    Some(Ok((name.to_string(), interval)))
+}
+
+/// Returns true if either operand of `VALUE OPERATOR VALUE` (or the value of a cast) is a
+/// declared variable, so the expression needs range analysis.
+pub fn uses_variables(parts: &[&str], variables: &Variables) -> bool {
+    let operands: &[&str] = if parts[1] == "as" { &parts[..1] } else { &[parts[0], parts[2]] };
+    operands.iter().any(|operand| variables.contains_key(*operand))
+}
+
+/// The smallest and largest values of a type. `u128::MAX` does not fit i128, so `u128` is
+/// capped at `i128::MAX`.
+fn type_bounds(int_type: IntegerType) -> Interval {
+    let (low, high) = match int_type {
+        IntegerType::U8 => (0, u8::MAX as i128),
+        IntegerType::I8 => (i8::MIN as i128, i8::MAX as i128),
+        IntegerType::U16 => (0, u16::MAX as i128),
+        IntegerType::I16 => (i16::MIN as i128, i16::MAX as i128),
+        IntegerType::U32 => (0, u32::MAX as i128),
+        IntegerType::I32 => (i32::MIN as i128, i32::MAX as i128),
+        IntegerType::U64 => (0, u64::MAX as i128),
+        IntegerType::I64 => (i64::MIN as i128, i64::MAX as i128),
+        IntegerType::U128 => (0, i128::MAX),
+        IntegerType::I128 => (i128::MIN, i128::MAX),
+    };
+    Interval { low, high }
+}
+
+/// The interval of an operand: a declared variable's range, or a literal as a one-value range.
+fn operand_interval(operand: &str, variables: &Variables) -> Option<Interval> {
+    match variables.get(operand) {
+        Some(interval) => Some(*interval),
+        None => operand.parse::<i128>().ok().map(|value| Interval { low: value, high: value }),
+    }
+}
+
+/// The smallest and largest of the corner results `f(a, b)` for `a` in `x` and `b` in `y`, or
+/// `None` if a corner does not fit i128.
+fn corners(x: Interval, y: Interval, f: impl Fn(i128, i128) -> Option<i128>) -> Option<Interval> {
+    let values = [f(x.low, y.low)?, f(x.low, y.high)?, f(x.high, y.low)?, f(x.high, y.high)?];
+    Some(Interval { low: *values.iter().min()?, high: *values.iter().max()? })
+}
+
+/// Checks `OPERAND OPERATOR OPERAND` or `OPERAND as TYPE` by interval arithmetic and reports the
+/// worst-case result range and whether it can leave the bounds of `int_type`.
+pub fn check_range(parts: &[&str], int_type: IntegerType, variables: &Variables, expression: &str) -> CheckResult {
+    let (op1_str, operator, op2_str) = (parts[0], parts[1], parts[2]);
+    let bounds = type_bounds(int_type);
+    let type_name = int_type.name();
+
+    let operands: &[&str] = if operator == "as" { &[op1_str] } else { &[op1_str, op2_str] };
+    let mut intervals = Vec::new();
+    for operand in operands {
+        let interval = match operand_interval(operand, variables) {
+            Some(interval) => interval,
+            None => {
+                return CheckResult::new(
+                    FindingKind::InvalidOperand,
+                    Some(operator),
+                    format!("ERROR: Invalid operand '{}' (not a literal or declared variable) in expression: {}", operand, expression),
+                )
+            }
+        };
+        if interval.low < bounds.low || interval.high > bounds.high {
+            return CheckResult::new(
+                FindingKind::InvalidOperand,
+                Some(operator),
+                format!("ERROR: Operand '{}' ({}) does not fit {} in expression: {}", operand, interval.describe(), type_name, expression),
+            );
+        }
+        intervals.push(interval);
+    }
+
+    if operator == "as" {
+        let target = match op2_str.parse::<IntegerType>() {
+            Ok(target) => type_bounds(target),
+            Err(_) => {
+                return CheckResult::new(
+                    FindingKind::InvalidCastTarget,
+                    Some(operator),
+                    format!("ERROR: Invalid cast target '{}' in expression: {}", op2_str, expression),
+                )
+            }
+        };
+        let value = intervals[0];
+        let range = value.describe();
+        return if value.low < target.low || value.high > target.high {
+            CheckResult::new(
+                FindingKind::LossyCast,
+                Some(operator),
+                format!("WARNING: Possibly lossy cast: {} as {} with {} in {} does not always fit {}", op1_str, op2_str, op1_str, range, op2_str),
+            )
+            .with_value(range)
+        } else {
+            CheckResult::new(FindingKind::Ok, Some(operator), format!("OK: {} as {} always fits: {} in {}", op1_str, op2_str, op1_str, range))
+                .with_value(range)
+        };
+    }
+
+    let (x, y) = (intervals[0], intervals[1]);
+    let result = match operator {
+        "+" => corners(x, y, i128::checked_add),
+        "-" => corners(x, y, i128::checked_sub),
+        "*" => corners(x, y, i128::checked_mul),
+        "/" => {
+            if y.low <= 0 && y.high >= 0 {
+                return CheckResult::new(
+                    FindingKind::DivisionByZero,
+                    Some(operator),
+                    format!("ERROR: Possible division by zero: divisor {} ranges over {} in expression: {}", op2_str, y.describe(), expression),
+                );
+            }
+            // With a divisor range of one sign, the quotient is monotonic in each operand.
+            corners(x, y, i128::checked_div)
+        }
+        _ => {
+            return CheckResult::new(
+                FindingKind::UnsupportedOperator,
+                Some(operator),
+                format!("ERROR: Unsupported operator '{}' in expression: {}", operator, expression),
+            )
+        }
+    };
+
+    // A result that does not even fit i128 certainly leaves the type's bounds.
+    let Some(result) = result else {
+        return CheckResult::new(
+            FindingKind::Overflow,
+            Some(operator),
+            format!("WARNING: Possible overflow for {} as {}: worst case exceeds the i128 range used for analysis", expression, type_name),
+        );
+    };
+    let range = result.describe();
+    if result.high > bounds.high {
+        let kind = if operator == "/" { FindingKind::DivisionOverflow } else { FindingKind::Overflow };
+        let label = if operator == "/" { "Possible division overflow" } else { "Possible overflow" };
+        CheckResult::new(
+            kind,
+            Some(operator),
+            format!("WARNING: {} for {} as {}: worst case {} exceeds {}::MAX ({}), result range {}", label, expression, type_name, result.high, type_name, bounds.high, range),
+        )
+        .with_value(range)
+    } else if result.low < bounds.low {
+        CheckResult::new(
+            FindingKind::Underflow,
+            Some(operator),
+            format!("WARNING: Possible underflow for {} as {}: worst case {} is below {}::MIN ({}), result range {}", expression, type_name, result.low, type_name, bounds.low, range),
+        )
+        .with_value(range)
+    } else {
+        CheckResult::new(FindingKind::Ok, Some(operator), format!("OK: {} as {} cannot overflow: result range {}", expression, type_name, range))
+            .with_value(range)
+    }
+}
@@ -0,0 +1,166 @@
+// source.rs
+//
+// Rust source scanning used by `--source`.
+//
+// Each line is tokenized on its own, with comments dropped and string literal contents skipped.
+// Constant arithmetic on integer literals is then picked out: isolated binary expressions such as
+// `250u8 + 10` and casts such as `300 as u8`. A literal type suffix (`250u8`, `250_u8`) becomes
+// the checker's `TYPE:` prefix. Expressions next to other arithmetic operators (`1 + 2 * 3`) are
+// skipped instead of guessing at precedence, and operands that are not literals are never
+// evaluated. This is a line-based heuristic, not a Rust parser: multi-line strings and block
+// comments are not tracked across lines.
+// Standard library only, in keeping with the rest of the tool.
+
+use crate::{IntegerType, SUPPORTED_OPERATORS};
+
+/// A checkable expression found in a line of Rust source.
+pub struct SourceExpression {
+    /// Character index of the expression's first character within the line.
+    pub start: usize,
+    /// The expression in checker syntax, e.g. `u8: 250 + 10`.
+    pub expression: String,
+}
+
+/// A token of a source line.
+#[derive(Debug)]
+enum Token {
+    /// An integer literal: its digits (with any sign, without `_`) and optional type suffix.
+    Int(String, Option<IntegerType>),
+    /// One of the supported binary operators.
+    Op(char),
+    /// An identifier, keyword, or a number that is not a plain decimal integer (e.g. `1.5`, `0xFF`).
+    Word(String),
+    /// Any other character, including the contents of string literals.
+    Other(char),
+}
+
+/// Splits a numeric literal such as `250_u8` into its digits and type suffix. Returns `None`
+/// for anything that is not a decimal integer literal.
+fn parse_int_literal(text: &str) -> Option<(String, Option<IntegerType>)> {
+    let text: String = text.chars().filter(|&c| c != '_').collect();
+    let split = text.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(text.len());
+    let (digits, suffix) = text.split_at(split);
+    let unsigned = digits.strip_prefix('-').unwrap_or(digits);
+    if unsigned.is_empty() || !unsigned.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let suffix = match suffix {
+        "" => None,
+        suffix => Some(suffix.parse::<IntegerType>().ok()?),
+    };
+    Some((digits.to_string(), suffix))
+}
+
+/// Tokenizes one line of Rust source, returning each token with its character index.
+fn tokenize(line: &str) -> Vec<(usize, Token)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && matches!(next, Some('/') | Some('*')) {
+            break; // Line or block comment: nothing after it on this line is checked.
+        } else if c == '"' {
+            // Skip the string literal, honoring backslash escapes.
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i += 1;
+            tokens.push((start, Token::Other('"')));
+        } else if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit()) && !follows_value(&tokens)) {
+            // A '-' directly before a digit is a unary minus unless it follows a value.
+            let start = i;
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric()
+                    || chars[i] == '_'
+                    || (chars[i] == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())))
+            {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let token = match parse_int_literal(&text) {
+                Some((digits, suffix)) => Token::Int(digits, suffix),
+                None => Token::Word(text),
+            };
+            tokens.push((start, token));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Word(chars[start..i].iter().collect())));
+        } else if SUPPORTED_OPERATORS.iter().any(|op| op.starts_with(c)) && next != Some('=') {
+            tokens.push((i, Token::Op(c)));
+            i += 1;
+        } else {
+            // Includes compound assignment operators such as `+=`, which are not expressions.
+            tokens.push((i, Token::Other(c)));
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// Returns true if the last token ends a value, so a following '-' is a binary minus.
+fn follows_value(tokens: &[(usize, Token)]) -> bool {
+    matches!(tokens.last(), Some((_, Token::Int(..) | Token::Word(_) | Token::Other(')' | ']'))))
+}
+
+/// Returns true if a token would bind to a neighbouring literal, making the expression part of
+/// a larger one (another operator, a cast, or a field or method access).
+fn binds_to_neighbour(token: Option<&(usize, Token)>) -> bool {
+    match token {
+        Some((_, Token::Op(_) | Token::Other('.'))) => true,
+        Some((_, Token::Word(word))) => word == "as",
+        _ => false,
+    }
+}
+
+/// Finds the constant integer expressions in one line of Rust source.
+pub fn find_expressions(line: &str) -> Vec<SourceExpression> {
+    let tokens = tokenize(line);
+    let mut found = Vec::new();
+    for i in 0..tokens.len() {
+        let before = i.checked_sub(1).and_then(|j| tokens.get(j));
+        match (&tokens[i], tokens.get(i + 1), tokens.get(i + 2)) {
+            ((start, Token::Int(a, a_type)), Some((_, Token::Op(op))), Some((_, Token::Int(b, b_type))))
+                if !binds_to_neighbour(before) && !binds_to_neighbour(tokens.get(i + 3)) =>
+            {
+                // Mismatched suffixes (`1u8 + 1u16`) do not compile, so there is nothing to check.
+                if a_type.is_some() && b_type.is_some() && a_type != b_type {
+                    continue;
+                }
+                let prefix = a_type.or(*b_type).map(|t| format!("{}: ", t.name())).unwrap_or_default();
+                found.push(SourceExpression { start: *start, expression: format!("{}{} {} {}", prefix, a, op, b) });
+            }
+            ((start, Token::Int(value, value_type)), Some((_, Token::Word(keyword))), Some((_, Token::Word(target))))
+                if keyword == "as"
+                    && target.parse::<IntegerType>().is_ok()
+                    && !matches!(before, Some((_, Token::Other('.')))) =>
+            {
+                let prefix = value_type.map(|t| format!("{}: ", t.name())).unwrap_or_default();
+                found.push(SourceExpression { start: *start, expression: format!("{}{} as {}", prefix, value, target) });
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Returns the 1-based display column of character index `index` in `line`, expanding tabs to
+/// the next multiple of `tab_width` as editors do.
+pub fn display_column(line: &str, index: usize, tab_width: usize) -> usize {
+    line.chars().take(index).fold(0, |column, c| {
+        if c == '\t' {
+            column + tab_width - column % tab_width
+        } else {
+            column + 1
+        }
+    }) + 1
+}
@@ -0,0 +1,231 @@
+# scripts/audit_all.py
+#
+# "Audit all" sweep over a directory using the Rust tools:
+#   * config files (.toml, .conf, .cfg, .ini, .env) -> 09 safe_config_parser_linter (-f json)
+#   * log files (*.log, *.log.N)                      -> 11 memory_efficient_log_search (--format ndjson)
+#   * binary files (NUL byte in the first 8 KiB)       -> 10 binary_string_extractor (--format csv)
+# Each tool is built with cargo and run sequentially through its machine-readable output
+# format, and the results are merged into a single JSON report. The tools are run with
+# --no-defaults, so options in a user's defaults file cannot change the output being parsed.
+import argparse
+import csv
+import io
+import json
+import os
+import subprocess
+import sys
+
+CONFIG_EXTENSIONS = ('.toml', '.conf', '.cfg', '.ini', '.env')
+SKIPPED_DIRS = ('target', '.git')
+BINARY_SNIFF_BYTES = 8192
+
+# Built-in IOC patterns, matched case-insensitively as substrings (the log searcher's default).
+DEFAULT_IOC_PATTERNS = [
+    'Failed password',
+    'Accepted password for root',
+    '/etc/shadow',
+    'powershell -enc',
+    'nc -e',
+    '/bin/sh -i',
+    'wget http',
+    'curl http',
+    'base64 -d',
+]
+
+RUST_TOOLS = {
+    'linter': ('09_safe_config_parser_linter', 'safe_config_parser_linter'),
+    'extractor': ('10_binary_string_extractor', 'binary_string_extractor'),
+    'log_search': ('11_memory_efficient_log_search', 'memory_efficient_log_search'),
+}
+
+def info(message, verbose):
+    if verbose:
+        print(f"[INFO] {message}", file=sys.stderr)
+
+def warn(message):
+    print(f"[WARNING] {message}", file=sys.stderr)
+
+def fatal_error(message):
+    print(f"[ERROR] {message}", file=sys.stderr)
+    sys.exit(1)
+
+def build_tools(rust_dir, verbose):
+    """Builds each tool in release mode and returns a map of tool name to binary path."""
+    binaries = {}
+    for name, (crate_dir, binary) in RUST_TOOLS.items():
+        crate_path = os.path.join(rust_dir, crate_dir)
+        info(f"Building {crate_dir}...", verbose)
+        result = subprocess.run(['cargo', 'build', '--release', '--quiet'], cwd=crate_path,
+                                capture_output=True, text=True)
+        if result.returncode != 0:
+            fatal_error(f"Failed to build {crate_dir}:\n{result.stderr}")
+        binaries[name] = os.path.join(crate_path, 'target', 'release', binary)
+    return binaries
+
+def is_log_file(name):
+    return name.endswith('.log') or '.log.' in name
+
+def is_binary_file(path):
+    try:
+        with open(path, 'rb') as f:
+            return b'\0' in f.read(BINARY_SNIFF_BYTES)
+    except OSError:
+        return False
+
+def classify_files(root):
+    """Walks `root` and sorts regular files into config, log and binary lists."""
+    configs, logs, binaries, skipped = [], [], [], 0
+    for dirpath, dirnames, filenames in os.walk(root):
+        dirnames[:] = sorted(d for d in dirnames if d not in SKIPPED_DIRS)
+        for name in sorted(filenames):
+            path = os.path.join(dirpath, name)
+            if not os.path.isfile(path):
+                continue
+            if name.lower().endswith(CONFIG_EXTENSIONS):
+                configs.append(path)
+            elif is_log_file(name):
+                logs.append(path)
+            elif is_binary_file(path):
+                binaries.append(path)
+            else:
+                skipped += 1
+    return configs, logs, binaries, skipped
+
+def run_tool(command):
+    result = subprocess.run(command, capture_output=True, text=True, errors='replace')
+    return result.stdout, result.stderr.strip(), result.returncode
+
+def audit_configs(linter, configs, schema, errors, verbose):
+    """Lints each config file, against `schema` or its own inline `#@rule` annotations."""
+    results = []
+    for path in configs:
+        info(f"Linting {path}", verbose)
+        command = [linter, '--no-defaults', '-c', path, '-f', 'json']
+        command += ['-s', schema] if schema else ['--inline-schema']
+        stdout, stderr, _ = run_tool(command)
+        # The linter exits 1 both for findings and for fatal errors (4 for I/O errors); only errors
+        # lack a JSON report.
+        try:
+            findings = json.loads(stdout)
+        except json.JSONDecodeError:
+            errors.append({'tool': 'linter', 'file': path, 'message': stderr})
+            continue
+        results.append({'file': path, 'findings': findings})
+    return results
+
+def audit_logs(log_search, logs, patterns, errors, verbose):
+    """Searches all log files for each IOC pattern and returns one hit per matching line."""
+    hits = []
+    if not logs:
+        return hits
+    inputs = [arg for path in logs for arg in ('-i', path)]
+    for pattern in patterns:
+        info(f"Searching {len(logs)} log file(s) for '{pattern}'", verbose)
+        stdout, stderr, exit_code = run_tool([log_search, '--no-defaults', *inputs, '-p', pattern, '--format', 'ndjson'])
+        if exit_code != 0:
+            errors.append({'tool': 'log_search', 'pattern': pattern, 'message': stderr})
+            continue
+        for line in stdout.splitlines():
+            record = json.loads(line)
+            record['pattern'] = pattern
+            hits.append(record)
+    return hits
+
+def audit_binaries(extractor, binaries, patterns, errors, verbose):
+    """Extracts strings from each binary and keeps those containing an IOC pattern."""
+    results = []
+    lowered_patterns = [p.lower() for p in patterns]
+    for path in binaries:
+        info(f"Extracting strings from {path}", verbose)
+        stdout, stderr, exit_code = run_tool([extractor, '--no-defaults', '-i', path, '--format', 'csv'])
+        if exit_code != 0:
+            errors.append({'tool': 'extractor', 'file': path, 'message': stderr})
+            continue
+        rows = list(csv.DictReader(io.StringIO(stdout)))
+        matches = []
+        for row in rows:
+            value = row['value'].lower()
+            for pattern, lowered in zip(patterns, lowered_patterns):
+                if lowered in value:
+                    matches.append({'offset': int(row['offset']), 'value': row['value'], 'pattern': pattern})
+                    break
+        results.append({'file': path, 'total_strings': len(rows), 'ioc_strings': matches})
+    return results
+
+def load_patterns(path):
+    try:
+        with open(path, encoding='utf-8') as f:
+            patterns = [line.strip() for line in f if line.strip() and not line.startswith('#')]
+    except OSError as e:
+        fatal_error(f"Failed to read IOC file {path}: {e}")
+    if not patterns:
+        fatal_error(f"IOC file {path} contains no patterns.")
+    return patterns
+
+def main():
+    parser = argparse.ArgumentParser(
+        description="Run the config linter, string extractor and log searcher over a directory "
+                    "and write one consolidated JSON report.")
+    parser.add_argument('directory', help="Directory to audit (searched recursively).")
+    parser.add_argument('-s', '--schema', help="Schema for the config linter. Without it, each config "
+                                               "is linted against its own '#@rule' annotations.")
+    parser.add_argument('--ioc-file', help="File with one IOC pattern per line ('#' comments allowed). "
+                                           "Replaces the built-in patterns.")
+    parser.add_argument('-o', '--output', help="Write the report to FILE instead of stdout.")
+    parser.add_argument('-v', '--verbose', action='store_true', help="Enable verbose output.")
+    args = parser.parse_args()
+
+    if not os.path.isdir(args.directory):
+        fatal_error(f"Not a directory: {args.directory}")
+    if args.schema and not os.path.isfile(args.schema):
+        fatal_error(f"Schema file not found: {args.schema}")
+    patterns = load_patterns(args.ioc_file) if args.ioc_file else DEFAULT_IOC_PATTERNS
+
+    rust_dir = os.path.join(os.path.dirname(os.path.abspath(__file__)), '..', 'rust')
+    tools = build_tools(rust_dir, args.verbose)
+
+    configs, logs, binaries, skipped = classify_files(args.directory)
+    info(f"Found {len(configs)} config, {len(logs)} log and {len(binaries)} binary file(s); "
+         f"{skipped} other file(s) skipped.", args.verbose)
+
+    errors = []
+    config_results = audit_configs(tools['linter'], configs, args.schema, errors, args.verbose)
+    log_hits = audit_logs(tools['log_search'], logs, patterns, errors, args.verbose)
+    binary_results = audit_binaries(tools['extractor'], binaries, patterns, errors, args.verbose)
+    for error in errors:
+        warn(f"{error['tool']} failed: {error['message']}")
+
+    finding_count = sum(len(r['findings']) for r in config_results)
+    ioc_string_count = sum(len(r['ioc_strings']) for r in binary_results)
+    report = {
+        'directory': args.directory,
+        'summary': {
+            'config_files': len(configs),
+            'config_findings': finding_count,
+            'log_files': len(logs),
+            'log_ioc_hits': len(log_hits),
+            'binary_files': len(binaries),
+            'binary_ioc_strings': ioc_string_count,
+            'skipped_files': skipped,
+            'errors': len(errors),
+        },
+        'configs': config_results,
+        'logs': log_hits,
+        'binaries': binary_results,
+        'errors': errors,
+    }
+
+    text = json.dumps(report, indent=2)
+    if args.output:
+        with open(args.output, 'w', encoding='utf-8') as f:
+            f.write(text + '\n')
+        info(f"Report written to {args.output}", args.verbose)
+    else:
+        print(text)
+
+    # Non-zero exit when anything needs attention, so the sweep can gate CI.
+    if finding_count or log_hits or ioc_string_count or errors:
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()